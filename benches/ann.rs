@@ -0,0 +1,94 @@
+//! Vector search: HNSW graph vs. the exact scan
+//!
+//! Run with `cargo bench --bench ann`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use elysium_mcp::search::chunker::Chunk;
+use elysium_mcp::search::filter::SearchFilter;
+use elysium_mcp::search::vectordb::{NoteRecord, VectorDB};
+
+const NOTES: usize = 5_000;
+const CHUNKS_PER_NOTE: usize = 3;
+const DIMENSION: usize = 128;
+
+/// Deterministic pseudo-random vectors (xorshift)
+fn vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut state = seed;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+    };
+    (0..count)
+        .map(|_| (0..DIMENSION).map(|_| next()).collect())
+        .collect()
+}
+
+fn populated_db() -> VectorDB {
+    let db = VectorDB::open_in_memory().unwrap();
+    let mut embeddings = vectors(NOTES * (CHUNKS_PER_NOTE + 1), 1).into_iter();
+
+    for i in 0..NOTES {
+        let note = NoteRecord {
+            id: format!("note-{}", i),
+            path: format!("/vault/Notes/note-{}.md", i),
+            title: format!("note-{}", i),
+            gist: Some("gist".to_string()),
+            note_type: None,
+            status: None,
+            area: None,
+            tags: Vec::new(),
+            mtime: 0,
+        };
+        let gist = embeddings.next().unwrap();
+        let chunks: Vec<(Chunk, Vec<f32>)> = (0..CHUNKS_PER_NOTE)
+            .map(|c| {
+                let chunk = Chunk {
+                    heading_path: Vec::new(),
+                    start: c * 100,
+                    end: c * 100 + 100,
+                    text: "passage".to_string(),
+                };
+                (chunk, embeddings.next().unwrap())
+            })
+            .collect();
+        db.upsert_note(&note, "", "hash", Some(&gist), &chunks)
+            .unwrap();
+    }
+
+    db
+}
+
+fn bench_search(c: &mut Criterion) {
+    let db = populated_db();
+    db.rebuild_ann().unwrap();
+    let queries = vectors(32, 2);
+    let filter = SearchFilter::default();
+
+    let mut group = c.benchmark_group(format!(
+        "vector_search_{}x{}",
+        NOTES * (CHUNKS_PER_NOTE + 1),
+        DIMENSION
+    ));
+    group.sample_size(20);
+    group.bench_function("exact", |b| {
+        let mut queries = queries.iter().cycle();
+        b.iter(|| {
+            db.search_exact(black_box(queries.next().unwrap()), &filter, 10)
+                .unwrap()
+        })
+    });
+    group.bench_function("hnsw", |b| {
+        let mut queries = queries.iter().cycle();
+        b.iter(|| {
+            db.search(black_box(queries.next().unwrap()), &filter, 10)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_search);
+criterion_main!(benches);
@@ -2,8 +2,11 @@ use anyhow::Result;
 use colored::*;
 use serde::Serialize;
 
-use crate::core::note::{collect_all_notes, collect_note_names};
+use crate::core::attachment::{AttachmentIndex, ReferenceIssue, References};
+use crate::core::config::VaultSchema;
+use crate::core::note::collect_all_notes;
 use crate::core::paths::VaultPaths;
+use crate::core::wikilink::LinkResolver;
 
 #[derive(Serialize)]
 struct AuditResult {
@@ -24,20 +27,21 @@ struct CheckResult {
 }
 
 pub fn run(quick: bool, json: bool, strict: bool) -> Result<()> {
-    let paths = VaultPaths::new();
+    let paths = VaultPaths::new()?;
     let notes = collect_all_notes(&paths);
-    let note_names = collect_note_names(&paths);
+    let attachments = AttachmentIndex::for_vault(&paths);
+    let resolver = LinkResolver::from_notes(&notes).with_files(attachments.files());
 
     let mut checks = Vec::new();
 
-    let schema_result = check_schema(&notes);
+    let schema_result = check_schema(&notes, &paths.config.schema);
     checks.push(schema_result);
 
-    let wikilink_result = check_wikilinks(&notes, &note_names);
+    let wikilink_result = check_wikilinks(&notes, &resolver);
     checks.push(wikilink_result);
 
     if !quick {
-        let folder_result = check_folder_type(&notes);
+        let folder_result = check_folder_type(&notes, &paths);
         checks.push(folder_result);
 
         let gist_result = check_gist(&notes);
@@ -46,8 +50,17 @@ pub fn run(quick: bool, json: bool, strict: bool) -> Result<()> {
         let tag_result = check_tags(&notes);
         checks.push(tag_result);
 
-        let orphan_result = check_orphans(&notes, &note_names);
+        let orphan_result = check_orphans(&notes, &resolver);
         checks.push(orphan_result);
+
+        let references: Vec<References> = notes
+            .iter()
+            .map(|note| attachments.check_note(note, &resolver))
+            .collect();
+        checks.extend(check_references(&references));
+
+        let orphan_attachment_result = check_orphan_attachments(&references, &attachments);
+        checks.push(orphan_attachment_result);
     }
 
     let passed = checks.iter().filter(|c| c.status == "pass").count();
@@ -74,10 +87,10 @@ pub fn run(quick: bool, json: bool, strict: bool) -> Result<()> {
     Ok(())
 }
 
-fn check_schema(notes: &[crate::core::note::Note]) -> CheckResult {
+fn check_schema(notes: &[crate::core::note::Note], schema: &VaultSchema) -> CheckResult {
     let mut errors = 0;
     for note in notes {
-        errors += note.validate_schema().len();
+        errors += note.validate_schema(schema).len();
     }
 
     CheckResult {
@@ -89,18 +102,11 @@ fn check_schema(notes: &[crate::core::note::Note]) -> CheckResult {
     }
 }
 
-fn check_wikilinks(
-    notes: &[crate::core::note::Note],
-    note_names: &std::collections::HashSet<String>,
-) -> CheckResult {
-    let mut errors = 0;
-    for note in notes {
-        for link in note.wikilinks() {
-            if !note_names.contains(&link) {
-                errors += 1;
-            }
-        }
-    }
+fn check_wikilinks(notes: &[crate::core::note::Note], resolver: &LinkResolver) -> CheckResult {
+    let errors: usize = notes
+        .iter()
+        .map(|n| resolver.broken_links(&n.name, &n.content).len())
+        .sum();
 
     CheckResult {
         id: "wikilinks".to_string(),
@@ -111,10 +117,46 @@ fn check_wikilinks(
     }
 }
 
-fn check_folder_type(notes: &[crate::core::note::Note]) -> CheckResult {
+/// Markdown link and attachment checks, in that order
+fn check_references(references: &[References]) -> [CheckResult; 2] {
+    let mut broken_links = 0;
+    let mut missing_attachments = 0;
+    for refs in references {
+        for issue in &refs.issues {
+            match issue {
+                ReferenceIssue::BrokenMarkdownLink(_) => broken_links += 1,
+                ReferenceIssue::MissingAttachment(_) => missing_attachments += 1,
+            }
+        }
+    }
+
+    [
+        CheckResult {
+            id: "markdown_links".to_string(),
+            name: "Markdown Links".to_string(),
+            status: if broken_links == 0 { "pass" } else { "fail" }.to_string(),
+            errors: broken_links,
+            details: None,
+        },
+        CheckResult {
+            id: "attachments".to_string(),
+            name: "Attachments".to_string(),
+            status: if missing_attachments == 0 {
+                "pass"
+            } else {
+                "fail"
+            }
+            .to_string(),
+            errors: missing_attachments,
+            details: None,
+        },
+    ]
+}
+
+fn check_folder_type(notes: &[crate::core::note::Note], paths: &VaultPaths) -> CheckResult {
     let errors = notes
         .iter()
-        .filter(|n| !n.check_folder_type_match())
+        .filter(|n| !n.check_folder_type_match(paths))
         .count();
 
     CheckResult {
@@ -151,22 +193,15 @@ fn check_tags(notes: &[crate::core::note::Note]) -> CheckResult {
     }
 }
 
-fn check_orphans(
-    notes: &[crate::core::note::Note],
-    note_names: &std::collections::HashSet<String>,
-) -> CheckResult {
+fn check_orphans(notes: &[crate::core::note::Note], resolver: &LinkResolver) -> CheckResult {
     use std::collections::HashSet;
 
     let mut linked: HashSet<String> = HashSet::new();
     for note in notes {
-        for link in note.wikilinks() {
-            if note_names.contains(&link) {
-                linked.insert(link);
-            }
-        }
+        linked.extend(resolver.linked_notes(&note.name, &note.content));
     }
 
-    let orphans = note_names.difference(&linked).count();
+    let orphans = resolver.names().filter(|n| !linked.contains(*n)).count();
     let ratio = orphans as f64 / notes.len() as f64;
 
     CheckResult {
@@ -178,6 +213,27 @@ fn check_orphans(
     }
 }
 
+fn check_orphan_attachments(
+    references: &[References],
+    attachments: &AttachmentIndex,
+) -> CheckResult {
+    let total = attachments.attachments().count();
+    let orphans = attachments.orphaned(references).len();
+    let ratio = if total > 0 {
+        orphans as f64 / total as f64
+    } else {
+        0.0
+    };
+
+    CheckResult {
+        id: "orphan_attachments".to_string(),
+        name: "Orphan Attachments".to_string(),
+        status: if ratio < 0.3 { "pass" } else { "fail" }.to_string(),
+        errors: orphans,
+        details: Some(format!("{} of {} attachments unreferenced", orphans, total)),
+    }
+}
+
 fn print_report(result: &AuditResult) {
     println!("{}", "Vault Full Audit Report".bold());
     println!("{}", "=".repeat(60));
@@ -0,0 +1,120 @@
+//! Eval command - Measure search quality against golden queries
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+use std::path::Path;
+
+use crate::core::paths::VaultPaths;
+use crate::search::embedder::embedder_status;
+use crate::search::engine::{SearchEngine, SearchMode};
+use crate::search::eval::{evaluate, load_queries, Metrics};
+
+/// Run eval command
+///
+/// Scores the existing index as is; with `reindex` it is brought up to date
+/// first, so the scores reflect the vault's current notes and settings.
+pub fn run(
+    queries_path: &Path,
+    k: usize,
+    modes: Vec<SearchMode>,
+    reindex: bool,
+    json: bool,
+) -> Result<()> {
+    let paths = VaultPaths::new()?;
+    let db_path = paths.search_db();
+    let queries = load_queries(queries_path)?;
+    let modes = if modes.is_empty() {
+        vec![SearchMode::Vector, SearchMode::Keyword, SearchMode::Hybrid]
+    } else {
+        modes
+    };
+
+    let embedder = embedder_status(&paths.config.search.embedder, &paths.root);
+    if !embedder.is_ready() {
+        bail!(
+            "Embedder {} is not available; run `elysium index --status` for details",
+            embedder.id
+        );
+    }
+
+    if reindex {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    } else if !db_path.exists() {
+        bail!("No search index found; run `elysium index` first or pass --index");
+    }
+    let mut engine = SearchEngine::new(&paths.root, &db_path)?;
+    if reindex {
+        engine.index_all()?;
+    }
+    let notes = engine.get_stats()?.note_count;
+    let (embedder, _) = engine.embedder_id();
+    let reports = evaluate(&mut engine, &queries, &modes, k)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "queries": queries.len(),
+                "k": k,
+                "embedder": embedder,
+                "notes": notes,
+                "modes": reports,
+            }))?
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} {} queries, top {} results, embedder {}",
+        "→".dimmed(),
+        queries.len(),
+        k,
+        embedder.cyan()
+    );
+    println!();
+    println!(
+        "  {:<8} {:>10} {:>8} {:>10}",
+        "mode".bold(),
+        format!("recall@{}", k).bold(),
+        "MRR".bold(),
+        format!("nDCG@{}", k).bold()
+    );
+    for report in &reports {
+        let Metrics { recall, mrr, ndcg } = report.metrics;
+        println!(
+            "  {:<8} {:>10.3} {:>8.3} {:>10.3}",
+            report.mode.to_string().cyan(),
+            recall,
+            mrr,
+            ndcg
+        );
+    }
+
+    let misses: Vec<_> = reports
+        .iter()
+        .flat_map(|report| {
+            report
+                .queries
+                .iter()
+                .filter(|q| !q.missed.is_empty())
+                .map(move |q| (report.mode, q))
+        })
+        .collect();
+    if !misses.is_empty() {
+        println!();
+        println!("{}", "Missed".bold());
+        for (mode, query) in misses {
+            println!(
+                "  {} [{}] {} → {}",
+                "✗".red(),
+                mode,
+                query.query.cyan(),
+                query.missed.join(", ")
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -4,11 +4,12 @@ use std::path::Path;
 
 use anyhow::Result;
 use colored::*;
-use regex::Regex;
 use serde::Serialize;
 
-use crate::core::note::{collect_all_notes, collect_note_names};
+use crate::core::attachment::AttachmentIndex;
+use crate::core::note::{collect_all_notes, Note};
 use crate::core::paths::VaultPaths;
+use crate::core::wikilink::{LinkError, LinkResolver, Wikilink};
 
 #[derive(Serialize)]
 struct FixResult {
@@ -27,7 +28,7 @@ struct FixDetail {
 }
 
 pub fn run(wikilinks: bool, footer: bool, migrate: bool, check: bool, dry_run: bool, json: bool) -> Result<()> {
-    let paths = VaultPaths::new();
+    let paths = VaultPaths::new()?;
 
     if wikilinks {
         run_wikilinks_fix(&paths, dry_run, json)?;
@@ -224,10 +225,18 @@ fn add_footer_start(content: &str) -> String {
 }
 
 fn migrate_metadata(content: &str) -> String {
-    let metadata_re = Regex::new(r"(?s)### Metadata\n(.*?)(?=\n<!-- footer_end -->|\n##|\z)").unwrap();
-    
-    if let Some(caps) = metadata_re.captures(content) {
-        let metadata_content = caps.get(1).map_or("", |m| m.as_str());
+    const HEADER: &str = "### Metadata\n";
+
+    // The section runs until the footer end marker, the next heading or EOF
+    if let Some(start) = content.find(HEADER) {
+        let body_start = start + HEADER.len();
+        let rest = &content[body_start..];
+        let body_len = ["\n<!-- footer_end -->", "\n##"]
+            .iter()
+            .filter_map(|marker| rest.find(marker))
+            .min()
+            .unwrap_or(rest.len());
+        let metadata_content = &rest[..body_len];
         let mut yaml_lines = Vec::new();
         
         for line in metadata_content.lines() {
@@ -244,8 +253,12 @@ fn migrate_metadata(content: &str) -> String {
         if !yaml_lines.is_empty() {
             let yaml_content = yaml_lines.join("\n");
             let footer_meta = format!("<!-- footer_meta\n{}\n-->", yaml_content);
-            let new_content = metadata_re.replace(content, &format!("{}\n", footer_meta));
-            return new_content.to_string();
+            return format!(
+                "{}{}\n{}",
+                &content[..start],
+                footer_meta,
+                &rest[body_len..]
+            );
         }
     }
     
@@ -294,20 +307,14 @@ fn print_footer_report(result: &FixResult, migrate: bool) {
 
 fn run_wikilinks_fix(paths: &VaultPaths, dry_run: bool, json: bool) -> Result<()> {
     let notes = collect_all_notes(paths);
-    let note_names = collect_note_names(paths);
+    let attachments = AttachmentIndex::for_vault(paths);
+    let resolver = LinkResolver::from_notes(&notes).with_files(attachments.files());
 
-    let mut broken_links: Vec<(String, String, String)> = Vec::new();
+    let mut broken_links: Vec<(&Note, Wikilink, LinkError)> = Vec::new();
 
     for note in &notes {
-        let links = note.wikilinks();
-        for link in links {
-            if !note_names.contains(&link) {
-                broken_links.push((
-                    note.name.clone(),
-                    note.path.to_string_lossy().to_string(),
-                    link,
-                ));
-            }
+        for (link, err) in resolver.broken_links(&note.name, &note.content) {
+            broken_links.push((note, link, err));
         }
     }
 
@@ -328,32 +335,43 @@ fn run_wikilinks_fix(paths: &VaultPaths, dry_run: bool, json: bool) -> Result<()
 
     let unique_broken: HashSet<_> = broken_links
         .iter()
-        .map(|(_, _, link)| link.clone())
+        .map(|(_, link, _)| link.to_string())
         .collect();
     let mut details = Vec::new();
     let mut fixes_applied = 0;
 
-    for (note_name, note_path, link) in &broken_links {
-        let fix_description = format!("Remove [[{}]] from {}", link, note_name);
-
-        if !dry_run {
-            if let Err(e) = remove_wikilink_from_file(Path::new(note_path), link) {
-                details.push(FixDetail {
-                    file: note_name.clone(),
-                    issue: format!("Broken link: [[{}]]", link),
-                    fix: format!("Failed: {}", e),
-                    applied: false,
-                });
+    let mut failed: HashSet<&str> = HashSet::new();
+    if !dry_run {
+        let mut fixed_notes: HashSet<&str> = HashSet::new();
+        for (note, _, _) in &broken_links {
+            if !fixed_notes.insert(note.name.as_str()) {
                 continue;
             }
-            fixes_applied += 1;
+            match fix_broken_links_in_file(&note.path, &note.name, &resolver) {
+                Ok(count) => fixes_applied += count,
+                Err(_) => {
+                    failed.insert(note.name.as_str());
+                }
+            }
         }
+    }
+
+    for (note, link, err) in &broken_links {
+        let fix = match repair_link(link, err) {
+            Some(replacement) => format!("Replace {} with {} in {}", link, replacement, note.name),
+            None => format!("Unlink {} in {}", link, note.name),
+        };
+        let applied = !dry_run && !failed.contains(note.name.as_str());
 
         details.push(FixDetail {
-            file: note_name.clone(),
-            issue: format!("Broken link: [[{}]]", link),
-            fix: fix_description,
-            applied: !dry_run,
+            file: note.name.clone(),
+            issue: format!("Broken link: {} ({})", link, err),
+            fix: if !dry_run && !applied {
+                format!("Failed: {}", fix)
+            } else {
+                fix
+            },
+            applied,
         });
     }
 
@@ -373,21 +391,39 @@ fn run_wikilinks_fix(paths: &VaultPaths, dry_run: bool, json: bool) -> Result<()
     Ok(())
 }
 
-fn remove_wikilink_from_file(path: &Path, target: &str) -> Result<()> {
-    let content = fs::read_to_string(path)?;
+/// Link that keeps pointing at the target note when only its heading or block is
+/// missing; None means the link should be replaced by its display text
+fn repair_link(link: &Wikilink, err: &LinkError) -> Option<Wikilink> {
+    match err {
+        LinkError::MissingNote => None,
+        _ if link.is_self_link() => None,
+        _ => Some(Wikilink {
+            heading: None,
+            block_ref: None,
+            ..link.clone()
+        }),
+    }
+}
 
-    let pattern_simple = format!("[[{}]]", target);
-    let new_content = content.replace(&pattern_simple, target);
+/// Rewrite the broken links in the file at `path`, returning how many were fixed
+fn fix_broken_links_in_file(path: &Path, name: &str, resolver: &LinkResolver) -> Result<usize> {
+    let content = fs::read_to_string(path)?;
+    let broken = resolver.broken_links(name, &content);
 
-    let pattern_display =
-        regex::Regex::new(&format!(r"\[\[{}\|([^\]]+)\]\]", regex::escape(target)))?;
-    let new_content = pattern_display.replace_all(&new_content, "$1").to_string();
+    let mut new_content = content.clone();
+    for (link, err) in broken.iter().rev() {
+        let replacement = match repair_link(link, err) {
+            Some(repaired) => repaired.to_string(),
+            None => link.display_text(),
+        };
+        new_content.replace_range(link.span.clone(), &replacement);
+    }
 
     if new_content != content {
         fs::write(path, new_content)?;
     }
 
-    Ok(())
+    Ok(broken.len())
 }
 
 fn print_wikilink_report(result: &FixResult, unique_broken: &HashSet<String>) {
@@ -405,7 +441,7 @@ fn print_wikilink_report(result: &FixResult, unique_broken: &HashSet<String>) {
 
     println!("{}", "Unique broken targets:".cyan());
     for link in unique_broken {
-        println!("  • [[{}]]", link.red());
+        println!("  • {}", link.red());
     }
     println!();
 
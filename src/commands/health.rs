@@ -1,12 +1,14 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use anyhow::Result;
 use chrono::{Duration, Local};
 use colored::*;
 use serde::Serialize;
 
+use crate::core::config::VaultSchema;
 use crate::core::note::collect_all_notes;
 use crate::core::paths::VaultPaths;
+use crate::core::wikilink::LinkResolver;
 
 const WEIGHT_CONNECTIVITY: u32 = 25;
 const WEIGHT_TAG_HEALTH: u32 = 20;
@@ -30,7 +32,7 @@ struct CategoryScore {
 }
 
 pub fn run(details: bool, json: bool) -> Result<()> {
-    let paths = VaultPaths::new();
+    let paths = VaultPaths::new()?;
     let notes = collect_all_notes(&paths);
 
     let mut breakdown = HashMap::new();
@@ -75,7 +77,7 @@ pub fn run(details: bool, json: bool) -> Result<()> {
         },
     );
 
-    let (schema_score, schema_details) = calculate_schema_compliance(&notes);
+    let (schema_score, schema_details) = calculate_schema_compliance(&notes, &paths.config.schema);
     breakdown.insert(
         "schema_compliance".to_string(),
         CategoryScore {
@@ -122,16 +124,14 @@ pub fn run(details: bool, json: bool) -> Result<()> {
 fn calculate_connectivity(
     notes: &[crate::core::note::Note],
 ) -> (u32, HashMap<String, serde_json::Value>) {
-    let note_names: HashSet<_> = notes.iter().map(|n| n.name.clone()).collect();
+    let resolver = LinkResolver::from_notes(notes);
     let mut incoming: HashMap<String, usize> = HashMap::new();
     let mut total_outgoing = 0;
 
     for note in notes {
-        for link in note.wikilinks() {
-            if note_names.contains(&link) {
-                *incoming.entry(link).or_insert(0) += 1;
-                total_outgoing += 1;
-            }
+        for link in resolver.linked_notes(&note.name, &note.content) {
+            *incoming.entry(link).or_insert(0) += 1;
+            total_outgoing += 1;
         }
     }
 
@@ -267,12 +267,25 @@ fn calculate_maintenance(
     (score.max(0) as u32, details)
 }
 
+/// Share of notes with no schema violations under the vault schema
+///
+/// A note counts as valid only if it passes every check `validate` runs:
+/// required fields, allowed values and tag rules. Notes without a gist are
+/// reported separately in `missing_gist`.
 fn calculate_schema_compliance(
     notes: &[crate::core::note::Note],
+    schema: &VaultSchema,
 ) -> (u32, HashMap<String, serde_json::Value>) {
-    let valid = notes.iter().filter(|n| n.gist().is_some()).count();
-    let missing_gist = notes.len() - valid;
-    let ratio = valid as f64 / notes.len() as f64;
+    let valid = notes
+        .iter()
+        .filter(|n| n.validate_schema(schema).is_empty())
+        .count();
+    let missing_gist = notes.iter().filter(|n| n.gist().is_none()).count();
+    let ratio = if notes.is_empty() {
+        0.0
+    } else {
+        valid as f64 / notes.len() as f64
+    };
 
     let mut details = HashMap::new();
     details.insert("valid_schema".to_string(), valid.into());
@@ -332,3 +345,47 @@ fn print_results(result: &HealthResult, show_details: bool) {
     println!();
     println!("{}", "=".repeat(50));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::frontmatter::Frontmatter;
+    use crate::core::note::Note;
+
+    fn note(name: &str, frontmatter: &str) -> Note {
+        let content = format!("---\n{}\n---\nBody\n", frontmatter);
+        Note {
+            path: format!("/vault/Notes/{}.md", name).into(),
+            name: name.to_string(),
+            frontmatter: Frontmatter::parse(&content).unwrap(),
+            content,
+            frontmatter_error: None,
+            modified: Local::now(),
+            created: Local::now(),
+        }
+    }
+
+    #[test]
+    fn test_schema_compliance_counts_fully_valid_notes() {
+        let notes = vec![
+            note("Valid", "type: note\nstatus: active\narea: tech\ngist: G"),
+            note(
+                "Bad Area",
+                "type: note\nstatus: active\narea: moon\ngist: G",
+            ),
+            note("No Gist", "type: note\nstatus: active\narea: tech"),
+            note(
+                "Bad Tag",
+                "type: note\nstatus: active\narea: tech\ngist: G\ntags: [Rust]",
+            ),
+        ];
+
+        let (score, details) = calculate_schema_compliance(&notes, &VaultSchema::default());
+        assert_eq!(score, 25);
+        assert_eq!(details["valid_schema"], 1);
+        assert_eq!(details["missing_gist"], 1);
+
+        let (score, _) = calculate_schema_compliance(&[], &VaultSchema::default());
+        assert_eq!(score, 0);
+    }
+}
@@ -0,0 +1,99 @@
+//! Inbox command - Capture memos and triage them into notes
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::core::inbox::{self, Inbox};
+use crate::core::paths::VaultPaths;
+use crate::core::template::NewNote;
+
+/// Append a memo to the inbox
+pub fn capture(text: &str, json: bool) -> Result<()> {
+    let paths = VaultPaths::new()?;
+    let memo = inbox::capture(&paths, text)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&memo)?);
+    } else {
+        println!(
+            "{} Captured memo {} in {}",
+            "✓".green().bold(),
+            memo.index.to_string().cyan(),
+            paths.config.layout.inbox
+        );
+    }
+    Ok(())
+}
+
+/// List the memos in the inbox with their indices and hashes
+pub fn list(json: bool) -> Result<()> {
+    let paths = VaultPaths::new()?;
+    let inbox = Inbox::load(&paths.inbox)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&inbox.memos)?);
+        return Ok(());
+    }
+
+    if inbox.memos.is_empty() {
+        println!("{} Inbox is empty", "✓".green().bold());
+        return Ok(());
+    }
+
+    println!(
+        "{} {} memos in {}",
+        "→".dimmed(),
+        inbox.memos.len(),
+        paths.config.layout.inbox
+    );
+    println!();
+    for memo in &inbox.memos {
+        let first_line = memo.text.lines().next().unwrap_or("");
+        let display = if first_line.chars().count() > 100 {
+            format!("{}...", first_line.chars().take(100).collect::<String>())
+        } else {
+            first_line.to_string()
+        };
+        match &memo.captured {
+            Some(captured) => println!(
+                "{}. {} {} {}",
+                memo.index.to_string().bold(),
+                memo.hash.dimmed(),
+                captured.dimmed(),
+                display
+            ),
+            None => println!(
+                "{}. {} {}",
+                memo.index.to_string().bold(),
+                memo.hash.dimmed(),
+                display
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Turn a memo into a note and remove it from the inbox
+pub fn promote(index: usize, hash: &str, note: NewNote, json: bool) -> Result<()> {
+    let paths = VaultPaths::new()?;
+    let path = inbox::promote(&paths, index, hash, note)?;
+    let rel = path.strip_prefix(&paths.root).unwrap_or(&path);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "promoted": index,
+                "path": rel.to_string_lossy(),
+            })
+        );
+    } else {
+        println!(
+            "{} Promoted memo {} to {}",
+            "✓".green().bold(),
+            index.to_string().cyan(),
+            rel.display()
+        );
+    }
+    Ok(())
+}
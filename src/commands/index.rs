@@ -2,60 +2,26 @@
 
 use anyhow::Result;
 use colored::Colorize;
-use std::path::PathBuf;
+use std::path::Path;
 
 use crate::core::paths::VaultPaths;
-use crate::search::engine::SearchEngine;
-
-/// Get default paths for search engine
-fn get_default_paths() -> (PathBuf, PathBuf, PathBuf) {
-    let vault_path = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let tools_path = vault_path.join(".opencode/tools");
-    let db_path = tools_path.join("data/search.db");
-    let model_path = tools_path.join("models/model.onnx");
-
-    (vault_path, db_path, model_path)
-}
+use crate::search::embedder::{embedder_status, EmbedderStatus};
+use crate::search::engine::{SearchEngine, META_DIMENSION, META_EMBEDDER};
+use crate::search::vectordb::VectorDB;
 
 /// Run index command
 pub fn run(status_only: bool, rebuild: bool, json: bool) -> Result<()> {
-    let (vault_path, db_path, model_path) = get_default_paths();
+    let paths = VaultPaths::new()?;
+    let db_path = paths.search_db();
+    let embedder = embedder_status(&paths.config.search.embedder, &paths.root);
 
     if status_only {
-        return show_status(&db_path, json);
+        return show_status(&db_path, &embedder, json);
     }
 
-    // Check if model exists
-    if !model_path.exists() {
-        if json {
-            println!(
-                "{}",
-                serde_json::json!({
-                    "error": "Model not found",
-                    "model_path": model_path.display().to_string(),
-                    "hint": "Download the ONNX model first"
-                })
-            );
-        } else {
-            eprintln!(
-                "{} Model not found at: {}",
-                "Error:".red().bold(),
-                model_path.display()
-            );
-            eprintln!();
-            eprintln!("To download the model, run:");
-            eprintln!(
-                "  {}",
-                "# Download paraphrase-multilingual-MiniLM-L12-v2".dimmed()
-            );
-            eprintln!(
-                "  curl -L -o {} \\",
-                model_path.display()
-            );
-            eprintln!(
-                "    https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2/resolve/main/onnx/model.onnx"
-            );
-        }
+    // Only file-based embedders need model files; HTP works out of the box
+    if !embedder.is_ready() {
+        report_unready(&embedder, json);
         std::process::exit(1);
     }
 
@@ -67,13 +33,17 @@ pub fn run(status_only: bool, rebuild: bool, json: bool) -> Result<()> {
     // Delete existing database if rebuild requested
     if rebuild && db_path.exists() {
         std::fs::remove_file(&db_path)?;
+        let ann_path = VectorDB::ann_path(&db_path);
+        if ann_path.exists() {
+            std::fs::remove_file(ann_path)?;
+        }
         if !json {
             println!("{} Removed existing index", "→".dimmed());
         }
     }
 
     // Initialize search engine
-    let mut engine = SearchEngine::new(&vault_path, &db_path, &model_path)?;
+    let mut engine = SearchEngine::new(&paths.root, &db_path)?;
 
     if !json {
         println!("{} Building search index...", "→".dimmed());
@@ -81,28 +51,51 @@ pub fn run(status_only: bool, rebuild: bool, json: bool) -> Result<()> {
 
     // Index all notes
     let stats = engine.index_all()?;
+    let (embedder, dimension) = engine.embedder_id();
 
     if json {
         println!(
             "{}",
             serde_json::json!({
-                "indexed": stats.indexed,
+                "indexed": stats.indexed(),
+                "added": stats.added,
+                "updated": stats.updated,
+                "removed": stats.removed,
+                "unchanged": stats.unchanged,
                 "skipped": stats.skipped,
                 "failed": stats.failed,
+                "rebuilt": stats.rebuilt,
+                "embedder": embedder,
+                "dimension": dimension,
                 "duration_ms": stats.duration_ms,
             })
         );
     } else {
+        if stats.rebuilt {
+            println!(
+                "{} Index was outdated or built with different embedder settings; rebuilt with {}",
+                "!".yellow().bold(),
+                embedder.cyan()
+            );
+        }
         println!();
         println!(
             "{} Indexed {} notes in {:.2}s",
             "✓".green().bold(),
-            stats.indexed.to_string().cyan(),
+            stats.indexed().to_string().cyan(),
             stats.duration_ms as f64 / 1000.0
         );
+        println!(
+            "  {} {} added, {} updated, {} removed, {} unchanged",
+            "→".dimmed(),
+            stats.added,
+            stats.updated,
+            stats.removed,
+            stats.unchanged
+        );
         if stats.skipped > 0 {
             println!(
-                "  {} {} notes skipped (no gist)",
+                "  {} {} notes skipped (empty or duplicate name)",
                 "→".dimmed(),
                 stats.skipped
             );
@@ -124,36 +117,81 @@ pub fn run(status_only: bool, rebuild: bool, json: bool) -> Result<()> {
     Ok(())
 }
 
+/// Explain why the configured embedder cannot be loaded
+fn report_unready(embedder: &EmbedderStatus, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "error": "Embedder not available",
+                "embedder": embedder.id,
+                "missing": embedder.missing,
+                "reason": embedder.unavailable,
+            })
+        );
+        return;
+    }
+
+    eprintln!(
+        "{} Embedder {} is not available",
+        "Error:".red().bold(),
+        embedder.id.cyan()
+    );
+    if let Some(reason) = &embedder.unavailable {
+        eprintln!("  {} {}", "→".dimmed(), reason);
+    }
+    for path in &embedder.missing {
+        eprintln!("  {} Missing: {}", "✗".red(), path.display());
+    }
+    eprintln!();
+    eprintln!(
+        "Provide the files or choose another backend under {} in the vault config.",
+        "search.embedder".cyan()
+    );
+}
+
+/// Embedder section of the status output
+fn embedder_json(embedder: &EmbedderStatus) -> serde_json::Value {
+    serde_json::json!({
+        "id": embedder.id,
+        "dimension": embedder.dimension,
+        "ready": embedder.is_ready(),
+        "missing": embedder.missing,
+    })
+}
+
 /// Show index status
-fn show_status(db_path: &PathBuf, json: bool) -> Result<()> {
+fn show_status(db_path: &Path, configured: &EmbedderStatus, json: bool) -> Result<()> {
     if !db_path.exists() {
         if json {
             println!(
                 "{}",
                 serde_json::json!({
                     "exists": false,
-                    "error": "Index not found"
+                    "error": "Index not found",
+                    "configured_embedder": embedder_json(configured),
                 })
             );
         } else {
             println!(
                 "{} Index not found. Run {} first.",
                 "!".yellow().bold(),
-                "vault index".cyan()
+                "elysium index".cyan()
             );
         }
         return Ok(());
     }
 
     // Open database and get stats
-    use crate::search::vectordb::VectorDB;
     let db = VectorDB::open(db_path)?;
     let stats = db.get_stats()?;
+    let embedder = db.get_meta(META_EMBEDDER)?;
+    let dimension = db.get_meta(META_DIMENSION)?;
+    let ann_vectors = db.ann_len()?;
+    let quantization = db.quantization();
 
     // Get file size
-    let file_size = std::fs::metadata(db_path)
-        .map(|m| m.len())
-        .unwrap_or(0);
+    let file_size = std::fs::metadata(db_path).map(|m| m.len()).unwrap_or(0);
 
     if json {
         println!(
@@ -162,7 +200,13 @@ fn show_status(db_path: &PathBuf, json: bool) -> Result<()> {
                 "exists": true,
                 "note_count": stats.note_count,
                 "embedding_count": stats.embedding_count,
+                "chunk_count": stats.chunk_count,
+                "ann_vectors": ann_vectors,
+                "quantization": quantization.as_str(),
                 "last_indexed": stats.last_indexed,
+                "embedder": embedder,
+                "dimension": dimension.and_then(|d| d.parse::<usize>().ok()),
+                "configured_embedder": embedder_json(configured),
                 "file_size_bytes": file_size,
             })
         );
@@ -175,10 +219,46 @@ fn show_status(db_path: &PathBuf, json: bool) -> Result<()> {
             stats.note_count.to_string().cyan()
         );
         println!(
-            "  {} {} embeddings",
+            "  {} {} gist embeddings, {} chunk embeddings",
             "→".dimmed(),
-            stats.embedding_count.to_string().cyan()
+            stats.embedding_count.to_string().cyan(),
+            stats.chunk_count.to_string().cyan()
         );
+        println!(
+            "  {} Quantization: {}",
+            "→".dimmed(),
+            quantization.as_str().cyan()
+        );
+        match ann_vectors {
+            Some(count) => println!(
+                "  {} ANN index: {} vectors",
+                "→".dimmed(),
+                count.to_string().cyan()
+            ),
+            None => println!("  {} Vector search: exact scan", "→".dimmed()),
+        }
+        if let (Some(embedder), Some(dimension)) = (&embedder, &dimension) {
+            println!(
+                "  {} Embedder: {} ({} dims)",
+                "→".dimmed(),
+                embedder.cyan(),
+                dimension
+            );
+        }
+        if embedder.as_deref() != Some(configured.id.as_str())
+            || dimension != Some(configured.dimension.to_string())
+        {
+            println!(
+                "  {} Vault is configured for {} ({} dims); run {} to rebuild",
+                "!".yellow().bold(),
+                configured.id.cyan(),
+                configured.dimension,
+                "elysium index".cyan()
+            );
+        }
+        if !configured.is_ready() {
+            println!("  {} Configured embedder is not available", "✗".red());
+        }
         println!(
             "  {} Size: {:.2} KB",
             "→".dimmed(),
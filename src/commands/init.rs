@@ -1,11 +1,14 @@
 use anyhow::Result;
 use colored::*;
 use std::fs;
+use std::path::Path;
 
+use crate::core::config::SubfolderPolicy;
+use crate::core::ignore::IgnoreRules;
 use crate::core::paths::VaultPaths;
 
 pub fn run(create: bool) -> Result<()> {
-    let paths = VaultPaths::new();
+    let paths = VaultPaths::new()?;
 
     println!("{}", "Second Brain Vault Structure Validator".bold());
     println!("{}", "=".repeat(50));
@@ -35,8 +38,7 @@ pub fn run(create: bool) -> Result<()> {
     println!("{}", "Checking structure violations...".cyan());
     println!();
 
-    violations += check_no_subfolders(&paths.notes)?;
-    violations += check_no_subfolders(&paths.projects)?;
+    violations += check_structure(&paths)?;
 
     println!();
     println!("{}", "Summary".bold());
@@ -84,24 +86,77 @@ pub fn run(create: bool) -> Result<()> {
     }
 }
 
-fn check_no_subfolders(path: &std::path::Path) -> Result<usize> {
-    if !path.exists() {
+/// Subfolders of Notes and Projects under the discovery subfolder policy;
+/// Archive and additional content folders may be organized freely
+fn check_structure(paths: &VaultPaths) -> Result<usize> {
+    let policy = paths.config.discovery.subfolder_policy();
+    if policy == SubfolderPolicy::Allow {
         return Ok(0);
     }
 
+    let ignore = IgnoreRules::for_vault(paths);
     let mut violations = 0;
-    for entry in fs::read_dir(path)? {
+    for dir in [&paths.notes, &paths.projects] {
+        violations += check_subfolders(paths, dir, &ignore, policy)?;
+    }
+    Ok(violations)
+}
+
+fn check_subfolders(
+    paths: &VaultPaths,
+    dir: &Path,
+    ignore: &IgnoreRules,
+    policy: SubfolderPolicy,
+) -> Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let mut violations = 0;
+    for entry in fs::read_dir(dir)? {
         let entry = entry?;
-        if entry.file_type()?.is_dir() {
+        let path = entry.path();
+        let rel = path.strip_prefix(&paths.root).unwrap_or(&path);
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !entry.file_type()?.is_dir() || hidden || ignore.is_ignored(rel, true) {
+            continue;
+        }
+
+        if policy == SubfolderPolicy::Forbid {
             violations += 1;
             println!(
                 "{} VIOLATION: Subfolder found in {} (prohibited): {}",
                 "✗".red(),
-                path.display(),
-                entry.path().display()
+                dir.display(),
+                path.display()
+            );
+        } else {
+            println!(
+                "{} Subfolder found in {}: {}",
+                "!".yellow(),
+                dir.display(),
+                path.display()
             );
         }
     }
 
     Ok(violations)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_checks_notes_and_projects() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let paths = VaultPaths::from_root(root.path().to_path_buf());
+        for dir in [&paths.notes, &paths.projects, &paths.archive] {
+            fs::create_dir_all(dir.join("Sub"))?;
+        }
+
+        // Archive subfolders are not violations
+        assert_eq!(check_structure(&paths)?, 2);
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 pub mod audit;
+pub mod eval;
 pub mod fix;
 pub mod health;
+pub mod inbox;
 pub mod index;
 pub mod init;
 pub mod related;
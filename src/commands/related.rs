@@ -5,7 +5,7 @@ use crate::core::note::collect_all_notes;
 use crate::core::paths::VaultPaths;
 
 pub fn run(note_name: &str, min_tags: Option<usize>) -> Result<()> {
-    let paths = VaultPaths::new();
+    let paths = VaultPaths::new()?;
     let notes = collect_all_notes(&paths);
 
     let target_note = notes.iter().find(|n| n.name == note_name);
@@ -41,7 +41,7 @@ pub fn run(note_name: &str, min_tags: Option<usize>) -> Result<()> {
         }
     }
 
-    related.sort_by(|a, b| b.2.cmp(&a.2));
+    related.sort_by_key(|r| std::cmp::Reverse(r.2));
 
     println!("{}", "Related Notes".bold());
     println!("{}", "=".repeat(60));
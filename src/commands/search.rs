@@ -1,13 +1,94 @@
+use std::path::Path;
+
 use anyhow::Result;
 use colored::*;
 use regex::RegexBuilder;
 
 use crate::core::note::collect_all_notes;
 use crate::core::paths::VaultPaths;
+use crate::search::engine::{INDEX_FORMAT, META_FORMAT};
+use crate::search::filter::SearchFilter;
+use crate::search::vectordb::VectorDB;
+
+/// (note name, folder, match context)
+type Match = (String, String, String);
 
 pub fn run(query: &str, gist_only: bool, limit: Option<usize>) -> Result<()> {
-    let paths = VaultPaths::new();
-    let notes = collect_all_notes(&paths);
+    let paths = VaultPaths::new()?;
+    let (mode, results) = match open_keyword_index(&paths)? {
+        Some(db) => (
+            "BM25 keyword index",
+            keyword_search(&db, query, gist_only)?,
+        ),
+        None => ("full scan", scan(&paths, query, gist_only)?),
+    };
+
+    let total = results.len();
+    let display_limit = limit.unwrap_or(20);
+    let results_to_show = &results[..results.len().min(display_limit)];
+
+    println!("{}", "Search Results".bold());
+    println!("{}", "=".repeat(60));
+    println!("Query: \"{}\"", query);
+    println!("Found: {} matches ({})", total, mode);
+    println!();
+
+    if results_to_show.is_empty() {
+        println!("{}", "No matches found.".yellow());
+    } else {
+        for (name, folder, context) in results_to_show {
+            println!("{} [{}]", name.cyan(), folder);
+            println!("  {}", context.dimmed());
+            println!();
+        }
+
+        if total > display_limit {
+            println!(
+                "{}",
+                format!("... and {} more results", total - display_limit).dimmed()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The search index, if it exists and includes the keyword table
+fn open_keyword_index(paths: &VaultPaths) -> Result<Option<VectorDB>> {
+    let db_path = paths.search_db();
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let db = VectorDB::open(&db_path)?;
+    let current = db.get_meta(META_FORMAT)?.as_deref() == Some(INDEX_FORMAT);
+    Ok((current && db.get_stats()?.note_count > 0).then_some(db))
+}
+
+/// BM25-ranked matches from the keyword index
+fn keyword_search(db: &VectorDB, query: &str, gist_only: bool) -> Result<Vec<Match>> {
+    let fields: Option<&[&str]> = gist_only.then_some(&["title", "gist"]);
+    let hits = db.keyword_search(query, fields, &SearchFilter::default(), usize::MAX)?;
+
+    Ok(hits
+        .into_iter()
+        .map(|(note, _, snippet)| {
+            let folder = Path::new(&note.path)
+                .parent()
+                .and_then(|p| p.file_name())
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let context = format!(
+                "Match: {}",
+                snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+            );
+            (note.title, folder, context)
+        })
+        .collect())
+}
+
+/// Case-insensitive substring matches from reading every note
+fn scan(paths: &VaultPaths, query: &str, gist_only: bool) -> Result<Vec<Match>> {
+    let notes = collect_all_notes(paths);
 
     let re = RegexBuilder::new(&regex::escape(query))
         .case_insensitive(true)
@@ -53,34 +134,7 @@ pub fn run(query: &str, gist_only: bool, limit: Option<usize>) -> Result<()> {
         }
     }
 
-    let total = results.len();
-    let display_limit = limit.unwrap_or(20);
-    let results_to_show = &results[..results.len().min(display_limit)];
-
-    println!("{}", "Search Results".bold());
-    println!("{}", "=".repeat(60));
-    println!("Query: \"{}\"", query);
-    println!("Found: {} matches", total);
-    println!();
-
-    if results_to_show.is_empty() {
-        println!("{}", "No matches found.".yellow());
-    } else {
-        for (name, folder, context) in results_to_show {
-            println!("{} [{}]", name.cyan(), folder);
-            println!("  {}", context.dimmed());
-            println!();
-        }
-
-        if total > display_limit {
-            println!(
-                "{}",
-                format!("... and {} more results", total - display_limit).dimmed()
-            );
-        }
-    }
-
-    Ok(())
+    Ok(results)
 }
 
 fn truncate(s: &str, max_chars: usize) -> String {
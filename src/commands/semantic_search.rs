@@ -2,36 +2,47 @@
 
 use anyhow::Result;
 use colored::Colorize;
-use std::path::PathBuf;
 
 use crate::core::paths::VaultPaths;
-use crate::search::engine::{simple_search, SearchEngine};
-
-/// Get default paths for search engine
-fn get_default_paths() -> (PathBuf, PathBuf, PathBuf) {
-    let vault_path = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    let tools_path = vault_path.join(".opencode/tools");
-    let db_path = tools_path.join("data/search.db");
-    let model_path = tools_path.join("models/model.onnx");
-
-    (vault_path, db_path, model_path)
-}
+use crate::search::embedder::embedder_status;
+use crate::search::engine::{simple_search, SearchEngine, SearchMode};
+use crate::search::filter::SearchFilter;
 
 /// Run semantic search command
-pub fn run(query: &str, limit: Option<usize>, json: bool, fallback: bool) -> Result<()> {
-    let (vault_path, db_path, model_path) = get_default_paths();
+pub fn run(
+    query: &str,
+    limit: Option<usize>,
+    json: bool,
+    fallback: bool,
+    mode: SearchMode,
+    filter: &SearchFilter,
+) -> Result<()> {
+    let paths = VaultPaths::new()?;
+    let db_path = paths.search_db();
     let limit = limit.unwrap_or(5);
 
-    // Check if we should use fallback (simple string search)
-    let use_fallback = fallback || !model_path.exists() || !db_path.exists();
-
-    if use_fallback {
-        return run_simple_search(&vault_path, query, limit, json);
+    // Fall back to simple string search without an index
+    if fallback || !db_path.exists() {
+        return run_simple_search(&paths, query, filter, limit, json);
     }
 
-    // Use semantic search
-    let mut engine = SearchEngine::new(&vault_path, &db_path, &model_path)?;
-    let results = engine.search(query, limit)?;
+    // Keyword search still works when the embedder cannot be loaded
+    let embedder_ready = embedder_status(&paths.config.search.embedder, &paths.root).is_ready();
+    let mode = if mode.uses_embedder() && !embedder_ready {
+        if !json {
+            println!(
+                "{} Embedder not available; using keyword search",
+                "!".yellow()
+            );
+            println!();
+        }
+        SearchMode::Keyword
+    } else {
+        mode
+    };
+
+    let mut engine = SearchEngine::new(&paths.root, &db_path)?;
+    let results = engine.search_with(mode, query, filter, limit)?;
 
     if json {
         let json_results: Vec<_> = results
@@ -45,6 +56,14 @@ pub fn run(query: &str, limit: Option<usize>, json: bool, fallback: bool) -> Res
                     "type": r.note_type,
                     "area": r.area,
                     "score": r.score,
+                    "mode": mode.to_string(),
+                    "snippet": r.snippet,
+                    "passage": r.passage.as_ref().map(|p| serde_json::json!({
+                        "text": p.text,
+                        "heading_path": p.heading_path,
+                        "start": p.start,
+                        "end": p.end,
+                    })),
                 })
             })
             .collect();
@@ -56,10 +75,11 @@ pub fn run(query: &str, limit: Option<usize>, json: bool, fallback: bool) -> Res
         }
 
         println!(
-            "{} {} results for: {}",
+            "{} {} results for: {} ({})",
             "→".dimmed(),
             results.len(),
-            query.cyan()
+            query.cyan(),
+            mode
         );
         println!();
 
@@ -90,6 +110,28 @@ pub fn run(query: &str, limit: Option<usize>, json: bool, fallback: bool) -> Res
                 println!("   {}", display_gist.dimmed());
             }
 
+            if let Some(ref passage) = result.passage {
+                if !passage.heading_path.is_empty() {
+                    println!("   § {}", passage.heading_path.join(" > ").cyan());
+                }
+                let text = passage
+                    .text
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let display_passage = if text.chars().count() > 160 {
+                    format!("{}...", text.chars().take(160).collect::<String>())
+                } else {
+                    text
+                };
+                println!("   {}", display_passage);
+            } else if let Some(ref snippet) = result.snippet {
+                println!(
+                    "   {}",
+                    snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+                );
+            }
+
             if let (Some(ref note_type), Some(ref area)) = (&result.note_type, &result.area) {
                 println!("   {} | {}", note_type, area);
             }
@@ -101,9 +143,14 @@ pub fn run(query: &str, limit: Option<usize>, json: bool, fallback: bool) -> Res
 }
 
 /// Run simple string-based search (fallback)
-fn run_simple_search(vault_path: &PathBuf, query: &str, limit: usize, json: bool) -> Result<()> {
-    let vault_paths = VaultPaths::from_root(vault_path.clone());
-    let results = simple_search(&vault_paths, query, limit);
+fn run_simple_search(
+    vault_paths: &VaultPaths,
+    query: &str,
+    filter: &SearchFilter,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let results = simple_search(vault_paths, query, filter, limit);
 
     if json {
         let json_results: Vec<_> = results
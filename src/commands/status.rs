@@ -6,6 +6,7 @@ use chrono::{Duration, Local};
 use colored::*;
 use serde::Serialize;
 
+use crate::core::inbox::Inbox;
 use crate::core::note::collect_all_notes;
 use crate::core::paths::VaultPaths;
 
@@ -34,13 +35,29 @@ struct Warning {
 }
 
 pub fn run(brief: bool, json: bool) -> Result<()> {
-    let paths = VaultPaths::new();
+    let paths = VaultPaths::new()?;
     let notes = collect_all_notes(&paths);
 
-    let mut folder_counts = HashMap::new();
-    folder_counts.insert("Notes".to_string(), count_files(&paths.notes));
-    folder_counts.insert("Projects".to_string(), count_files(&paths.projects));
-    folder_counts.insert("Archive".to_string(), count_files(&paths.archive));
+    let content_dirs = paths.content_dirs();
+    let label = |dir: &std::path::Path| {
+        dir.strip_prefix(&paths.root)
+            .unwrap_or(dir)
+            .to_string_lossy()
+            .to_string()
+    };
+    let mut folder_counts: HashMap<String, usize> =
+        content_dirs.iter().map(|dir| (label(dir), 0)).collect();
+    for note in &notes {
+        // A content dir nested in another one (e.g. an extra dir inside
+        // Notes/) counts its own notes, so no note is counted twice
+        if let Some(dir) = content_dirs
+            .iter()
+            .filter(|dir| note.path.starts_with(dir))
+            .max_by_key(|dir| dir.components().count())
+        {
+            *folder_counts.entry(label(dir)).or_default() += 1;
+        }
+    }
 
     let total: usize = folder_counts.values().sum();
     let templates = count_files(&paths.templates);
@@ -72,7 +89,7 @@ pub fn run(brief: bool, json: bool) -> Result<()> {
 
     if inbox_memos >= INBOX_WARN_THRESHOLD {
         warnings.push(Warning {
-            target: "inbox.md".to_string(),
+            target: paths.config.layout.inbox.clone(),
             warning_type: "inbox_overflow".to_string(),
             message: format!(
                 "{}개 메모 누적 ({}개+ 초과)",
@@ -131,11 +148,8 @@ fn count_files(path: &std::path::Path) -> usize {
 }
 
 fn count_inbox_memos(inbox_path: &std::path::Path) -> usize {
-    if !inbox_path.exists() {
-        return 0;
-    }
-    fs::read_to_string(inbox_path)
-        .map(|content| content.matches("\n---\n").count())
+    Inbox::load(inbox_path)
+        .map(|inbox| inbox.memos.len())
         .unwrap_or(0)
 }
 
@@ -33,7 +33,7 @@ struct Suggestion {
 }
 
 pub fn run(analyze: bool, json: bool) -> Result<()> {
-    let paths = VaultPaths::new();
+    let paths = VaultPaths::new()?;
     let notes = collect_all_notes(&paths);
 
     let mut tag_notes: HashMap<String, Vec<String>> = HashMap::new();
@@ -61,7 +61,7 @@ pub fn run(analyze: bool, json: bool) -> Result<()> {
         })
         .collect();
 
-    tag_usage.sort_by(|a, b| b.count.cmp(&a.count));
+    tag_usage.sort_by_key(|t| std::cmp::Reverse(t.count));
 
     let low_usage_tags: Vec<String> = tag_usage
         .iter()
@@ -82,16 +82,16 @@ pub fn run(analyze: bool, json: bool) -> Result<()> {
                     let other_lower = other.to_lowercase();
 
                     // Check if one is prefix of another
-                    if t_lower.starts_with(&other_lower) || other_lower.starts_with(&t_lower) {
-                        if !suggestions.iter().any(|s: &Suggestion| {
+                    if (t_lower.starts_with(&other_lower) || other_lower.starts_with(&t_lower))
+                        && !suggestions.iter().any(|s: &Suggestion| {
                             (s.tag == *t || s.tag == *other) && s.action == "merge"
-                        }) {
-                            suggestions.push(Suggestion {
-                                action: "merge".to_string(),
-                                tag: format!("{} / {}", t, other),
-                                reason: "Similar tag names - consider merging".to_string(),
-                            });
-                        }
+                        })
+                    {
+                        suggestions.push(Suggestion {
+                            action: "merge".to_string(),
+                            tag: format!("{} / {}", t, other),
+                            reason: "Similar tag names - consider merging".to_string(),
+                        });
                     }
                 }
             }
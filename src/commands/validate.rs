@@ -2,14 +2,18 @@ use anyhow::Result;
 use colored::*;
 use serde::Serialize;
 
-use crate::core::note::{collect_all_notes, collect_note_names};
+use crate::core::attachment::{AttachmentIndex, ReferenceIssue};
+use crate::core::note::collect_all_notes;
 use crate::core::paths::VaultPaths;
+use crate::core::wikilink::LinkResolver;
 
 #[derive(Serialize)]
 struct ValidationResult {
     total_files: usize,
     schema_errors: usize,
     broken_wikilinks: usize,
+    broken_markdown_links: usize,
+    missing_attachments: usize,
     folder_mismatches: usize,
     files_with_errors: Vec<FileError>,
 }
@@ -21,14 +25,17 @@ struct FileError {
 }
 
 pub fn run(schema_only: bool, wikilinks_only: bool, json: bool) -> Result<()> {
-    let paths = VaultPaths::new();
+    let paths = VaultPaths::new()?;
     let notes = collect_all_notes(&paths);
-    let note_names = collect_note_names(&paths);
+    let attachments = AttachmentIndex::for_vault(&paths);
+    let resolver = LinkResolver::from_notes(&notes).with_files(attachments.files());
 
     let mut result = ValidationResult {
         total_files: notes.len(),
         schema_errors: 0,
         broken_wikilinks: 0,
+        broken_markdown_links: 0,
+        missing_attachments: 0,
         folder_mismatches: 0,
         files_with_errors: Vec::new(),
     };
@@ -39,7 +46,7 @@ pub fn run(schema_only: bool, wikilinks_only: bool, json: bool) -> Result<()> {
         let mut errors = Vec::new();
 
         if check_all || schema_only {
-            let violations = note.validate_schema();
+            let violations = note.validate_schema(&paths.config.schema);
             for v in &violations {
                 errors.push(format!("[SCHEMA] {}", v));
             }
@@ -47,25 +54,33 @@ pub fn run(schema_only: bool, wikilinks_only: bool, json: bool) -> Result<()> {
         }
 
         if check_all || wikilinks_only {
-            let links = note.wikilinks();
-            for link in &links {
-                if !note_names.contains(link) {
-                    errors.push(format!("[WIKILINK] Broken link: [[{}]]", link));
-                    result.broken_wikilinks += 1;
+            for (link, err) in resolver.broken_links(&note.name, &note.content) {
+                errors.push(format!("[WIKILINK] Broken link: {} ({})", link, err));
+                result.broken_wikilinks += 1;
+            }
+
+            for issue in attachments.check_note(note, &resolver).issues {
+                match issue {
+                    ReferenceIssue::BrokenMarkdownLink(_) => {
+                        errors.push(format!("[LINK] {}", issue));
+                        result.broken_markdown_links += 1;
+                    }
+                    ReferenceIssue::MissingAttachment(_) => {
+                        errors.push(format!("[ATTACHMENT] {}", issue));
+                        result.missing_attachments += 1;
+                    }
                 }
             }
         }
 
-        if check_all {
-            if !note.check_folder_type_match() {
-                errors.push(format!(
-                    "[FOLDER] type='{}' status='{}' should not be in {}",
-                    note.note_type().unwrap_or("?"),
-                    note.status().unwrap_or("?"),
-                    note.folder()
-                ));
-                result.folder_mismatches += 1;
-            }
+        if check_all && !note.check_folder_type_match(&paths) {
+            errors.push(format!(
+                "[FOLDER] type='{}' status='{}' should not be in {}",
+                note.note_type().unwrap_or("?"),
+                note.status().unwrap_or("?"),
+                note.folder()
+            ));
+            result.folder_mismatches += 1;
         }
 
         if !errors.is_empty() {
@@ -82,7 +97,12 @@ pub fn run(schema_only: bool, wikilinks_only: bool, json: bool) -> Result<()> {
         print_report(&result);
     }
 
-    if result.schema_errors > 0 || result.broken_wikilinks > 0 || result.folder_mismatches > 0 {
+    if result.schema_errors > 0
+        || result.broken_wikilinks > 0
+        || result.broken_markdown_links > 0
+        || result.missing_attachments > 0
+        || result.folder_mismatches > 0
+    {
         std::process::exit(1);
     }
 
@@ -130,6 +150,22 @@ fn print_report(result: &ValidationResult) {
             result.broken_wikilinks.to_string().green()
         }
     );
+    println!(
+        "  Broken markdown links: {}",
+        if result.broken_markdown_links > 0 {
+            result.broken_markdown_links.to_string().red()
+        } else {
+            result.broken_markdown_links.to_string().green()
+        }
+    );
+    println!(
+        "  Missing attachments: {}",
+        if result.missing_attachments > 0 {
+            result.missing_attachments.to_string().red()
+        } else {
+            result.missing_attachments.to_string().green()
+        }
+    );
     println!(
         "  Folder mismatches: {}",
         if result.folder_mismatches > 0 {
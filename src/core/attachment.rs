@@ -0,0 +1,358 @@
+//! Attachment lookup and integrity checks for Markdown links and embeds
+//!
+//! Obsidian resolves `![[file.png]]` by file name anywhere in the vault and
+//! `[text](path)` relative to the note, the vault root, or (in its
+//! "shortest path" link format) by file name.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use super::ignore::IgnoreRules;
+use super::markdown::markdown_links;
+use super::note::Note;
+use super::paths::VaultPaths;
+use super::wikilink::{parse_wikilinks, LinkResolver};
+
+/// A Markdown link or attachment reference that does not resolve
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReferenceIssue {
+    /// `[text](Note.md)` whose target note does not exist
+    BrokenMarkdownLink(String),
+    /// Embed or link to a non-note file that does not exist
+    MissingAttachment(String),
+}
+
+impl fmt::Display for ReferenceIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BrokenMarkdownLink(link) => write!(f, "Broken markdown link: {}", link),
+            Self::MissingAttachment(link) => write!(f, "Missing attachment: {}", link),
+        }
+    }
+}
+
+/// Files a note references and the references that failed to resolve
+#[derive(Debug, Default)]
+pub struct References {
+    /// Resolved files, relative to the vault root
+    pub files: Vec<PathBuf>,
+    pub issues: Vec<ReferenceIssue>,
+}
+
+/// Every non-hidden, non-ignored file in the vault
+pub struct AttachmentIndex {
+    root: PathBuf,
+    /// Attachments folder, relative to the root
+    attachments_dir: PathBuf,
+    files: HashSet<PathBuf>,
+    /// Folders containing any of `files`, relative to the root
+    dirs: HashSet<PathBuf>,
+    /// Lowercased file name → relative paths, shortest first
+    by_name: HashMap<String, Vec<PathBuf>>,
+}
+
+impl AttachmentIndex {
+    pub fn for_vault(paths: &VaultPaths) -> Self {
+        let ignore = IgnoreRules::for_vault(paths);
+        let mut files = HashSet::new();
+
+        let walker = WalkDir::new(&paths.root).into_iter().filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let rel = entry
+                .path()
+                .strip_prefix(&paths.root)
+                .unwrap_or(entry.path());
+            !hidden && !ignore.is_ignored(rel, entry.file_type().is_dir())
+        });
+        for entry in walker.flatten() {
+            if entry.file_type().is_file() {
+                if let Ok(rel) = entry.path().strip_prefix(&paths.root) {
+                    files.insert(rel.to_path_buf());
+                }
+            }
+        }
+
+        Self::new(
+            paths.root.clone(),
+            paths
+                .attachments
+                .strip_prefix(&paths.root)
+                .unwrap_or(&paths.attachments)
+                .to_path_buf(),
+            files,
+        )
+    }
+
+    fn new(root: PathBuf, attachments_dir: PathBuf, files: HashSet<PathBuf>) -> Self {
+        let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let mut dirs = HashSet::new();
+        for file in &files {
+            dirs.extend(
+                file.ancestors()
+                    .skip(1)
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .map(Path::to_path_buf),
+            );
+            if let Some(name) = file.file_name() {
+                by_name
+                    .entry(name.to_string_lossy().to_lowercase())
+                    .or_default()
+                    .push(file.clone());
+            }
+        }
+        for candidates in by_name.values_mut() {
+            candidates.sort_by_key(|p| (p.components().count(), p.clone()));
+        }
+
+        Self {
+            root,
+            attachments_dir,
+            files,
+            dirs,
+            by_name,
+        }
+    }
+
+    /// Every indexed file, relative to the root
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(PathBuf::as_path)
+    }
+
+    /// Files inside the attachments folder
+    pub fn attachments(&self) -> impl Iterator<Item = &Path> {
+        self.files
+            .iter()
+            .filter(|f| f.starts_with(&self.attachments_dir))
+            .map(PathBuf::as_path)
+    }
+
+    /// Find `target` the way Obsidian resolves embeds: an exact vault path,
+    /// or else the file name anywhere (preferring paths ending in `target`)
+    pub fn find(&self, target: &str) -> Option<&Path> {
+        let target_path = normalize(Path::new(target.trim_start_matches('/')));
+        if let Some(file) = self.files.get(&target_path) {
+            return Some(file);
+        }
+
+        let name = target_path.file_name()?.to_string_lossy().to_lowercase();
+        let candidates = self.by_name.get(&name)?;
+        candidates
+            .iter()
+            .find(|c| ends_with_ignore_case(c, &target_path))
+            .or_else(|| candidates.first())
+            .map(PathBuf::as_path)
+    }
+
+    /// Resolve a Markdown link path from a note in `note_dir` (relative to the root)
+    fn find_relative(&self, note_dir: &Path, path: &str) -> Option<&Path> {
+        if !path.starts_with('/') {
+            let relative = normalize(&note_dir.join(path));
+            if let Some(file) = self.files.get(&relative) {
+                return Some(file);
+            }
+        }
+        self.find(path)
+    }
+
+    /// Whether a Markdown link path from a note in `note_dir` names a folder
+    fn is_folder(&self, note_dir: &Path, path: &str) -> bool {
+        let from_root = normalize(Path::new(path.trim_start_matches('/')));
+        (!path.starts_with('/') && self.dirs.contains(&normalize(&note_dir.join(path))))
+            || self.dirs.contains(&from_root)
+    }
+
+    /// Check the attachment embeds and Markdown links of `note`
+    pub fn check_note(&self, note: &Note, resolver: &LinkResolver) -> References {
+        let mut refs = References::default();
+        let note_dir = note
+            .path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(&self.root).ok())
+            .unwrap_or(Path::new(""));
+
+        // Links to notes are left to `LinkResolver`; any other target that
+        // names a vault file is an attachment, whatever its extension
+        for link in parse_wikilinks(&note.content) {
+            if link.is_self_link() || (!link.is_attachment() && resolver.contains(link.name())) {
+                continue;
+            }
+            match self
+                .find(&link.target)
+                .or_else(|| self.find(&format!("{}.md", link.target)))
+            {
+                Some(file) => refs.files.push(file.to_path_buf()),
+                None if link.is_attachment() => refs
+                    .issues
+                    .push(ReferenceIssue::MissingAttachment(link.to_string())),
+                None => {}
+            }
+        }
+
+        for link in markdown_links(&note.content) {
+            let Some(path) = link.path() else {
+                continue;
+            };
+            if let Some(file) = self.find_relative(note_dir, &path) {
+                refs.files.push(file.to_path_buf());
+                continue;
+            }
+            if path.ends_with('/') || self.is_folder(note_dir, &path) {
+                continue;
+            }
+
+            let written = note.content[link.span.clone()].to_string();
+            let is_note = Path::new(&path)
+                .extension()
+                .is_none_or(|e| e.eq_ignore_ascii_case("md"));
+            if is_note {
+                let stem = Path::new(&path)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                if !resolver.contains(&stem) {
+                    refs.issues
+                        .push(ReferenceIssue::BrokenMarkdownLink(written));
+                }
+            } else {
+                refs.issues.push(ReferenceIssue::MissingAttachment(written));
+            }
+        }
+
+        refs
+    }
+
+    /// Attachments none of `references` (from [`Self::check_note`] on every
+    /// note) points to, sorted by path
+    pub fn orphaned<'a>(
+        &self,
+        references: impl IntoIterator<Item = &'a References>,
+    ) -> Vec<PathBuf> {
+        let referenced: HashSet<&Path> = references
+            .into_iter()
+            .flat_map(|refs| refs.files.iter().map(PathBuf::as_path))
+            .collect();
+
+        let mut orphans: Vec<PathBuf> = self
+            .attachments()
+            .filter(|file| !referenced.contains(file))
+            .map(Path::to_path_buf)
+            .collect();
+        orphans.sort();
+        orphans
+    }
+}
+
+/// Lexically resolve `.` and `..` components
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::Normal(part) => out.push(part),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn ends_with_ignore_case(path: &Path, suffix: &Path) -> bool {
+    let path = path.to_string_lossy().to_lowercase();
+    let suffix = suffix.to_string_lossy().to_lowercase();
+    path == suffix || path.ends_with(&format!("/{}", suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(files: &[&str]) -> AttachmentIndex {
+        AttachmentIndex::new(
+            PathBuf::from("/vault"),
+            PathBuf::from("_system/Attachments"),
+            files.iter().map(PathBuf::from).collect(),
+        )
+    }
+
+    #[test]
+    fn test_find_attachments() {
+        let index = index(&[
+            "_system/Attachments/diagram.excalidraw.md",
+            "_system/Attachments/img/pic.png",
+            "Notes/pic.png",
+            "Notes/Other Note.md",
+        ]);
+
+        assert_eq!(
+            index.find("img/pic.png"),
+            Some(Path::new("_system/Attachments/img/pic.png"))
+        );
+        assert_eq!(index.find("PIC.png"), Some(Path::new("Notes/pic.png")));
+        assert_eq!(
+            index.find_relative(Path::new("Notes"), "../_system/Attachments/img/pic.png"),
+            Some(Path::new("_system/Attachments/img/pic.png"))
+        );
+        assert!(index.find("missing.png").is_none());
+        assert_eq!(index.attachments().count(), 2);
+    }
+
+    #[test]
+    fn test_check_note_references() {
+        let index = index(&[
+            "_system/Attachments/pic.png",
+            "_system/Attachments/unused.pdf",
+            "_system/Attachments/data.csv",
+            "_system/Attachments/report.docx",
+            "Notes/Other Note.md",
+        ]);
+        let resolver = LinkResolver::new([("Other Note", "")]).with_files(index.files());
+        let note = Note {
+            path: PathBuf::from("/vault/Notes/A.md"),
+            name: "A".to_string(),
+            content: "![[pic.png]] ![[gone.png]] [x](Other%20Note.md) [y](Nope.md) [z](Gone.MD) \
+                      ![](lost.jpg) ![[data.csv]] [[report.docx|report]] [[Other Note]] [[Missing]] \
+                      [all](../Notes/) [sys](/_system)"
+                .to_string(),
+            frontmatter: None,
+            frontmatter_error: None,
+            modified: chrono::Local::now(),
+            created: chrono::Local::now(),
+        };
+
+        let refs = index.check_note(&note, &resolver);
+        assert_eq!(
+            refs.files,
+            vec![
+                PathBuf::from("_system/Attachments/pic.png"),
+                PathBuf::from("_system/Attachments/data.csv"),
+                PathBuf::from("_system/Attachments/report.docx"),
+                PathBuf::from("Notes/Other Note.md"),
+            ]
+        );
+        assert_eq!(
+            refs.issues,
+            vec![
+                ReferenceIssue::MissingAttachment("![[gone.png]]".to_string()),
+                ReferenceIssue::BrokenMarkdownLink("[y](Nope.md)".to_string()),
+                ReferenceIssue::BrokenMarkdownLink("[z](Gone.MD)".to_string()),
+                ReferenceIssue::MissingAttachment("![](lost.jpg)".to_string()),
+            ]
+        );
+        assert_eq!(
+            index.orphaned([&refs]),
+            vec![PathBuf::from("_system/Attachments/unused.pdf")]
+        );
+
+        let broken = resolver.broken_links(&note.name, &note.content);
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].0.to_string(), "[[Missing]]");
+    }
+}
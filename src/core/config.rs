@@ -0,0 +1,587 @@
+//! Vault configuration loaded from `elysium.yaml` at the vault root
+//!
+//! Every section is optional; missing sections and keys fall back to the
+//! built-in defaults, which describe the standard Second Brain layout.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer};
+
+use super::schema::{DEFAULT_AREAS, DEFAULT_STATUS, DEFAULT_TYPES};
+
+/// Config file names looked up at the vault root, in order
+pub const CONFIG_FILE_NAMES: &[&str] = &["elysium.yaml", "elysium.yml"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VaultConfig {
+    pub schema: VaultSchema,
+    pub layout: VaultLayout,
+    pub discovery: DiscoveryConfig,
+    pub search: SearchConfig,
+}
+
+impl VaultConfig {
+    /// Load the config file from `root`, or the defaults if there is none
+    pub fn load(root: &Path) -> Result<Self> {
+        match Self::find(root) {
+            Some(path) => {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                Self::parse(&content)
+                    .with_context(|| format!("Invalid vault config {}", path.display()))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        if content.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Path of the config file in `root`, if one exists
+    pub fn find(root: &Path) -> Option<PathBuf> {
+        CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| root.join(name))
+            .find(|path| path.is_file())
+    }
+}
+
+/// Frontmatter rules enforced by validate, audit and health
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VaultSchema {
+    /// Allowed values per field; an empty list leaves the field unconstrained.
+    /// Entries are merged over the defaults for `type`, `status` and `area`.
+    #[serde(deserialize_with = "merge_allowed_values")]
+    pub allowed_values: BTreeMap<String, Vec<String>>,
+    /// Fields every note must have
+    pub required_fields: Vec<String>,
+    /// Additional required fields keyed by note type
+    pub type_required_fields: BTreeMap<String, Vec<String>>,
+    pub tags: TagRules,
+}
+
+impl Default for VaultSchema {
+    fn default() -> Self {
+        Self {
+            allowed_values: default_allowed_values(),
+            required_fields: ["type", "status", "area", "gist"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            type_required_fields: BTreeMap::new(),
+            tags: TagRules::default(),
+        }
+    }
+}
+
+impl VaultSchema {
+    /// Allowed values for `field`, or None if the field is unconstrained
+    pub fn allowed(&self, field: &str) -> Option<&[String]> {
+        self.allowed_values
+            .get(field)
+            .filter(|values| !values.is_empty())
+            .map(|values| values.as_slice())
+    }
+
+    /// Required fields for a note of `note_type`, global ones first
+    pub fn required_for(&self, note_type: Option<&str>) -> Vec<&str> {
+        let mut fields: Vec<&str> = self.required_fields.iter().map(String::as_str).collect();
+        if let Some(extra) = note_type.and_then(|t| self.type_required_fields.get(t)) {
+            for field in extra {
+                if !fields.contains(&field.as_str()) {
+                    fields.push(field);
+                }
+            }
+        }
+        fields
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagRules {
+    /// Maximum number of tags per note (None = unlimited)
+    pub max: Option<usize>,
+    pub lowercase: bool,
+    /// Allow `parent/child` tags
+    pub allow_hierarchy: bool,
+}
+
+impl Default for TagRules {
+    fn default() -> Self {
+        Self {
+            max: Some(5),
+            lowercase: true,
+            allow_hierarchy: false,
+        }
+    }
+}
+
+/// Folder layout, as paths relative to the vault root
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VaultLayout {
+    pub notes: String,
+    pub projects: String,
+    pub archive: String,
+    /// Additional folders whose notes are indexed, audited and searched
+    pub extra_content_dirs: Vec<String>,
+    pub system: String,
+    pub dashboards: String,
+    pub templates: String,
+    pub attachments: String,
+    /// AI agent configuration directory
+    pub agent_dir: String,
+    pub inbox: String,
+    /// Where the search index and other generated data live
+    pub data_dir: String,
+    /// Type/status → folder rules, first match wins. When unset, rules are
+    /// derived from `notes`, `projects` and `archive`.
+    pub folder_rules: Option<Vec<FolderRule>>,
+}
+
+impl Default for VaultLayout {
+    fn default() -> Self {
+        Self {
+            notes: "Notes".to_string(),
+            projects: "Projects".to_string(),
+            archive: "Archive".to_string(),
+            extra_content_dirs: Vec::new(),
+            system: "_system".to_string(),
+            dashboards: "_system/Dashboards".to_string(),
+            templates: "_system/Templates".to_string(),
+            attachments: "_system/Attachments".to_string(),
+            agent_dir: ".opencode".to_string(),
+            inbox: "inbox.md".to_string(),
+            data_dir: ".opencode/tools/data".to_string(),
+            folder_rules: None,
+        }
+    }
+}
+
+impl VaultLayout {
+    pub fn folder_rules(&self) -> Vec<FolderRule> {
+        if let Some(rules) = &self.folder_rules {
+            return rules.clone();
+        }
+
+        vec![
+            FolderRule {
+                types: vec!["project".to_string()],
+                status: Some("archived".to_string()),
+                folder: self.archive.clone(),
+            },
+            FolderRule {
+                types: vec!["project".to_string()],
+                status: None,
+                folder: self.projects.clone(),
+            },
+            FolderRule {
+                types: vec!["note".to_string(), "term".to_string(), "log".to_string()],
+                status: None,
+                folder: self.notes.clone(),
+            },
+        ]
+    }
+}
+
+/// Notes of one of `types` (and `status`, if set) belong in `folder`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FolderRule {
+    pub types: Vec<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    pub folder: String,
+}
+
+impl FolderRule {
+    pub fn matches(&self, note_type: Option<&str>, status: Option<&str>) -> bool {
+        let type_matches = note_type.is_some_and(|t| self.types.iter().any(|rt| rt == t));
+        let status_matches = match &self.status {
+            Some(s) => status == Some(s.as_str()),
+            None => true,
+        };
+        type_matches && status_matches
+    }
+}
+
+/// How notes are found inside the content directories
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscoveryConfig {
+    /// Descend into subfolders of content directories
+    pub recursive: bool,
+    /// `.gitignore`-style patterns, relative to the vault root
+    pub exclude: Vec<String>,
+    /// Honor `userIgnoreFilters` from `.obsidian/app.json`
+    pub obsidian_ignore: bool,
+    /// What `init` does about subfolders in content directories.
+    /// Defaults to `forbid`, or `allow` in recursive mode.
+    pub subfolders: Option<SubfolderPolicy>,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            recursive: false,
+            exclude: Vec::new(),
+            obsidian_ignore: true,
+            subfolders: None,
+        }
+    }
+}
+
+impl DiscoveryConfig {
+    pub fn subfolder_policy(&self) -> SubfolderPolicy {
+        self.subfolders.unwrap_or(if self.recursive {
+            SubfolderPolicy::Allow
+        } else {
+            SubfolderPolicy::Forbid
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubfolderPolicy {
+    Allow,
+    Warn,
+    Forbid,
+}
+
+/// Semantic search settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    pub embedder: EmbedderConfig,
+    /// How stored embeddings are compressed; changing it rebuilds the index
+    pub quantization: Quantization,
+    /// Weights of the fields mixed into each note's gist-level embedding;
+    /// changing them rebuilds the index
+    pub fields: FieldWeights,
+}
+
+/// How much the title, tags and gist contribute to a note's gist-level
+/// embedding; fields weighted 0 are left out
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FieldWeights {
+    pub title: f32,
+    pub tags: f32,
+    pub gist: f32,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        Self {
+            title: 0.5,
+            tags: 0.25,
+            gist: 1.0,
+        }
+    }
+}
+
+impl FieldWeights {
+    /// Identifier recorded in the index, so changed weights trigger a rebuild
+    pub fn id(&self) -> String {
+        format!("title={}:tags={}:gist={}", self.title, self.tags, self.gist)
+    }
+}
+
+/// Storage format of indexed embeddings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quantization {
+    /// Full-precision f32
+    #[default]
+    None,
+    /// One signed byte per dimension plus a scale, about 4x smaller
+    Int8,
+    /// One sign bit per dimension, about 32x smaller
+    Binary,
+}
+
+impl Quantization {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Int8 => "int8",
+            Self::Binary => "binary",
+        }
+    }
+
+    /// Parse a name written by [`Quantization::as_str`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::None, Self::Int8, Self::Binary]
+            .into_iter()
+            .find(|q| q.as_str() == name)
+    }
+}
+
+/// Which embedding backend builds the search index, keyed by `backend`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase", deny_unknown_fields)]
+pub enum EmbedderConfig {
+    /// Harmonic Token Projection; built in, needs no model files
+    Htp {
+        #[serde(default)]
+        tokenizer: TokenizerConfig,
+        #[serde(default)]
+        pooling: Pooling,
+    },
+    /// Feature-hashed word and character n-grams
+    Hashed {
+        #[serde(default = "default_hashed_dimension")]
+        dimension: usize,
+    },
+    /// Local sentence-transformer ONNX model (requires the `onnx` cargo feature).
+    /// Paths are relative to the vault root.
+    Onnx {
+        model: String,
+        tokenizer: String,
+        #[serde(default = "default_onnx_dimension")]
+        dimension: usize,
+        #[serde(default = "default_onnx_max_length")]
+        max_length: usize,
+    },
+    /// OpenAI-compatible `/embeddings` endpoint
+    OpenAi {
+        /// Base URL, e.g. `http://localhost:8080/v1`
+        url: String,
+        model: String,
+        dimension: usize,
+        /// Environment variable holding the API key, if the endpoint needs one
+        #[serde(default)]
+        api_key_env: Option<String>,
+    },
+}
+
+impl Default for EmbedderConfig {
+    fn default() -> Self {
+        Self::Htp {
+            tokenizer: TokenizerConfig::default(),
+            pooling: Pooling::default(),
+        }
+    }
+}
+
+/// How HTP splits text into tokens
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokenizerConfig {
+    /// Character n-gram size for Chinese and Japanese text
+    pub cjk_ngram: usize,
+    pub hangul: HangulMode,
+    /// Drop common English function words
+    pub stopwords: bool,
+    /// Reduce English words to their Porter stem
+    pub stemming: bool,
+}
+
+impl Default for TokenizerConfig {
+    fn default() -> Self {
+        Self {
+            cjk_ngram: 2,
+            hangul: HangulMode::Bigram,
+            stopwords: true,
+            stemming: true,
+        }
+    }
+}
+
+/// How HTP combines token vectors into a text embedding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pooling {
+    /// Plain mean over tokens
+    Mean,
+    /// Mean weighted by inverse document frequency over the indexed notes,
+    /// so words common to most notes count for less
+    #[default]
+    Idf,
+}
+
+impl Pooling {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Idf => "idf",
+        }
+    }
+}
+
+/// How Korean words are tokenized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HangulMode {
+    /// Whole words, as written
+    Word,
+    /// Syllable bigrams, so particles and endings only affect one token
+    Bigram,
+    /// Trigrams over the syllables decomposed into jamo
+    Jamo,
+}
+
+impl HangulMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Word => "word",
+            Self::Bigram => "bigram",
+            Self::Jamo => "jamo",
+        }
+    }
+}
+
+fn default_hashed_dimension() -> usize {
+    512
+}
+
+fn default_onnx_dimension() -> usize {
+    384
+}
+
+fn default_onnx_max_length() -> usize {
+    256
+}
+
+fn default_allowed_values() -> BTreeMap<String, Vec<String>> {
+    [
+        ("type", DEFAULT_TYPES),
+        ("status", DEFAULT_STATUS),
+        ("area", DEFAULT_AREAS),
+    ]
+    .iter()
+    .map(|(field, values)| {
+        (
+            field.to_string(),
+            values.iter().map(|v| v.to_string()).collect(),
+        )
+    })
+    .collect()
+}
+
+fn merge_allowed_values<'de, D>(deserializer: D) -> Result<BTreeMap<String, Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut values = default_allowed_values();
+    values.extend(BTreeMap::<String, Vec<String>>::deserialize(deserializer)?);
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_overrides_merge_with_defaults() {
+        let config = VaultConfig::parse(
+            "schema:\n  allowed_values:\n    type: [note, meeting]\n  type_required_fields:\n    meeting: [date]\n  tags:\n    max: null\n",
+        )
+        .unwrap();
+        let schema = &config.schema;
+
+        assert_eq!(schema.allowed("type").unwrap(), ["note", "meeting"]);
+        assert_eq!(
+            schema.allowed("status").unwrap().len(),
+            DEFAULT_STATUS.len()
+        );
+        assert_eq!(
+            schema.required_for(Some("meeting")),
+            ["type", "status", "area", "gist", "date"]
+        );
+        assert_eq!(schema.tags.max, None);
+        assert!(schema.tags.lowercase);
+    }
+
+    #[test]
+    fn test_layout_rules_follow_renamed_folders() {
+        let config = VaultConfig::parse("layout:\n  notes: Zettels\n").unwrap();
+        let rules = config.layout.folder_rules();
+        let rule = rules
+            .iter()
+            .find(|r| r.matches(Some("term"), Some("active")))
+            .unwrap();
+        assert_eq!(rule.folder, "Zettels");
+
+        let archived = rules
+            .iter()
+            .find(|r| r.matches(Some("project"), Some("archived")))
+            .unwrap();
+        assert_eq!(archived.folder, "Archive");
+    }
+
+    #[test]
+    fn test_embedder_backends() {
+        assert_eq!(
+            VaultConfig::default().search.embedder,
+            EmbedderConfig::Htp {
+                tokenizer: TokenizerConfig::default(),
+                pooling: Pooling::Idf,
+            }
+        );
+        let config = VaultConfig::parse(
+            "search:\n  embedder:\n    backend: htp\n    tokenizer: { hangul: jamo, stemming: false }\n    pooling: mean\n  fields: { title: 0 }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            (config.search.fields.title, config.search.fields.gist),
+            (0.0, 1.0)
+        );
+        let EmbedderConfig::Htp { tokenizer, pooling } = config.search.embedder else {
+            panic!("expected the HTP backend");
+        };
+        assert_eq!(
+            (tokenizer.hangul, tokenizer.stemming, tokenizer.stopwords),
+            (HangulMode::Jamo, false, true)
+        );
+        assert_eq!(pooling, Pooling::Mean);
+
+        let config = VaultConfig::parse("search:\n  embedder:\n    backend: hashed\n").unwrap();
+        assert_eq!(
+            config.search.embedder,
+            EmbedderConfig::Hashed { dimension: 512 }
+        );
+
+        let config = VaultConfig::parse(
+            "search:\n  embedder:\n    backend: openai\n    url: http://localhost:8080/v1\n    model: m\n    dimension: 8\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            config.search.embedder,
+            EmbedderConfig::OpenAi { dimension: 8, .. }
+        ));
+
+        assert!(VaultConfig::parse(
+            "search:\n  embedder:\n    backend: hashed\n    dimensions: 64\n"
+        )
+        .is_err());
+
+        assert_eq!(
+            VaultConfig::default().search.quantization,
+            Quantization::None
+        );
+        let config = VaultConfig::parse("search:\n  quantization: int8\n").unwrap();
+        assert_eq!(config.search.quantization, Quantization::Int8);
+        assert_eq!(
+            Quantization::from_name("binary"),
+            Some(Quantization::Binary)
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_rejected() {
+        assert!(VaultConfig::parse("schema:\n  typo: 1\n").is_err());
+        assert!(VaultConfig::parse("").is_ok());
+    }
+}
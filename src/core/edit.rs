@@ -0,0 +1,222 @@
+//! Targeted edits to a note: frontmatter fields, heading sections and the
+//! text before the footer
+//!
+//! Edits are applied against a version token of the content they were
+//! planned on, so a note changed in the meantime (e.g. in Obsidian) is
+//! never overwritten.
+
+use anyhow::{bail, Result};
+
+use super::frontmatter::{body_offset, FrontmatterUpdate};
+use super::markdown::{headings, normalize_heading};
+use super::template::{insert_body, FOOTER_START};
+use crate::search::hashed::fnv1a;
+
+/// A change to the note body
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyEdit {
+    /// Add text at the end of the section under `heading`, creating a `##`
+    /// section before the footer if there is none
+    AppendToSection { heading: String, text: String },
+    /// Replace everything under `heading` up to the next heading of the same
+    /// or a higher level, keeping the heading line
+    ReplaceSection { heading: String, text: String },
+    /// Add text just before `<!-- footer_start -->`, or at the end
+    InsertBeforeFooter(String),
+}
+
+/// Frontmatter and body changes applied together
+#[derive(Debug, Clone, Default)]
+pub struct NoteEdit {
+    pub frontmatter: FrontmatterUpdate,
+    pub body: Vec<BodyEdit>,
+}
+
+impl NoteEdit {
+    pub fn is_empty(&self) -> bool {
+        self.frontmatter.is_empty() && self.body.is_empty()
+    }
+
+    /// Apply the edit to a full note `content`, returning the new content
+    pub fn apply(&self, content: &str) -> Result<String> {
+        let mut content = self.frontmatter.apply(content)?;
+        for edit in &self.body {
+            content = edit.apply(&content)?;
+        }
+        Ok(content)
+    }
+}
+
+impl BodyEdit {
+    fn apply(&self, content: &str) -> Result<String> {
+        match self {
+            Self::InsertBeforeFooter(text) => Ok(insert_body(content, text)),
+            Self::AppendToSection { heading, text } => match find_section(content, heading) {
+                Some((_, end)) => Ok(join_blocks(&content[..end], text, &content[end..])),
+                None => Ok(insert_body(
+                    content,
+                    &format!("## {}\n\n{}", heading.trim(), text.trim_end()),
+                )),
+            },
+            Self::ReplaceSection { heading, text } => match find_section(content, heading) {
+                Some((start, end)) => Ok(join_blocks(&content[..start], text, &content[end..])),
+                None => bail!("Section '{}' not found", heading),
+            },
+        }
+    }
+}
+
+/// Version token of a note's content; changes whenever the content does
+///
+/// Also the content hash the search index keeps, so it must stay stable
+/// across builds.
+pub fn content_version(content: &str) -> String {
+    format!("{:016x}", fnv1a(content.as_bytes()))
+}
+
+/// Byte range of the section under `heading`, from just past the heading
+/// line to the next heading of the same or a higher level, the footer
+/// marker or the end of the note
+fn find_section(content: &str, heading: &str) -> Option<(usize, usize)> {
+    let body = body_offset(content);
+    let wanted = normalize_heading(heading.trim_start_matches('#'));
+    let all = headings(content);
+    let index = all
+        .iter()
+        .position(|h| h.offset >= body && normalize_heading(&h.text) == wanted)?;
+    let found = &all[index];
+
+    let start = content[found.offset..]
+        .find('\n')
+        .map_or(content.len(), |i| found.offset + i + 1);
+    let next_heading = all[index + 1..]
+        .iter()
+        .find(|h| h.level <= found.level)
+        .map(|h| h.offset);
+    let footer = content[start..].find(FOOTER_START).map(|i| start + i);
+    let end = [next_heading, footer]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(content.len());
+    Some((start, end))
+}
+
+/// `before`, then `text` set off by blank lines, then `after`
+fn join_blocks(before: &str, text: &str, after: &str) -> String {
+    let mut joined = match text.trim() {
+        "" => format!("{}\n", before.trim_end()),
+        text => format!("{}\n\n{}\n", before.trim_end(), text),
+    };
+    let after = after.trim_start_matches(['\r', '\n']);
+    if !after.is_empty() {
+        joined.push('\n');
+        joined.push_str(after);
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTE: &str = "---\ntype: note\n---\n# Title\n\nIntro\n\n## Log\n\n- first\n\n### Detail\n\nNested\n\n## Links\n\n- [[A]]\n\n<!-- footer_start -->\n## Footer\n<!-- footer_end -->\n";
+
+    fn apply(edit: BodyEdit) -> Result<String> {
+        NoteEdit {
+            body: vec![edit],
+            ..NoteEdit::default()
+        }
+        .apply(NOTE)
+    }
+
+    #[test]
+    fn test_append_and_replace_sections() -> Result<()> {
+        let appended = apply(BodyEdit::AppendToSection {
+            heading: "log".to_string(),
+            text: "- second".to_string(),
+        })?;
+        assert!(
+            appended.contains("### Detail\n\nNested\n\n- second\n\n## Links"),
+            "{}",
+            appended
+        );
+
+        let replaced = apply(BodyEdit::ReplaceSection {
+            heading: "## Links".to_string(),
+            text: "- [[B]]\n".to_string(),
+        })?;
+        assert!(
+            replaced.contains("## Links\n\n- [[B]]\n\n<!-- footer_start -->"),
+            "{}",
+            replaced
+        );
+        assert!(replaced.contains("- first"));
+
+        let created = apply(BodyEdit::AppendToSection {
+            heading: "Ideas".to_string(),
+            text: "More".to_string(),
+        })?;
+        assert!(
+            created.contains("- [[A]]\n\n## Ideas\n\nMore\n\n<!-- footer_start -->"),
+            "{}",
+            created
+        );
+
+        assert!(apply(BodyEdit::ReplaceSection {
+            heading: "Missing".to_string(),
+            text: String::new(),
+        })
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_before_footer_and_frontmatter() -> Result<()> {
+        let edit = NoteEdit {
+            frontmatter: FrontmatterUpdate::new().set("status", "done"),
+            body: vec![BodyEdit::InsertBeforeFooter("Closing words".to_string())],
+        };
+        let updated = edit.apply(NOTE)?;
+        assert!(updated.starts_with("---\ntype: note\nstatus: done\n---\n# Title"));
+        assert!(updated.contains("- [[A]]\n\nClosing words\n\n<!-- footer_start -->"));
+        assert_ne!(content_version(&updated), content_version(NOTE));
+        assert_eq!(content_version(NOTE), content_version(NOTE));
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_edit_checks_version() -> Result<()> {
+        use crate::core::config::VaultSchema;
+        use crate::core::note::Note;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("Edited.md");
+        let original = "---\ntype: note\nstatus: active\narea: tech\ngist: Edited\n---\nBody\n";
+        std::fs::write(&path, original)?;
+        let schema = VaultSchema::default();
+
+        let mut note = Note::load(&path)?;
+        let version = note.version();
+        let done = NoteEdit {
+            frontmatter: FrontmatterUpdate::new().set("status", "done"),
+            ..NoteEdit::default()
+        };
+        let invalid = NoteEdit {
+            frontmatter: FrontmatterUpdate::new().set("status", "someday"),
+            ..NoteEdit::default()
+        };
+        assert!(note.apply_edit(&invalid, &version, &schema).is_err());
+        assert_eq!(std::fs::read_to_string(&path)?, original);
+
+        // Edited elsewhere after the version was read
+        std::fs::write(&path, original.replace("Body", "Changed in Obsidian"))?;
+        assert!(note.apply_edit(&done, &version, &schema).is_err());
+
+        let version = Note::load(&path)?.version();
+        assert!(note.apply_edit(&done, &version, &schema)?);
+        assert_eq!(note.status(), Some("done"));
+        assert!(note.content.contains("Changed in Obsidian"));
+        Ok(())
+    }
+}
@@ -1,15 +1,18 @@
+use anyhow::{bail, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde_yaml::{Mapping, Value};
 
-use super::schema::{SchemaViolation, VALID_AREAS, VALID_STATUS, VALID_TYPES};
+use super::config::VaultSchema;
+use super::schema::SchemaViolation;
 
 lazy_static! {
-    static ref FRONTMATTER_RE: Regex = Regex::new(r"(?s)^---\r?\n(.*?)\r?\n---").unwrap();
-    static ref TYPE_RE: Regex = Regex::new(r"(?m)^type:\s*(\w+)").unwrap();
-    static ref STATUS_RE: Regex = Regex::new(r"(?m)^status:\s*(\w+)").unwrap();
-    static ref AREA_RE: Regex = Regex::new(r"(?m)^area:\s*(\w+)").unwrap();
-    static ref GIST_RE: Regex = Regex::new(r"(?m)^gist:\s*(.*)").unwrap();
-    static ref TAGS_RE: Regex = Regex::new(r"(?m)^tags:\s*\[(.*?)\]").unwrap();
+    // The YAML is optional so an empty `---\n---` block still counts as frontmatter
+    static ref FRONTMATTER_RE: Regex =
+        Regex::new(r"(?s)^---\r?\n(?:(.*?)\r?\n)?---").unwrap();
+    // Top-level `key:` line (plain or quoted key)
+    static ref KEY_LINE_RE: Regex =
+        Regex::new(r#"^("[^"]*"|'[^']*'|[^\s#'"\-][^:]*?)\s*:(\s|$)"#).unwrap();
 }
 
 #[derive(Debug, Default, Clone)]
@@ -19,120 +22,141 @@ pub struct Frontmatter {
     pub area: Option<String>,
     pub gist: Option<String>,
     pub tags: Vec<String>,
+    /// Unknown keys, in document order
+    pub extra: Mapping,
     pub raw: String,
 }
 
 impl Frontmatter {
-    pub fn parse(content: &str) -> Option<Self> {
-        let caps = FRONTMATTER_RE.captures(content)?;
-        let raw = caps.get(1)?.as_str().to_string();
+    /// Parse the YAML frontmatter block at the start of `content`
+    ///
+    /// Returns `Ok(None)` when the note has no frontmatter block and
+    /// `Err(SchemaViolation::InvalidYaml)` when the block is not a YAML mapping.
+    pub fn parse(content: &str) -> Result<Option<Self>, SchemaViolation> {
+        let raw = match FRONTMATTER_RE.captures(content) {
+            Some(c) => c.get(1).map_or("", |m| m.as_str()).to_string(),
+            None => return Ok(None),
+        };
 
-        let note_type = TYPE_RE.captures(&raw).map(|c| c[1].to_string());
-        let status = STATUS_RE.captures(&raw).map(|c| c[1].to_string());
-        let area = AREA_RE.captures(&raw).map(|c| c[1].to_string());
-        let gist = Self::extract_gist(&raw);
-        let tags = Self::extract_tags(&raw);
+        let mut map = match serde_yaml::from_str::<Value>(&raw) {
+            Ok(Value::Mapping(map)) => map,
+            Ok(Value::Null) => Mapping::new(),
+            Ok(_) => {
+                return Err(SchemaViolation::InvalidYaml(
+                    "frontmatter is not a key-value mapping".to_string(),
+                ))
+            }
+            Err(e) => return Err(SchemaViolation::InvalidYaml(e.to_string())),
+        };
+
+        let note_type = map.shift_remove("type").and_then(scalar_to_string);
+        let status = map.shift_remove("status").and_then(scalar_to_string);
+        let area = map.shift_remove("area").and_then(scalar_to_string);
+        let gist = map.shift_remove("gist").and_then(scalar_to_string);
+        let tags = map
+            .shift_remove("tags")
+            .map(value_to_tags)
+            .unwrap_or_default();
 
-        Some(Self {
+        Ok(Some(Self {
             note_type,
             status,
             area,
             gist,
             tags,
+            extra: map,
             raw,
-        })
-    }
-
-    fn extract_gist(raw: &str) -> Option<String> {
-        if let Some(caps) = GIST_RE.captures(raw) {
-            let gist_start = caps.get(1)?.as_str().trim();
-
-            if gist_start == ">" || gist_start == "|" || gist_start.is_empty() {
-                let lines: Vec<&str> = raw.lines().collect();
-                let gist_line_idx = lines.iter().position(|l| l.starts_with("gist:"))?;
-
-                let mut folded_content = Vec::new();
-                for line in lines.iter().skip(gist_line_idx + 1) {
-                    if line.starts_with(' ') || line.starts_with('\t') {
-                        folded_content.push(line.trim());
-                    } else if line.trim().is_empty() {
-                        continue;
-                    } else {
-                        break;
-                    }
-                }
+        }))
+    }
 
-                let gist = folded_content.join(" ");
-                if gist.is_empty() {
-                    None
-                } else {
-                    Some(gist)
-                }
-            } else {
-                let gist = gist_start.trim_matches('"').trim_matches('\'').to_string();
-                if gist.is_empty() {
-                    None
-                } else {
-                    Some(gist)
+    /// Look up an arbitrary key that has no typed field
+    pub fn get_extra(&self, key: &str) -> Option<&Value> {
+        self.extra.get(key)
+    }
+
+    /// Alternative names from `aliases` (or the older singular `alias`)
+    pub fn aliases(&self) -> Vec<String> {
+        ["aliases", "alias"]
+            .iter()
+            .filter_map(|key| self.extra.get(*key))
+            .flat_map(|v| value_to_tags(v.clone()))
+            .collect()
+    }
+
+    /// String value of `field`, looking in `extra` for non-typed keys
+    pub fn field_value(&self, field: &str) -> Option<String> {
+        match field {
+            "type" => self.note_type.clone(),
+            "status" => self.status.clone(),
+            "area" => self.area.clone(),
+            "gist" => self.gist.clone(),
+            "tags" => (!self.tags.is_empty()).then(|| self.tags.join(", ")),
+            _ => self.extra.get(field).cloned().and_then(scalar_to_string),
+        }
+    }
+
+    /// Values of `field` to check against its allowed values: one per item
+    /// for list fields such as `tags`
+    fn field_values(&self, field: &str) -> Vec<String> {
+        match field {
+            "tags" => self.tags.clone(),
+            "type" | "status" | "area" | "gist" => self.field_value(field).into_iter().collect(),
+            _ => match self.extra.get(field).cloned() {
+                Some(Value::Sequence(items)) => {
+                    items.into_iter().filter_map(scalar_to_string).collect()
                 }
-            }
-        } else {
-            None
+                Some(value) => scalar_to_string(value).into_iter().collect(),
+                None => Vec::new(),
+            },
         }
     }
 
-    fn extract_tags(raw: &str) -> Vec<String> {
-        TAGS_RE
-            .captures(raw)
-            .map(|c| {
-                c[1].split(',')
-                    .map(|t| t.trim().trim_matches('"').trim_matches('\'').to_string())
-                    .filter(|t| !t.is_empty())
-                    .collect()
-            })
-            .unwrap_or_default()
+    fn has_field(&self, field: &str) -> bool {
+        match field {
+            "tags" => !self.tags.is_empty(),
+            "type" | "status" | "area" | "gist" => self.field_value(field).is_some(),
+            _ => self.extra.get(field).is_some_and(|v| !v.is_null()),
+        }
     }
 
-    pub fn validate(&self) -> Vec<SchemaViolation> {
+    pub fn validate(&self, schema: &VaultSchema) -> Vec<SchemaViolation> {
         let mut violations = Vec::new();
 
-        match &self.note_type {
-            None => violations.push(SchemaViolation::MissingField("type".to_string())),
-            Some(t) if !VALID_TYPES.contains(t.as_str()) => {
-                violations.push(SchemaViolation::InvalidType(t.clone()))
+        for field in schema.required_for(self.note_type.as_deref()) {
+            if !self.has_field(field) {
+                violations.push(SchemaViolation::MissingField(field.to_string()));
             }
-            _ => {}
         }
 
-        match &self.status {
-            None => violations.push(SchemaViolation::MissingField("status".to_string())),
-            Some(s) if !VALID_STATUS.contains(s.as_str()) => {
-                violations.push(SchemaViolation::InvalidStatus(s.clone()))
+        for field in schema.allowed_values.keys() {
+            let Some(allowed) = schema.allowed(field) else {
+                continue;
+            };
+            for value in self.field_values(field) {
+                if !allowed.contains(&value) {
+                    violations.push(SchemaViolation::InvalidValue {
+                        field: field.clone(),
+                        value,
+                        allowed: allowed.to_vec(),
+                    });
+                }
             }
-            _ => {}
         }
 
-        match &self.area {
-            None => violations.push(SchemaViolation::MissingField("area".to_string())),
-            Some(a) if !VALID_AREAS.contains(a.as_str()) => {
-                violations.push(SchemaViolation::InvalidArea(a.clone()))
+        if let Some(max) = schema.tags.max {
+            if self.tags.len() > max {
+                violations.push(SchemaViolation::TooManyTags {
+                    count: self.tags.len(),
+                    max,
+                });
             }
-            _ => {}
-        }
-
-        if self.gist.is_none() {
-            violations.push(SchemaViolation::MissingField("gist".to_string()));
-        }
-
-        if self.tags.len() > 5 {
-            violations.push(SchemaViolation::TooManyTags(self.tags.len()));
         }
 
         for tag in &self.tags {
-            if tag.contains('/') {
+            if !schema.tags.allow_hierarchy && tag.contains('/') {
                 violations.push(SchemaViolation::HierarchicalTag(tag.clone()));
             }
-            if tag != &tag.to_lowercase() {
+            if schema.tags.lowercase && tag != &tag.to_lowercase() {
                 violations.push(SchemaViolation::NonLowercaseTag(tag.clone()));
             }
         }
@@ -140,3 +164,415 @@ impl Frontmatter {
         violations
     }
 }
+
+/// Byte offset where the note body starts, just past the frontmatter block
+pub fn body_offset(content: &str) -> usize {
+    match FRONTMATTER_RE.find(content) {
+        Some(m) => content[m.end()..]
+            .find('\n')
+            .map_or(content.len(), |i| m.end() + i + 1),
+        None => 0,
+    }
+}
+
+/// A set of key changes applied to a note's frontmatter in place
+///
+/// Only the lines belonging to changed keys are rewritten, so key order,
+/// comments, formatting of other keys and the note body are preserved.
+#[derive(Debug, Default, Clone)]
+pub struct FrontmatterUpdate {
+    changes: Vec<(String, Option<Value>)>,
+}
+
+impl FrontmatterUpdate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key` to `value`, appending the key if it does not exist yet
+    pub fn set(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.changes.push((key.to_string(), Some(value.into())));
+        self
+    }
+
+    pub fn set_tags<S: AsRef<str>>(self, tags: &[S]) -> Self {
+        let tags = tags
+            .iter()
+            .map(|t| Value::String(t.as_ref().to_string()))
+            .collect();
+        self.set("tags", Value::Sequence(tags))
+    }
+
+    /// Remove `key` (and any continuation lines) if present
+    pub fn remove(mut self, key: &str) -> Self {
+        self.changes.push((key.to_string(), None));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Apply the changes to a full note `content`, returning the new content
+    ///
+    /// Fails without producing output if the result would not parse.
+    pub fn apply(&self, content: &str) -> Result<String> {
+        let newline = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        // Byte range of the YAML lines; empty, just before the closing `---`,
+        // for an empty block
+        let block = FRONTMATTER_RE.captures(content).map(|c| match c.get(1) {
+            Some(m) => m.range(),
+            None => {
+                let end = c.get(0).map_or(0, |m| m.end()) - "---".len();
+                end..end
+            }
+        });
+
+        let mut lines: Vec<String> = block
+            .clone()
+            .map(|range| content[range].lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        for (key, value) in &self.changes {
+            let span = find_key_span(&lines, key);
+            match (value, span) {
+                (Some(value), Some((start, end))) => {
+                    let rendered = render_entry(key, value, &lines[start..end]);
+                    lines.splice(start..end, rendered);
+                }
+                (Some(value), None) => lines.extend(render_entry(key, value, &[])),
+                (None, Some((start, end))) => {
+                    lines.drain(start..end);
+                }
+                (None, None) => {}
+            }
+        }
+
+        let updated = match block {
+            Some(range) if range.is_empty() && lines.is_empty() => return Ok(content.to_string()),
+            Some(range) if range.is_empty() => format!(
+                "{}{}{}{}",
+                &content[..range.start],
+                lines.join(newline),
+                newline,
+                &content[range.start..]
+            ),
+            Some(range) => format!(
+                "{}{}{}",
+                &content[..range.start],
+                lines.join(newline),
+                &content[range.end..]
+            ),
+            None if lines.is_empty() => return Ok(content.to_string()),
+            None => format!(
+                "---{nl}{}{nl}---{nl}{}",
+                lines.join(newline),
+                content,
+                nl = newline
+            ),
+        };
+
+        if let Err(e) = Frontmatter::parse(&updated) {
+            bail!("Update would produce invalid frontmatter: {}", e);
+        }
+
+        Ok(updated)
+    }
+}
+
+/// Locate the line range `[start, end)` holding top-level `key`
+fn find_key_span(lines: &[String], key: &str) -> Option<(usize, usize)> {
+    let start = lines.iter().position(|line| {
+        KEY_LINE_RE
+            .captures(line)
+            .map(|c| c[1].trim_matches(|ch| ch == '"' || ch == '\'') == key)
+            .unwrap_or(false)
+    })?;
+
+    // Continuation lines are indented, block list items, or blank
+    let mut end = start + 1;
+    while end < lines.len() {
+        let line = &lines[end];
+        if line.trim().is_empty() || line.starts_with([' ', '\t', '-']) {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    while end > start + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+
+    Some((start, end))
+}
+
+/// Render `key: value` as frontmatter lines, reusing the style of `previous`
+fn render_entry(key: &str, value: &Value, previous: &[String]) -> Vec<String> {
+    let key = render_scalar(&Value::String(key.to_string()));
+    let previous_value = previous
+        .first()
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, v)| v.trim())
+        .unwrap_or("");
+    let continuation_indent = previous
+        .get(1)
+        .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+        .unwrap_or_else(|| "  ".to_string());
+
+    match value {
+        Value::Null => vec![format!("{}:", key)],
+        Value::Sequence(items) if items.iter().all(is_scalar) => {
+            let block_style = previous
+                .get(1)
+                .map(|line| line.trim_start().starts_with('-'))
+                .unwrap_or(false);
+            if block_style && !items.is_empty() {
+                std::iter::once(format!("{}:", key))
+                    .chain(
+                        items.iter().map(|item| {
+                            format!("{}- {}", continuation_indent, render_scalar(item))
+                        }),
+                    )
+                    .collect()
+            } else {
+                let items: Vec<String> = items.iter().map(render_scalar).collect();
+                vec![format!("{}: [{}]", key, items.join(", "))]
+            }
+        }
+        Value::String(s)
+            if previous_value.starts_with(['>', '|'])
+                && previous.len() > 1
+                && !s.trim().is_empty() =>
+        {
+            // Keep folded/literal block scalars such as `gist: >`; an empty
+            // value falls through to `key: ''` so no header is left dangling
+            std::iter::once(format!("{}: {}", key, previous_value))
+                .chain(
+                    s.lines()
+                        .map(|line| format!("{}{}", continuation_indent, line)),
+                )
+                .collect()
+        }
+        v if is_scalar(v) => {
+            let mut line = format!("{}: {}", key, render_scalar(v));
+            if previous.len() == 1 {
+                if let Some(comment) = inline_comment(previous_value) {
+                    line = format!("{} {}", line, comment);
+                }
+            }
+            line.lines().map(String::from).collect()
+        }
+        v => {
+            let nested = serde_yaml::to_string(v).unwrap_or_default();
+            std::iter::once(format!("{}:", key))
+                .chain(nested.lines().map(|line| format!("  {}", line)))
+                .collect()
+        }
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::String(_) | Value::Number(_) | Value::Bool(_) | Value::Null
+    )
+}
+
+fn render_scalar(value: &Value) -> String {
+    serde_yaml::to_string(value)
+        .map(|s| s.trim_end().to_string())
+        .unwrap_or_default()
+}
+
+/// Extract a trailing `# comment` that sits outside any quotes
+fn inline_comment(value: &str) -> Option<&str> {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, ch) in value.char_indices() {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() => return Some(&value[i..]),
+            _ => {}
+        }
+        prev = ch;
+    }
+    None
+}
+
+/// Convert a scalar YAML value to a trimmed string, treating null/empty as absent
+fn scalar_to_string(value: Value) -> Option<String> {
+    let s = match value {
+        Value::String(s) => s,
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        _ => return None,
+    };
+    let s = s.trim();
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+/// Accept both `[a, b]` / block lists and a single `a, b` string
+fn value_to_tags(value: Value) -> Vec<String> {
+    match value {
+        Value::Sequence(items) => items.into_iter().filter_map(scalar_to_string).collect(),
+        Value::String(s) => s
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        other => scalar_to_string(other).into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_block_tags_and_quoted_values() {
+        let content = "---\ntype: \"note\"\nstatus: 'active'\narea: tech\ngist: >\n  First line\n  second line\ntags:\n  - gpu\n  - memory\naliases: [k8s]\n---\nBody";
+        let fm = Frontmatter::parse(content).unwrap().unwrap();
+
+        assert_eq!(fm.note_type.as_deref(), Some("note"));
+        assert_eq!(fm.status.as_deref(), Some("active"));
+        assert_eq!(fm.gist.as_deref(), Some("First line second line"));
+        assert_eq!(fm.tags, vec!["gpu", "memory"]);
+        assert!(fm.get_extra("aliases").is_some());
+        assert_eq!(fm.aliases(), vec!["k8s"]);
+        assert!(fm.validate(&VaultSchema::default()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_with_custom_schema() {
+        let content =
+            "---\ntype: meeting\nstatus: active\narea: ops\ngist: Sync\ntags: [Team/Ops]\n---\n";
+        let fm = Frontmatter::parse(content).unwrap().unwrap();
+
+        let default_violations = fm.validate(&VaultSchema::default());
+        assert_eq!(default_violations.len(), 4);
+
+        let mut schema = VaultSchema::default();
+        schema
+            .allowed_values
+            .insert("type".to_string(), vec!["meeting".to_string()]);
+        schema.allowed_values.insert("area".to_string(), Vec::new());
+        schema
+            .type_required_fields
+            .insert("meeting".to_string(), vec!["date".to_string()]);
+        schema.tags.lowercase = false;
+        schema.tags.allow_hierarchy = true;
+
+        assert_eq!(
+            fm.validate(&schema),
+            vec![SchemaViolation::MissingField("date".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_validate_list_fields_per_item() {
+        let content = "---\ntype: note\nstatus: active\narea: tech\ngist: G\ntags: [rust, cli]\nteams: [infra, web]\n---\n";
+        let fm = Frontmatter::parse(content).unwrap().unwrap();
+
+        let mut schema = VaultSchema::default();
+        schema.allowed_values.insert(
+            "tags".to_string(),
+            vec!["rust".to_string(), "cli".to_string()],
+        );
+        schema
+            .allowed_values
+            .insert("teams".to_string(), vec!["infra".to_string()]);
+
+        assert_eq!(
+            fm.validate(&schema),
+            vec![SchemaViolation::InvalidValue {
+                field: "teams".to_string(),
+                value: "web".to_string(),
+                allowed: vec!["infra".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_flow_tags() {
+        let content = "---\ntype: note\ntags: [a, \"b\", 2024]\n---\n";
+        let fm = Frontmatter::parse(content).unwrap().unwrap();
+        assert_eq!(fm.tags, vec!["a", "b", "2024"]);
+    }
+
+    #[test]
+    fn test_parse_missing_and_invalid() {
+        assert!(Frontmatter::parse("# No frontmatter").unwrap().is_none());
+
+        let err = Frontmatter::parse("---\ntype: [unclosed\n---\n").unwrap_err();
+        assert!(matches!(err, SchemaViolation::InvalidYaml(_)));
+    }
+
+    #[test]
+    fn test_update_preserves_layout() {
+        let content = "---\n# managed by obsidian\ntype: note\nstatus: active # wip\ngist: >\n  Old gist\ntags:\n  - a\n  - b\ncustom: keep\n---\nBody\n\n  stays   as-is\n";
+        let updated = FrontmatterUpdate::new()
+            .set("status", "done")
+            .set("gist", "New gist")
+            .set_tags(&["a", "c"])
+            .remove("custom")
+            .set("area", "tech")
+            .apply(content)
+            .unwrap();
+
+        assert_eq!(
+            updated,
+            "---\n# managed by obsidian\ntype: note\nstatus: done # wip\ngist: >\n  New gist\ntags:\n  - a\n  - c\narea: tech\n---\nBody\n\n  stays   as-is\n"
+        );
+    }
+
+    #[test]
+    fn test_update_without_frontmatter() {
+        let updated = FrontmatterUpdate::new()
+            .set("type", "note")
+            .set_tags(&["x"])
+            .apply("Body\r\n")
+            .unwrap();
+        assert_eq!(updated, "---\r\ntype: note\r\ntags: [x]\r\n---\r\nBody\r\n");
+
+        let untouched = FrontmatterUpdate::new()
+            .remove("type")
+            .apply("Body")
+            .unwrap();
+        assert_eq!(untouched, "Body");
+    }
+
+    #[test]
+    fn test_update_empty_block_and_emptied_folded_value() {
+        let updated = FrontmatterUpdate::new()
+            .set("type", "note")
+            .apply("---\n---\nBody\n")
+            .unwrap();
+        assert_eq!(updated, "---\ntype: note\n---\nBody\n");
+        assert!(Frontmatter::parse("---\n---\nBody").unwrap().is_some());
+        assert_eq!(
+            FrontmatterUpdate::new()
+                .remove("type")
+                .apply("---\n---\nBody\n")
+                .unwrap(),
+            "---\n---\nBody\n"
+        );
+
+        let updated = FrontmatterUpdate::new()
+            .set("gist", "")
+            .apply("---\ngist: >\n  Old gist\ntype: note\n---\nBody\n")
+            .unwrap();
+        assert_eq!(updated, "---\ngist: ''\ntype: note\n---\nBody\n");
+        let fm = Frontmatter::parse(&updated).unwrap().unwrap();
+        assert_eq!(fm.gist, None);
+        assert_eq!(fm.note_type.as_deref(), Some("note"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Ignore rules for note discovery
+//!
+//! Combines `.gitignore`-style patterns from `discovery.exclude` in the vault
+//! config with Obsidian's `userIgnoreFilters` from `.obsidian/app.json`.
+
+use std::fs;
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+
+use super::paths::VaultPaths;
+
+const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug)]
+enum Rule {
+    /// gitignore-style glob; unanchored globs match the entry's file name
+    Glob {
+        pattern: Pattern,
+        anchored: bool,
+        dir_only: bool,
+        negated: bool,
+    },
+    /// Obsidian path-prefix filter
+    Prefix(String),
+    /// Obsidian `/regex/` filter
+    Regex(Regex),
+}
+
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// Rules configured for the vault at `paths`
+    pub fn for_vault(paths: &VaultPaths) -> Self {
+        let discovery = &paths.config.discovery;
+        let mut rules = Self::from_patterns(&discovery.exclude);
+        if discovery.obsidian_ignore {
+            rules.add_obsidian_filters(&paths.root);
+        }
+        rules
+    }
+
+    /// Parse `.gitignore`-style patterns; invalid globs are skipped
+    pub fn from_patterns<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let rules = patterns
+            .iter()
+            .filter_map(|p| parse_glob_rule(p.as_ref()))
+            .collect();
+        Self { rules }
+    }
+
+    fn add_obsidian_filters(&mut self, root: &Path) {
+        let Ok(content) = fs::read_to_string(root.join(".obsidian/app.json")) else {
+            return;
+        };
+        let Ok(app) = serde_json::from_str::<serde_json::Value>(&content) else {
+            return;
+        };
+        let filters = app
+            .get("userIgnoreFilters")
+            .and_then(|f| f.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|f| f.as_str());
+
+        for filter in filters {
+            if filter.len() > 2 && filter.starts_with('/') && filter.ends_with('/') {
+                if let Ok(re) = Regex::new(&filter[1..filter.len() - 1]) {
+                    self.rules.push(Rule::Regex(re));
+                }
+            } else if !filter.is_empty() {
+                self.rules.push(Rule::Prefix(filter.to_string()));
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether `rel_path` (relative to the vault root) is excluded.
+    /// The last matching glob wins, so `!pattern` can re-include entries.
+    pub fn is_ignored(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let path = rel_path.to_string_lossy().replace('\\', "/");
+        let name = path.rsplit('/').next().unwrap_or(&path);
+
+        let mut ignored = false;
+        for rule in &self.rules {
+            match rule {
+                Rule::Glob {
+                    pattern,
+                    anchored,
+                    dir_only,
+                    negated,
+                } => {
+                    if *dir_only && !is_dir {
+                        continue;
+                    }
+                    let target = if *anchored { path.as_str() } else { name };
+                    if pattern.matches_with(target, GLOB_OPTIONS) {
+                        ignored = !negated;
+                    }
+                }
+                Rule::Prefix(prefix) => {
+                    if path.starts_with(prefix.as_str())
+                        || (is_dir && format!("{}/", path) == *prefix)
+                    {
+                        return true;
+                    }
+                }
+                Rule::Regex(re) => {
+                    if re.is_match(&path) {
+                        return true;
+                    }
+                }
+            }
+        }
+        ignored
+    }
+}
+
+fn parse_glob_rule(line: &str) -> Option<Rule> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let line = line.trim_start_matches('/');
+
+    Pattern::new(line).ok().map(|pattern| Rule::Glob {
+        pattern,
+        anchored,
+        dir_only,
+        negated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitignore_style_patterns() {
+        let rules = IgnoreRules::from_patterns(&[
+            "*.excalidraw.md",
+            "drafts/",
+            "/Notes/private/**",
+            "!keep.excalidraw.md",
+        ]);
+
+        assert!(rules.is_ignored(Path::new("Notes/a/b.excalidraw.md"), false));
+        assert!(!rules.is_ignored(Path::new("Notes/keep.excalidraw.md"), false));
+        assert!(rules.is_ignored(Path::new("Projects/drafts"), true));
+        assert!(!rules.is_ignored(Path::new("Projects/drafts"), false));
+        assert!(rules.is_ignored(Path::new("Notes/private/secret.md"), false));
+        assert!(!rules.is_ignored(Path::new("Archive/Notes/private/x.md"), false));
+        assert!(!rules.is_ignored(Path::new("Notes/ok.md"), false));
+    }
+
+    #[test]
+    fn test_obsidian_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".obsidian")).unwrap();
+        fs::write(
+            dir.path().join(".obsidian/app.json"),
+            r#"{"userIgnoreFilters": ["Archive/", "/\\.tmp\\.md$/"]}"#,
+        )
+        .unwrap();
+
+        let mut rules = IgnoreRules::default();
+        rules.add_obsidian_filters(dir.path());
+
+        assert!(rules.is_ignored(Path::new("Archive"), true));
+        assert!(rules.is_ignored(Path::new("Archive/old.md"), false));
+        assert!(rules.is_ignored(Path::new("Notes/x.tmp.md"), false));
+        assert!(!rules.is_ignored(Path::new("Notes/x.md"), false));
+    }
+}
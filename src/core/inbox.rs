@@ -0,0 +1,315 @@
+//! Quick-capture inbox (`inbox.md`)
+//!
+//! Memos are separated by `---` lines. Captured memos start with their
+//! capture time in bold. Frontmatter and a `# ` title (e.g. `# Inbox`)
+//! before the first memo are kept as the preamble.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+use super::edit::content_version;
+use super::frontmatter::body_offset;
+use super::paths::VaultPaths;
+use super::template::NewNote;
+
+lazy_static! {
+    static ref SEPARATOR_RE: Regex = Regex::new(r"(?m)^---[ \t]*\r?$").unwrap();
+    static ref CAPTURED_RE: Regex =
+        Regex::new(r"^\*\*(\d{4}-\d{2}-\d{2} \d{2}:\d{2})\*\*$").unwrap();
+}
+
+/// One memo in the inbox
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Memo {
+    /// 1-based position in the inbox
+    pub index: usize,
+    /// Capture time (`YYYY-MM-DD HH:MM`) for memos added by `capture`
+    pub captured: Option<String>,
+    pub text: String,
+    /// Hash of the text, which `promote` checks before removing the memo
+    pub hash: String,
+}
+
+impl Memo {
+    fn new(index: usize, captured: Option<String>, text: String) -> Self {
+        Self {
+            index,
+            captured,
+            hash: content_version(&text),
+            text,
+        }
+    }
+}
+
+/// The parsed inbox file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inbox {
+    preamble: String,
+    pub memos: Vec<Memo>,
+}
+
+impl Inbox {
+    /// Load the inbox at `path`; a missing file is an empty inbox
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let memos_start = memos_offset(content);
+        let preamble = content[..memos_start].to_string();
+
+        let memos = SEPARATOR_RE
+            .split(&content[memos_start..])
+            .map(str::trim)
+            .filter(|chunk| !chunk.is_empty())
+            .enumerate()
+            .map(|(i, chunk)| {
+                let (first, rest) = chunk.split_once('\n').unwrap_or((chunk, ""));
+                match CAPTURED_RE.captures(first.trim()) {
+                    Some(caps) => {
+                        Memo::new(i + 1, Some(caps[1].to_string()), rest.trim().to_string())
+                    }
+                    None => Memo::new(i + 1, None, chunk.to_string()),
+                }
+            })
+            .collect();
+
+        Self { preamble, memos }
+    }
+
+    pub fn memo(&self, index: usize) -> Option<&Memo> {
+        index.checked_sub(1).and_then(|i| self.memos.get(i))
+    }
+
+    /// File content, one `---` line after each memo
+    pub fn render(&self) -> String {
+        let mut content = self.preamble.trim_end().to_string();
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        for memo in &self.memos {
+            content.push_str(&render_memo(memo.captured.as_deref(), &memo.text));
+        }
+        content
+    }
+
+    /// Remove the memo at 1-based `index`, renumbering the rest
+    pub fn remove(&mut self, index: usize) -> Option<Memo> {
+        if index == 0 || index > self.memos.len() {
+            return None;
+        }
+        let memo = self.memos.remove(index - 1);
+        for (i, memo) in self.memos.iter_mut().enumerate() {
+            memo.index = i + 1;
+        }
+        Some(memo)
+    }
+}
+
+/// Append a timestamped memo to the vault's inbox, creating it if needed
+///
+/// The file is only appended to, so edits made to it meanwhile are kept.
+pub fn capture(paths: &VaultPaths, text: &str) -> Result<Memo> {
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("Memo is empty");
+    }
+    let captured = Local::now().format("%Y-%m-%d %H:%M").to_string();
+    let existing = fs::read_to_string(&paths.inbox).unwrap_or_default();
+    // Close a trailing memo that has no separator yet
+    let unterminated = SEPARATOR_RE
+        .split(&existing[memos_offset(&existing)..])
+        .last()
+        .is_some_and(|last| !last.trim().is_empty());
+    let lead = if unterminated {
+        "\n\n---\n\n"
+    } else if existing.is_empty() || existing.ends_with("\n\n") {
+        ""
+    } else if existing.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+
+    if let Some(parent) = paths.inbox.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&paths.inbox)
+        .with_context(|| format!("Failed to open {}", paths.inbox.display()))?;
+    file.write_all(format!("{}{}", lead, render_memo(Some(&captured), text)).as_bytes())?;
+
+    Ok(Memo::new(
+        Inbox::parse(&existing).memos.len() + 1,
+        Some(captured),
+        text.to_string(),
+    ))
+}
+
+/// Turn memo `index` into a new note with the memo as its body, then remove
+/// the memo from the inbox
+///
+/// `hash` is the memo's hash as listed; if the memo at `index` has another
+/// one (the inbox was edited or renumbered meanwhile), nothing is done.
+/// `note.body` is replaced by the memo text. Nothing is removed if the note
+/// cannot be created.
+pub fn promote(paths: &VaultPaths, index: usize, hash: &str, note: NewNote) -> Result<PathBuf> {
+    let memo = listed_memo(&Inbox::load(&paths.inbox)?, index, hash)?.clone();
+    let path = NewNote {
+        body: Some(memo.text),
+        ..note
+    }
+    .create(paths)?;
+
+    // Re-read the inbox so memos captured while the note was written are kept
+    let mut inbox = Inbox::load(&paths.inbox)?;
+    listed_memo(&inbox, index, hash).with_context(|| {
+        format!(
+            "Created {} but the inbox changed meanwhile; remove the memo by hand",
+            path.display()
+        )
+    })?;
+    inbox.remove(index);
+    fs::write(&paths.inbox, inbox.render())
+        .with_context(|| format!("Created {} but failed to update the inbox", path.display()))?;
+    Ok(path)
+}
+
+/// Memo `index`, provided it still has the listed `hash`
+fn listed_memo<'a>(inbox: &'a Inbox, index: usize, hash: &str) -> Result<&'a Memo> {
+    let Some(memo) = inbox.memo(index) else {
+        bail!(
+            "No memo {} in the inbox ({} memos)",
+            index,
+            inbox.memos.len()
+        );
+    };
+    if memo.hash != hash {
+        bail!(
+            "Memo {} changed since it was listed (hash {}, expected {}); list the inbox again",
+            index,
+            memo.hash,
+            hash
+        );
+    }
+    Ok(memo)
+}
+
+/// Byte offset of the first memo, past any frontmatter and one `# ` title
+fn memos_offset(content: &str) -> usize {
+    let mut offset = body_offset(content);
+    let mut titled = false;
+    for line in content[offset..].split_inclusive('\n') {
+        if line.starts_with("# ") && !titled {
+            titled = true;
+        } else if !line.trim().is_empty() {
+            break;
+        }
+        offset += line.len();
+    }
+    offset
+}
+
+fn render_memo(captured: Option<&str>, text: &str) -> String {
+    match captured {
+        Some(captured) => format!("**{}**\n\n{}\n\n---\n\n", captured, text.trim()),
+        None => format!("{}\n\n---\n\n", text.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_render() {
+        let content = "# Inbox\n\n**2024-05-01 09:30**\n\nCall the bank\n\n---\n\nRead about [[CRDT]]s\nand sync\n---\n";
+        let mut inbox = Inbox::parse(content);
+        assert_eq!(
+            inbox.memos,
+            vec![
+                Memo::new(
+                    1,
+                    Some("2024-05-01 09:30".to_string()),
+                    "Call the bank".to_string()
+                ),
+                Memo::new(2, None, "Read about [[CRDT]]s\nand sync".to_string()),
+            ]
+        );
+
+        assert_eq!(inbox.remove(1).unwrap().text, "Call the bank");
+        assert_eq!(inbox.memos[0].index, 1);
+        assert_eq!(
+            inbox.render(),
+            "# Inbox\n\nRead about [[CRDT]]s\nand sync\n\n---\n\n"
+        );
+        assert_eq!(Inbox::parse(&inbox.render()), inbox);
+        assert!(Inbox::parse("").memos.is_empty());
+
+        // Only one title is preamble; hashtags and later headings are memos
+        let inbox = Inbox::parse("# Inbox\n\n#idea CRDT sync\n---\n# Later\n");
+        let texts: Vec<&str> = inbox.memos.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["#idea CRDT sync", "# Later"]);
+    }
+
+    #[test]
+    fn test_capture_and_promote() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let paths = VaultPaths::from_root(root.path().to_path_buf());
+        fs::write(&paths.inbox, "# Inbox\nFirst thought")?;
+
+        assert_eq!(capture(&paths, "  Second thought  ")?.index, 2);
+        let inbox = Inbox::load(&paths.inbox)?;
+        assert_eq!(inbox.memos.len(), 2);
+        assert_eq!(inbox.memos[1].text, "Second thought");
+        assert!(inbox.memos[1].captured.is_some());
+        assert!(capture(&paths, " ").is_err());
+
+        let note = NewNote {
+            title: "Second Thought".to_string(),
+            note_type: "note".to_string(),
+            area: Some("life".to_string()),
+            gist: Some("A second thought".to_string()),
+            ..NewNote::default()
+        };
+        let hash = inbox.memos[1].hash.clone();
+        assert!(promote(&paths, 3, &hash, note.clone()).is_err());
+        let invalid = NewNote {
+            area: None,
+            ..note.clone()
+        };
+        assert!(promote(&paths, 2, &hash, invalid).is_err());
+        // The listed memo is no longer at that index
+        let err = promote(&paths, 1, &hash, note.clone()).unwrap_err();
+        assert!(
+            err.to_string().contains("changed since it was listed"),
+            "{}",
+            err
+        );
+        assert_eq!(Inbox::load(&paths.inbox)?.memos.len(), 2);
+        assert!(!paths.notes.join("Second Thought.md").exists());
+
+        let path = promote(&paths, 2, &hash, note)?;
+        assert_eq!(path, paths.notes.join("Second Thought.md"));
+        assert!(fs::read_to_string(&path)?.ends_with("---\n\nSecond thought\n"));
+        let inbox = Inbox::load(&paths.inbox)?;
+        assert_eq!(inbox.memos.len(), 1);
+        assert_eq!(inbox.memos[0].text, "First thought");
+        assert!(fs::read_to_string(&paths.inbox)?.starts_with("# Inbox\n\nFirst thought\n\n---\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+//! Lightweight Markdown structure helpers
+//!
+//! Just enough of the grammar to find code regions, headings and block ids;
+//! not a full CommonMark parser.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref HEADING_RE: Regex = Regex::new(r"^(#{1,6})[ \t]+(.*?)[ \t#]*$").unwrap();
+    static ref BLOCK_ID_RE: Regex = Regex::new(r"(?:^|\s)\^([A-Za-z0-9-]+)[ \t]*$").unwrap();
+    // [text](url "title") or ![alt](<url with spaces>)
+    static ref MD_LINK_RE: Regex =
+        Regex::new(r#"(!?)\[([^\]\n]*)\]\(\s*(<[^>\n]*>|[^)\s]+)(?:\s+"[^"\n]*")?\s*\)"#).unwrap();
+    static ref URL_SCHEME_RE: Regex = Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]*:").unwrap();
+}
+
+/// A Markdown ATX heading
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+    pub text: String,
+    /// Byte offset of the heading line
+    pub offset: usize,
+}
+
+/// A standard Markdown `[text](url)` link or `![alt](url)` image
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownLink {
+    pub text: String,
+    /// Destination as written, without surrounding `<>`
+    pub url: String,
+    pub is_image: bool,
+    pub span: Range<usize>,
+}
+
+impl MarkdownLink {
+    /// Whether the destination is a URL (`https:`, `mailto:`, `obsidian:`, ...)
+    pub fn is_external(&self) -> bool {
+        URL_SCHEME_RE.is_match(&self.url)
+    }
+
+    /// Percent-decoded file path without `#fragment` or `?query`; None for
+    /// external and same-document links
+    pub fn path(&self) -> Option<String> {
+        if self.is_external() {
+            return None;
+        }
+        let path = self.url.split(['#', '?']).next().unwrap_or("");
+        if path.is_empty() {
+            return None;
+        }
+        Some(percent_decode(path))
+    }
+}
+
+/// Markdown links and images outside code blocks
+pub fn markdown_links(content: &str) -> Vec<MarkdownLink> {
+    let code = code_ranges(content);
+    MD_LINK_RE
+        .captures_iter(content)
+        .filter_map(|c| {
+            let whole = c.get(0)?;
+            if in_ranges(&code, whole.start() + c[1].len()) {
+                return None;
+            }
+            let url = c[3].trim_start_matches('<').trim_end_matches('>');
+            Some(MarkdownLink {
+                text: c[2].to_string(),
+                url: url.to_string(),
+                is_image: !c[1].is_empty(),
+                span: whole.range(),
+            })
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes; malformed escapes are kept as-is
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(hi), Some(lo)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Byte ranges of fenced code blocks and inline code spans
+pub fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut fence: Option<(char, usize, usize)> = None; // (char, len, start)
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start_matches(' ');
+        let indent = line.len() - trimmed.len();
+
+        if let Some((ch, len, start)) = fence {
+            let run = trimmed.chars().take_while(|&c| c == ch).count();
+            if indent < 4 && run >= len && trimmed[run..].trim().is_empty() {
+                ranges.push(start..offset);
+                fence = None;
+            }
+            continue;
+        }
+
+        if indent < 4 {
+            if let Some(ch) = trimmed.chars().next().filter(|c| *c == '`' || *c == '~') {
+                let run = trimmed.chars().take_while(|&c| c == ch).count();
+                if run >= 3 {
+                    fence = Some((ch, run, line_start));
+                    continue;
+                }
+            }
+        }
+
+        ranges.extend(
+            inline_code_ranges(line)
+                .into_iter()
+                .map(|r| r.start + line_start..r.end + line_start),
+        );
+    }
+
+    // An unclosed fence runs to the end of the document
+    if let Some((_, _, start)) = fence {
+        ranges.push(start..content.len());
+    }
+
+    ranges
+}
+
+/// Inline code spans within a single line (matching backtick runs)
+fn inline_code_ranges(line: &str) -> Vec<Range<usize>> {
+    let bytes = line.as_bytes();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i] == b'`' {
+            i += 1;
+        }
+        let run = i - start;
+
+        // Find a closing run of exactly the same length
+        let mut j = i;
+        let mut closed = None;
+        while j < bytes.len() {
+            if bytes[j] == b'`' {
+                let close_start = j;
+                while j < bytes.len() && bytes[j] == b'`' {
+                    j += 1;
+                }
+                if j - close_start == run {
+                    closed = Some(j);
+                    break;
+                }
+            } else {
+                j += 1;
+            }
+        }
+
+        if let Some(end) = closed {
+            ranges.push(start..end);
+            i = end;
+        }
+    }
+
+    ranges
+}
+
+pub fn in_ranges(ranges: &[Range<usize>], pos: usize) -> bool {
+    ranges.iter().any(|r| r.contains(&pos))
+}
+
+/// ATX headings outside code blocks
+pub fn headings(content: &str) -> Vec<Heading> {
+    let code = code_ranges(content);
+    let mut result = Vec::new();
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        if in_ranges(&code, line_start) {
+            continue;
+        }
+        if let Some(caps) = HEADING_RE.captures(line.trim_end_matches(['\r', '\n'])) {
+            result.push(Heading {
+                level: caps[1].len(),
+                text: caps[2].trim().to_string(),
+                offset: line_start,
+            });
+        }
+    }
+
+    result
+}
+
+/// Block ids (`^block-id` at the end of a line) outside code blocks
+pub fn block_ids(content: &str) -> HashSet<String> {
+    let code = code_ranges(content);
+    let mut ids = HashSet::new();
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        if in_ranges(&code, line_start) {
+            continue;
+        }
+        if let Some(caps) = BLOCK_ID_RE.captures(line.trim_end_matches(['\r', '\n'])) {
+            ids.insert(caps[1].to_string());
+        }
+    }
+
+    ids
+}
+
+/// Normalize heading text the way Obsidian compares heading links:
+/// case-insensitive, with link-breaking characters treated as spaces
+pub fn normalize_heading(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '#' | '|' | '^' | ':' | '%' | '[' | ']' => ' ',
+            c => c,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_ranges_and_headings() {
+        let content = "# Title\nText `[[inline]]` here\n```rust\n# not a heading\n```\n## Section ##\nLine ^abc-1\n";
+        let code = code_ranges(content);
+
+        let inline = content.find("[[inline]]").unwrap();
+        assert!(in_ranges(&code, inline));
+        assert!(in_ranges(&code, content.find("# not").unwrap()));
+        assert!(!in_ranges(&code, content.find("## Section").unwrap()));
+
+        let texts: Vec<_> = headings(content).into_iter().map(|h| h.text).collect();
+        assert_eq!(texts, vec!["Title", "Section"]);
+        assert!(block_ids(content).contains("abc-1"));
+    }
+
+    #[test]
+    fn test_markdown_links() {
+        let content = "[Other](Other%20Note.md#Intro) ![img](<../a b.png> \"t\") [web](https://x.y) `[c](d.md)`";
+        let links = markdown_links(content);
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].path().as_deref(), Some("Other Note.md"));
+        assert!(links[1].is_image);
+        assert_eq!(links[1].path().as_deref(), Some("../a b.png"));
+        assert!(links[2].is_external() && links[2].path().is_none());
+    }
+}
@@ -1,5 +1,12 @@
+pub mod attachment;
+pub mod config;
+pub mod edit;
 pub mod frontmatter;
+pub mod ignore;
+pub mod inbox;
+pub mod markdown;
 pub mod note;
 pub mod paths;
 pub mod schema;
+pub mod template;
 pub mod wikilink;
@@ -2,19 +2,25 @@ use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use chrono::{DateTime, Local};
+use walkdir::WalkDir;
 
-use super::frontmatter::Frontmatter;
+use super::config::VaultSchema;
+use super::edit::{content_version, NoteEdit};
+use super::frontmatter::{Frontmatter, FrontmatterUpdate};
+use super::ignore::IgnoreRules;
 use super::paths::VaultPaths;
 use super::schema::SchemaViolation;
-use super::wikilink::extract_wikilinks;
+use super::wikilink::{extract_wikilinks, parse_wikilinks, Wikilink};
 
 pub struct Note {
     pub path: PathBuf,
     pub name: String,
     pub content: String,
     pub frontmatter: Option<Frontmatter>,
+    /// Set when the frontmatter block exists but is not valid YAML
+    pub frontmatter_error: Option<SchemaViolation>,
     pub modified: DateTime<Local>,
     pub created: DateTime<Local>,
 }
@@ -30,7 +36,10 @@ impl Note {
             .unwrap_or("")
             .to_string();
 
-        let frontmatter = Frontmatter::parse(&content);
+        let (frontmatter, frontmatter_error) = match Frontmatter::parse(&content) {
+            Ok(fm) => (fm, None),
+            Err(e) => (None, Some(e)),
+        };
         let modified = DateTime::from(metadata.modified()?);
         let created = DateTime::from(metadata.created().unwrap_or(metadata.modified()?));
 
@@ -39,11 +48,76 @@ impl Note {
             name,
             content,
             frontmatter,
+            frontmatter_error,
             modified,
             created,
         })
     }
 
+    /// Apply `update` to the note's frontmatter on disk, leaving the body untouched
+    ///
+    /// Returns Ok(false) if the file was already up to date.
+    pub fn update_frontmatter(&mut self, update: &FrontmatterUpdate) -> Result<bool> {
+        let current = fs::read_to_string(&self.path)?;
+        let updated = update.apply(&current)?;
+        if updated == current {
+            return Ok(false);
+        }
+
+        fs::write(&self.path, updated)?;
+        *self = Self::load(&self.path)?;
+        Ok(true)
+    }
+
+    /// Version token of the content as loaded, for `apply_edit`
+    pub fn version(&self) -> String {
+        content_version(&self.content)
+    }
+
+    /// Apply `edit` to the note on disk if it is still at `expected_version`
+    ///
+    /// Fails without writing if the file changed since that version was read,
+    /// or if the edit would add schema violations. Returns Ok(false) if the
+    /// file was already up to date.
+    pub fn apply_edit(
+        &mut self,
+        edit: &NoteEdit,
+        expected_version: &str,
+        schema: &VaultSchema,
+    ) -> Result<bool> {
+        let current = fs::read_to_string(&self.path)?;
+        if content_version(&current) != expected_version {
+            bail!(
+                "Note '{}' changed since version {} was read; get it again before editing",
+                self.name,
+                expected_version
+            );
+        }
+
+        let updated = edit.apply(&current)?;
+        if updated == current {
+            return Ok(false);
+        }
+        let violations = |content: &str| match Frontmatter::parse(content) {
+            Ok(Some(fm)) => fm.validate(schema),
+            Ok(None) => vec![SchemaViolation::MissingFrontmatter],
+            Err(e) => vec![e],
+        };
+        let before = violations(&current);
+        let added: Vec<String> = violations(&updated)
+            .into_iter()
+            .filter(|v| !before.contains(v))
+            .map(|v| v.to_string())
+            .collect();
+        if !added.is_empty() {
+            bail!("Edit would break the vault schema: {}", added.join("; "));
+        }
+
+        fs::write(&self.path, updated)?;
+        *self = Self::load(&self.path)?;
+        Ok(true)
+    }
+
     pub fn folder(&self) -> &str {
         self.path
             .parent()
@@ -52,17 +126,26 @@ impl Note {
             .unwrap_or("")
     }
 
-    pub fn validate_schema(&self) -> Vec<SchemaViolation> {
+    pub fn validate_schema(&self, schema: &VaultSchema) -> Vec<SchemaViolation> {
+        if let Some(err) = &self.frontmatter_error {
+            return vec![err.clone()];
+        }
         match &self.frontmatter {
-            Some(fm) => fm.validate(),
+            Some(fm) => fm.validate(schema),
             None => vec![SchemaViolation::MissingFrontmatter],
         }
     }
 
+    /// Names of the notes this note links to
     pub fn wikilinks(&self) -> Vec<String> {
         extract_wikilinks(&self.content)
     }
 
+    /// Every wikilink in the note, including embeds and same-note links
+    pub fn links(&self) -> Vec<Wikilink> {
+        parse_wikilinks(&self.content)
+    }
+
     pub fn tags(&self) -> Vec<String> {
         self.frontmatter
             .as_ref()
@@ -70,6 +153,13 @@ impl Note {
             .unwrap_or_default()
     }
 
+    pub fn aliases(&self) -> Vec<String> {
+        self.frontmatter
+            .as_ref()
+            .map(|fm| fm.aliases())
+            .unwrap_or_default()
+    }
+
     pub fn note_type(&self) -> Option<&str> {
         self.frontmatter.as_ref()?.note_type.as_deref()
     }
@@ -86,61 +176,185 @@ impl Note {
         self.frontmatter.as_ref()?.gist.as_deref()
     }
 
-    pub fn check_folder_type_match(&self) -> bool {
-        let folder = self.folder();
-        let note_type = self.note_type();
-        let status = self.status();
-
-        match (note_type, status) {
-            (Some("project"), Some("archived")) => folder == "Archive",
-            (Some("project"), _) => folder == "Projects",
-            (Some("note") | Some("term") | Some("log"), _) => folder == "Notes",
-            _ => true,
+    /// Whether the note sits in the folder its type/status maps to.
+    /// Notes whose type has no folder rule always match.
+    pub fn check_folder_type_match(&self, paths: &VaultPaths) -> bool {
+        match paths.expected_folder(self.note_type(), self.status()) {
+            Some(folder) => self.path.starts_with(folder),
+            None => true,
         }
     }
 }
 
 pub fn collect_all_notes(paths: &VaultPaths) -> Vec<Note> {
-    let mut notes = Vec::new();
-
-    for dir in paths.content_dirs() {
-        if !dir.exists() {
-            continue;
-        }
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().map(|e| e == "md").unwrap_or(false) {
-                    if let Ok(note) = Note::load(&path) {
-                        notes.push(note);
-                    }
-                }
-            }
-        }
-    }
+    let mut notes: Vec<Note> = note_files(paths)
+        .iter()
+        .filter_map(|path| Note::load(path).ok())
+        .collect();
 
     notes.sort_by(|a, b| a.name.cmp(&b.name));
     notes
 }
 
+/// The note named `name`, or else the one whose name or alias matches it
+/// case-insensitively, for callers that must not guess which note is meant
+///
+/// Unlike link resolution, a name several notes share is an error rather
+/// than resolving to the first of them.
+pub fn resolve_note(paths: &VaultPaths, name: &str) -> Result<Option<Note>> {
+    let (exact, others): (Vec<Note>, Vec<Note>) = collect_all_notes(paths)
+        .into_iter()
+        .partition(|n| n.name == name);
+    let mut matches = if exact.is_empty() {
+        let lower = name.to_lowercase();
+        others
+            .into_iter()
+            .filter(|n| {
+                n.name.to_lowercase() == lower
+                    || n.aliases().iter().any(|a| a.to_lowercase() == lower)
+            })
+            .collect()
+    } else {
+        exact
+    };
+    if matches.len() > 1 {
+        let found: Vec<String> = matches
+            .iter()
+            .map(|n| {
+                n.path
+                    .strip_prefix(&paths.root)
+                    .unwrap_or(&n.path)
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        bail!("\"{}\" is ambiguous: {}", name, found.join(", "));
+    }
+    Ok(matches.pop())
+}
+
 pub fn collect_note_names(paths: &VaultPaths) -> HashSet<String> {
-    let mut names = HashSet::new();
+    note_files(paths)
+        .iter()
+        .filter_map(|path| path.file_stem().and_then(|s| s.to_str()))
+        .map(String::from)
+        .collect()
+}
+
+/// Markdown files in the content directories, honoring the discovery config
+///
+/// Only the top level of each directory is read unless `discovery.recursive`
+/// is set. Symlinks are followed; hidden directories (such as `.obsidian` or
+/// `.trash`) and paths matched by the ignore rules are skipped.
+pub fn note_files(paths: &VaultPaths) -> Vec<PathBuf> {
+    let ignore = IgnoreRules::for_vault(paths);
+    let max_depth = if paths.config.discovery.recursive {
+        usize::MAX
+    } else {
+        1
+    };
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
 
     for dir in paths.content_dirs() {
         if !dir.exists() {
             continue;
         }
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().map(|e| e == "md").unwrap_or(false) {
-                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                        names.insert(stem.to_string());
-                    }
+        let walker = WalkDir::new(dir)
+            .follow_links(true)
+            .max_depth(max_depth)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                if entry.depth() == 0 {
+                    return true;
                 }
+                let is_dir = entry.file_type().is_dir();
+                let hidden = is_dir && entry.file_name().to_string_lossy().starts_with('.');
+                let rel = entry
+                    .path()
+                    .strip_prefix(&paths.root)
+                    .unwrap_or(entry.path());
+                !hidden && !ignore.is_ignored(rel, is_dir)
+            });
+
+        for entry in walker.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_file()
+                && path.extension().map(|e| e == "md").unwrap_or(false)
+                && seen.insert(path.to_path_buf())
+            {
+                files.push(path.to_path_buf());
             }
         }
     }
 
-    names
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_files_follows_symlinks_and_keeps_hidden_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("Notes/.trash")).unwrap();
+        fs::create_dir_all(root.join("Notes/sub")).unwrap();
+        fs::create_dir_all(root.join("Elsewhere")).unwrap();
+        fs::write(root.join("Notes/Plain.md"), "plain").unwrap();
+        fs::write(root.join("Notes/.Hidden.md"), "hidden").unwrap();
+        fs::write(root.join("Notes/.trash/Deleted.md"), "deleted").unwrap();
+        fs::write(root.join("Notes/sub/Nested.md"), "nested").unwrap();
+        fs::write(root.join("Elsewhere/Target.md"), "target").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(
+            root.join("Elsewhere/Target.md"),
+            root.join("Notes/Linked.md"),
+        )
+        .unwrap();
+
+        let mut paths = VaultPaths::from_root(root.to_path_buf());
+        let names = |paths: &VaultPaths| {
+            let mut names: Vec<String> = collect_note_names(paths).into_iter().collect();
+            names.sort();
+            names
+        };
+
+        let mut expected = vec![".Hidden", "Plain"];
+        #[cfg(unix)]
+        expected.insert(1, "Linked");
+        assert_eq!(names(&paths), expected);
+
+        paths.config.discovery.recursive = true;
+        expected.insert(expected.len() - 1, "Nested");
+        assert_eq!(names(&paths), expected);
+    }
+
+    #[test]
+    fn test_resolve_note_exactly() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = tmp.path();
+        fs::create_dir_all(root.join("Notes/sub"))?;
+        fs::write(root.join("Notes/Alpha.md"), "---\naliases: [First]\n---\n")?;
+        fs::write(root.join("Notes/Beta.md"), "---\naliases: [shared]\n---\n")?;
+        fs::write(root.join("Notes/Gamma.md"), "---\naliases: [Shared]\n---\n")?;
+        fs::write(root.join("Notes/sub/Alpha.md"), "nested")?;
+        let mut paths = VaultPaths::from_root(root.to_path_buf());
+        let name = |name: &str| -> Result<Option<String>> {
+            Ok(resolve_note(&paths, name)?.map(|n| n.name))
+        };
+
+        assert_eq!(name("Alpha")?.as_deref(), Some("Alpha"));
+        assert_eq!(name("first")?.as_deref(), Some("Alpha"));
+        assert_eq!(name("beta")?.as_deref(), Some("Beta"));
+        assert_eq!(name("a")?, None);
+        assert!(name("shared").is_err());
+
+        paths.config.discovery.recursive = true;
+        let err = resolve_note(&paths, "Alpha").err().unwrap();
+        assert!(err.to_string().contains("Notes/sub/Alpha.md"), "{}", err);
+        Ok(())
+    }
 }
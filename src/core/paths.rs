@@ -1,59 +1,106 @@
 use std::path::PathBuf;
 
+use anyhow::Result;
+
+use super::config::VaultConfig;
+
 pub struct VaultPaths {
     pub root: PathBuf,
     pub notes: PathBuf,
     pub projects: PathBuf,
     pub archive: PathBuf,
+    pub extra_content_dirs: Vec<PathBuf>,
     pub system: PathBuf,
     pub dashboards: PathBuf,
     pub templates: PathBuf,
     pub attachments: PathBuf,
     pub opencode: PathBuf,
     pub inbox: PathBuf,
+    pub data: PathBuf,
+    pub config: VaultConfig,
 }
 
 impl VaultPaths {
-    pub fn new() -> Self {
-        let root = std::env::current_dir().expect("Failed to get current directory");
-        Self::from_root(root)
+    /// Open the vault in the current directory
+    pub fn new() -> Result<Self> {
+        Self::open(std::env::current_dir()?)
+    }
+
+    /// Open the vault at `root`, reading its `elysium.yaml` if present
+    pub fn open(root: PathBuf) -> Result<Self> {
+        let config = VaultConfig::load(&root)?;
+        Ok(Self::with_config(root, config))
     }
 
+    /// Vault at `root` with the built-in default config
     pub fn from_root(root: PathBuf) -> Self {
+        Self::with_config(root, VaultConfig::default())
+    }
+
+    pub fn with_config(root: PathBuf, config: VaultConfig) -> Self {
+        let layout = &config.layout;
         Self {
-            notes: root.join("Notes"),
-            projects: root.join("Projects"),
-            archive: root.join("Archive"),
-            system: root.join("_system"),
-            dashboards: root.join("_system/Dashboards"),
-            templates: root.join("_system/Templates"),
-            attachments: root.join("_system/Attachments"),
-            opencode: root.join(".opencode"),
-            inbox: root.join("inbox.md"),
+            notes: root.join(&layout.notes),
+            projects: root.join(&layout.projects),
+            archive: root.join(&layout.archive),
+            extra_content_dirs: layout
+                .extra_content_dirs
+                .iter()
+                .map(|dir| root.join(dir))
+                .collect(),
+            system: root.join(&layout.system),
+            dashboards: root.join(&layout.dashboards),
+            templates: root.join(&layout.templates),
+            attachments: root.join(&layout.attachments),
+            opencode: root.join(&layout.agent_dir),
+            inbox: root.join(&layout.inbox),
+            data: root.join(&layout.data_dir),
             root,
+            config,
         }
     }
 
     pub fn content_dirs(&self) -> Vec<&PathBuf> {
-        vec![&self.notes, &self.projects, &self.archive]
+        let mut dirs = vec![&self.notes, &self.projects, &self.archive];
+        dirs.extend(&self.extra_content_dirs);
+        dirs
+    }
+
+    /// SQLite database holding the search index
+    pub fn search_db(&self) -> PathBuf {
+        self.data.join("search.db")
+    }
+
+    /// Folder a note of this type/status belongs in, per the layout's folder rules
+    pub fn expected_folder(
+        &self,
+        note_type: Option<&str>,
+        status: Option<&str>,
+    ) -> Option<PathBuf> {
+        self.config
+            .layout
+            .folder_rules()
+            .into_iter()
+            .find(|rule| rule.matches(note_type, status))
+            .map(|rule| self.root.join(rule.folder))
     }
 
     pub fn required_folders(&self) -> Vec<(&PathBuf, &str, bool)> {
-        vec![
+        let mut folders = vec![
             (&self.notes, "All notes (note, term, log)", false),
             (&self.projects, "Active projects", false),
             (&self.archive, "Completed projects", false),
+        ];
+        for dir in &self.extra_content_dirs {
+            folders.push((dir, "Additional notes", false));
+        }
+        folders.extend([
             (&self.system, "System files", true),
             (&self.dashboards, "Dataview queries", false),
             (&self.templates, "Note templates", false),
             (&self.attachments, "Media files", false),
             (&self.opencode, "AI agent configuration", true),
-        ]
-    }
-}
-
-impl Default for VaultPaths {
-    fn default() -> Self {
-        Self::new()
+        ]);
+        folders
     }
 }
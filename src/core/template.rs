@@ -0,0 +1,280 @@
+//! Creating notes from the templates in the vault's templates folder
+//!
+//! A template for type `project` is `project.md` (any case) or
+//! `project template.md` in `VaultPaths::templates`. Its `{{title}}`,
+//! `{{date}}` and `{{time}}` placeholders are filled in, then the given
+//! frontmatter fields are set on top of whatever the template declares.
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+
+use super::frontmatter::{Frontmatter, FrontmatterUpdate};
+use super::note::collect_all_notes;
+use super::paths::VaultPaths;
+use super::wikilink::LinkResolver;
+
+/// Marker before which generated body text goes, kept by `elysium fix --footer`
+pub const FOOTER_START: &str = "<!-- footer_start -->";
+
+/// Characters Obsidian does not allow in note names, or that break wikilinks
+const FORBIDDEN_TITLE_CHARS: &[char] = &[
+    '/', '\\', ':', '*', '?', '"', '<', '>', '|', '[', ']', '#', '^',
+];
+
+/// Fields of a note to be created
+#[derive(Debug, Clone, Default)]
+pub struct NewNote {
+    pub title: String,
+    pub note_type: String,
+    /// Defaults to the template's status, then the first allowed status
+    pub status: Option<String>,
+    pub area: Option<String>,
+    pub tags: Vec<String>,
+    pub gist: Option<String>,
+    /// Markdown placed before the template's footer, or at the end
+    pub body: Option<String>,
+}
+
+impl NewNote {
+    /// Full file content of the note, validated against the vault schema
+    pub fn render(&self, paths: &VaultPaths) -> Result<String> {
+        check_title(&self.title)?;
+        let template = find_template(paths, &self.note_type)
+            .map(|path| {
+                fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let content = fill_placeholders(&template, &self.title);
+
+        let declared = Frontmatter::parse(&content)
+            .map_err(|e| anyhow::anyhow!("Template for '{}' has {}", self.note_type, e))?;
+        let status = self
+            .status
+            .clone()
+            .or_else(|| declared.as_ref().and_then(|fm| fm.status.clone()))
+            .or_else(|| {
+                paths
+                    .config
+                    .schema
+                    .allowed("status")
+                    .and_then(|allowed| allowed.first().cloned())
+            });
+
+        let mut update = FrontmatterUpdate::new().set("type", self.note_type.as_str());
+        if let Some(status) = status {
+            update = update.set("status", status);
+        }
+        if let Some(area) = &self.area {
+            update = update.set("area", area.as_str());
+        }
+        if let Some(gist) = &self.gist {
+            update = update.set("gist", gist.as_str());
+        }
+        if !self.tags.is_empty() {
+            update = update.set_tags(&self.tags);
+        }
+        let mut content = update.apply(&content)?;
+        if let Some(body) = self.body.as_deref().filter(|b| !b.trim().is_empty()) {
+            content = insert_body(&content, body);
+        }
+
+        let frontmatter = Frontmatter::parse(&content)
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .unwrap_or_default();
+        let violations = frontmatter.validate(&paths.config.schema);
+        if !violations.is_empty() {
+            let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            bail!(
+                "Note does not match the vault schema: {}",
+                messages.join("; ")
+            );
+        }
+        Ok(content)
+    }
+
+    /// Path the note is created at: the folder its type and status map to,
+    /// or the notes folder when no folder rule matches
+    pub fn target_path(&self, paths: &VaultPaths, content: &str) -> PathBuf {
+        let status = Frontmatter::parse(content)
+            .ok()
+            .flatten()
+            .and_then(|fm| fm.status);
+        paths
+            .expected_folder(Some(&self.note_type), status.as_deref())
+            .unwrap_or_else(|| paths.notes.clone())
+            .join(format!("{}.md", self.title.trim()))
+    }
+
+    /// Write the note into the vault, returning its path
+    ///
+    /// Fails if a note with the same name or alias already exists anywhere
+    /// in the vault; an existing file is never overwritten.
+    pub fn create(&self, paths: &VaultPaths) -> Result<PathBuf> {
+        let content = self.render(paths)?;
+        let notes = collect_all_notes(paths);
+        if let Some(existing) = LinkResolver::from_notes(&notes).lookup(self.title.trim()) {
+            bail!("Note '{}' already exists", existing);
+        }
+
+        let path = self.target_path(paths, &content);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        file.write_all(content.as_bytes())?;
+        Ok(path)
+    }
+}
+
+/// Template file for `note_type`, if the vault has one
+pub fn find_template(paths: &VaultPaths, note_type: &str) -> Option<PathBuf> {
+    let wanted = [
+        note_type.to_lowercase(),
+        format!("{} template", note_type.to_lowercase()),
+    ];
+    let mut entries: Vec<PathBuf> = fs::read_dir(&paths.templates)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "md"))
+        .collect();
+    entries.sort();
+    entries.into_iter().find(|path| {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|stem| wanted.contains(&stem.to_lowercase()))
+    })
+}
+
+fn check_title(title: &str) -> Result<()> {
+    let title = title.trim();
+    if title.is_empty() {
+        bail!("Note title is empty");
+    }
+    if title.starts_with('.') {
+        bail!("Note title cannot start with '.'");
+    }
+    if let Some(c) = title
+        .chars()
+        .find(|c| FORBIDDEN_TITLE_CHARS.contains(c) || c.is_control())
+    {
+        bail!("Note title cannot contain '{}'", c);
+    }
+    Ok(())
+}
+
+/// Replace Obsidian's core template placeholders
+fn fill_placeholders(template: &str, title: &str) -> String {
+    let now = Local::now();
+    template
+        .replace("{{title}}", title.trim())
+        .replace("{{date}}", &now.format("%Y-%m-%d").to_string())
+        .replace("{{time}}", &now.format("%H:%M").to_string())
+}
+
+/// Insert `body` before the footer marker, or append it
+pub fn insert_body(content: &str, body: &str) -> String {
+    let body = body.trim_end();
+    match content.find(FOOTER_START) {
+        Some(pos) => {
+            let before = content[..pos].trim_end();
+            format!("{}\n\n{}\n\n{}", before, body, &content[pos..])
+        }
+        None if content.trim().is_empty() => format!("{}\n", body),
+        None => format!("{}\n\n{}\n", content.trim_end(), body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault() -> (tempfile::TempDir, VaultPaths) {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("_system/Templates")).unwrap();
+        fs::create_dir_all(root.path().join("Notes")).unwrap();
+        let paths = VaultPaths::from_root(root.path().to_path_buf());
+        (root, paths)
+    }
+
+    fn project(title: &str) -> NewNote {
+        NewNote {
+            title: title.to_string(),
+            note_type: "project".to_string(),
+            area: Some("work".to_string()),
+            tags: vec!["infra".to_string()],
+            gist: Some("Move the cluster to new hardware".to_string()),
+            body: Some("## Plan\n\n- order racks".to_string()),
+            ..NewNote::default()
+        }
+    }
+
+    #[test]
+    fn test_create_from_template() -> Result<()> {
+        let (_root, paths) = vault();
+        fs::write(
+            paths.templates.join("Project.md"),
+            "---\ntype: project\nstatus: active\narea:\ngist:\ntags: []\ncreated: {{date}}\n---\n# {{title}}\n\n<!-- footer_start -->\n## Footer\n<!-- footer_end -->\n",
+        )?;
+
+        let path = project("Cluster Move").create(&paths)?;
+        assert_eq!(path, paths.projects.join("Cluster Move.md"));
+        let content = fs::read_to_string(&path)?;
+        assert!(content.starts_with("---\ntype: project\nstatus: active\narea: work\ngist: Move the cluster to new hardware\ntags: [infra]\ncreated: 20"));
+        assert!(
+            content.contains("# Cluster Move\n\n## Plan\n\n- order racks\n\n<!-- footer_start -->")
+        );
+
+        // Never overwrite, also when only the case differs
+        let err = project("cluster move").create(&paths).unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_validates() -> Result<()> {
+        let (_root, paths) = vault();
+
+        // Without a template the note is built from the fields alone
+        let note = NewNote {
+            note_type: "note".to_string(),
+            ..project("Plain")
+        };
+        assert_eq!(
+            note.target_path(&paths, &note.render(&paths)?),
+            paths.notes.join("Plain.md")
+        );
+
+        let missing_area = NewNote {
+            area: None,
+            ..project("No Area")
+        };
+        let err = missing_area.render(&paths).unwrap_err();
+        assert!(
+            err.to_string().contains("Missing required field: area"),
+            "{}",
+            err
+        );
+
+        let bad_type = NewNote {
+            note_type: "recipe".to_string(),
+            ..project("Soup")
+        };
+        assert!(bad_type.render(&paths).is_err());
+        assert!(project("a/b").render(&paths).is_err());
+        assert!(project("  ").render(&paths).is_err());
+
+        Ok(())
+    }
+}
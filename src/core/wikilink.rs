@@ -1,19 +1,383 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+
+use super::frontmatter::Frontmatter;
+use super::markdown::{block_ids, code_ranges, headings, in_ranges, normalize_heading};
+use super::note::Note;
 
 lazy_static! {
-    // [[target]] or [[target|display]]
-    static ref WIKILINK_RE: Regex = Regex::new(r"\[\[([^\]|]+)(?:\|[^\]]+)?\]\]").unwrap();
+    // [[target#heading|alias]] or ![[embed]]; the inner text cannot span lines
+    static ref WIKILINK_RE: Regex = Regex::new(r"(!?)\[\[([^\[\]\n]+?)\]\]").unwrap();
 }
 
-pub fn extract_wikilinks(content: &str) -> Vec<String> {
+/// File extensions that make a link target an attachment rather than a note
+pub const ATTACHMENT_EXTENSIONS: &[&str] = &[
+    "png",
+    "jpg",
+    "jpeg",
+    "gif",
+    "bmp",
+    "svg",
+    "webp",
+    "avif",
+    "pdf",
+    "mp3",
+    "wav",
+    "m4a",
+    "ogg",
+    "flac",
+    "webm",
+    "mp4",
+    "mkv",
+    "mov",
+    "ogv",
+    "canvas",
+    "excalidraw",
+];
+
+/// A parsed Obsidian wikilink
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wikilink {
+    /// Link target as written, without a trailing `.md`; empty for same-note links
+    pub target: String,
+    /// Heading path after `#` (`H1#H2` for nested headings)
+    pub heading: Option<String>,
+    /// Block id after `#^`
+    pub block_ref: Option<String>,
+    pub alias: Option<String>,
+    /// `.md` suffix as written after the target, kept when the link is rewritten
+    pub suffix: String,
+    /// Whether the alias pipe is escaped as `\|`, as inside tables
+    pub escaped_pipe: bool,
+    /// `![[...]]` transclusion
+    pub is_embed: bool,
+    /// Byte range of the whole link in the source text, including `!`
+    pub span: Range<usize>,
+}
+
+impl Wikilink {
+    fn parse(inner: &str, is_embed: bool, span: Range<usize>) -> Self {
+        // Inside tables the alias pipe is escaped as `\|`
+        let (link, alias) = match inner.split_once('|') {
+            Some((link, alias)) => (link, Some(alias.trim())),
+            None => (inner, None),
+        };
+        let escaped_pipe = alias.is_some() && link.ends_with('\\');
+        let link = link.trim_end_matches('\\');
+
+        let (target, anchor) = match link.split_once('#') {
+            Some((target, anchor)) => (target, Some(anchor.trim())),
+            None => (link, None),
+        };
+        let target = target.trim();
+        let (target, suffix) = match target.len().checked_sub(3) {
+            Some(i) if target.is_char_boundary(i) && target[i..].eq_ignore_ascii_case(".md") => {
+                target.split_at(i)
+            }
+            _ => (target, ""),
+        };
+
+        let (heading, block_ref) = match anchor.filter(|a| !a.is_empty()) {
+            Some(a) => match a.strip_prefix('^') {
+                Some(block) => (None, Some(block.to_string())),
+                None => (Some(a.to_string()), None),
+            },
+            None => (None, None),
+        };
+
+        Self {
+            target: target.to_string(),
+            heading,
+            block_ref,
+            alias: alias.filter(|a| !a.is_empty()).map(String::from),
+            suffix: suffix.to_string(),
+            escaped_pipe,
+            is_embed,
+            span,
+        }
+    }
+
+    /// Note name the link points to: the last path segment of the target
+    pub fn name(&self) -> &str {
+        self.target.rsplit('/').next().unwrap_or(&self.target)
+    }
+
+    /// Whether the link points at a heading or block of the note it is in
+    pub fn is_self_link(&self) -> bool {
+        self.target.is_empty()
+    }
+
+    /// Whether the target has a known attachment extension such as an image
+    /// or PDF; [`LinkResolver::is_attachment`] also knows the vault's files
+    pub fn is_attachment(&self) -> bool {
+        self.name()
+            .rsplit_once('.')
+            .map(|(_, ext)| ATTACHMENT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            .unwrap_or(false)
+    }
+
+    /// Text Obsidian renders for the link
+    pub fn display_text(&self) -> String {
+        if let Some(alias) = &self.alias {
+            return alias.clone();
+        }
+        let mut text = self.target.clone();
+        if let Some(heading) = &self.heading {
+            if !text.is_empty() {
+                text.push_str(" > ");
+            }
+            text.push_str(&heading.replace('#', " > "));
+        }
+        if let Some(block) = &self.block_ref {
+            if !text.is_empty() {
+                text.push_str(" > ");
+            }
+            text.push('^');
+            text.push_str(block);
+        }
+        text
+    }
+}
+
+impl fmt::Display for Wikilink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_embed {
+            write!(f, "!")?;
+        }
+        write!(f, "[[{}{}", self.target, self.suffix)?;
+        if let Some(heading) = &self.heading {
+            write!(f, "#{}", heading)?;
+        }
+        if let Some(block) = &self.block_ref {
+            write!(f, "#^{}", block)?;
+        }
+        if let Some(alias) = &self.alias {
+            let separator = if self.escaped_pipe { "\\|" } else { "|" };
+            write!(f, "{}{}", separator, alias)?;
+        }
+        write!(f, "]]")
+    }
+}
+
+/// Parse every wikilink outside fenced code blocks and inline code
+pub fn parse_wikilinks(content: &str) -> Vec<Wikilink> {
+    let code = code_ranges(content);
     WIKILINK_RE
         .captures_iter(content)
-        .map(|c| c[1].trim().to_string())
+        .filter_map(|c| {
+            let whole = c.get(0)?;
+            let bracket = whole.start() + c[1].len();
+            if in_ranges(&code, bracket) {
+                return None;
+            }
+            Some(Wikilink::parse(&c[2], !c[1].is_empty(), whole.range()))
+        })
+        .collect()
+}
+
+/// Names of the notes linked from `content`, skipping attachments and same-note links
+pub fn extract_wikilinks(content: &str) -> Vec<String> {
+    parse_wikilinks(content)
+        .into_iter()
+        .filter(|l| !l.is_self_link() && !l.is_attachment())
+        .map(|l| l.name().to_string())
         .collect()
 }
 
+/// Why a wikilink does not resolve
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkError {
+    MissingNote,
+    MissingHeading(String),
+    MissingBlock(String),
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingNote => write!(f, "note not found"),
+            Self::MissingHeading(h) => write!(f, "heading '{}' not found", h),
+            Self::MissingBlock(b) => write!(f, "block '^{}' not found", b),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Anchors {
+    headings: HashSet<String>,
+    block_ids: HashSet<String>,
+}
+
+impl Anchors {
+    fn of(content: &str) -> Self {
+        Self {
+            headings: headings(content)
+                .iter()
+                .map(|h| normalize_heading(&h.text))
+                .collect(),
+            block_ids: block_ids(content),
+        }
+    }
+}
+
+/// Resolves wikilinks against the notes of a vault
+///
+/// Link targets match note names exactly, then case-insensitively, then
+/// frontmatter `aliases`; headings and block ids are checked against the
+/// resolved note.
+#[derive(Debug, Default)]
+pub struct LinkResolver {
+    notes: HashMap<String, Anchors>,
+    /// Lowercased names and aliases → canonical note name
+    keys: HashMap<String, String>,
+    /// Lowercased names of the non-Markdown files in the vault
+    files: HashSet<String>,
+}
+
+impl LinkResolver {
+    /// Build from `(name, content)` pairs; aliases are read from the frontmatter
+    pub fn new<'a>(notes: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let entries: Vec<_> = notes
+            .into_iter()
+            .map(|(name, content)| {
+                let aliases = Frontmatter::parse(content)
+                    .ok()
+                    .flatten()
+                    .map(|fm| fm.aliases())
+                    .unwrap_or_default();
+                (name, content, aliases)
+            })
+            .collect();
+        Self::build(entries)
+    }
+
+    pub fn from_notes(notes: &[Note]) -> Self {
+        Self::build(
+            notes
+                .iter()
+                .map(|n| (n.name.as_str(), n.content.as_str(), n.aliases()))
+                .collect(),
+        )
+    }
+
+    fn build(entries: Vec<(&str, &str, Vec<String>)>) -> Self {
+        let mut resolver = Self::default();
+
+        // Names take precedence over aliases; on collisions the first entry wins
+        for (name, content, _) in &entries {
+            resolver
+                .notes
+                .entry(name.to_string())
+                .or_insert_with(|| Anchors::of(content));
+            resolver
+                .keys
+                .entry(name.to_lowercase())
+                .or_insert_with(|| name.to_string());
+        }
+        for (name, _, aliases) in &entries {
+            for alias in aliases {
+                resolver
+                    .keys
+                    .entry(alias.to_lowercase())
+                    .or_insert_with(|| name.to_string());
+            }
+        }
+
+        resolver
+    }
+
+    /// Canonical name of the note called or aliased `name`
+    pub fn lookup(&self, name: &str) -> Option<&str> {
+        match self.notes.get_key_value(name) {
+            Some((canonical, _)) => Some(canonical),
+            None => self.keys.get(&name.to_lowercase()).map(String::as_str),
+        }
+    }
+
+    /// Also treat links to these vault files as attachments, so links like
+    /// `[[data.csv]]` are not reported as missing notes
+    pub fn with_files<'a>(mut self, files: impl IntoIterator<Item = &'a Path>) -> Self {
+        self.files.extend(
+            files
+                .into_iter()
+                .filter(|f| {
+                    f.extension()
+                        .is_none_or(|ext| !ext.eq_ignore_ascii_case("md"))
+                })
+                .filter_map(|f| f.file_name())
+                .map(|name| name.to_string_lossy().to_lowercase()),
+        );
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.lookup(name).is_some()
+    }
+
+    /// Whether `link` points at a file rather than a note: a known attachment
+    /// extension, or a vault file whose name no note has
+    pub fn is_attachment(&self, link: &Wikilink) -> bool {
+        link.is_attachment()
+            || (!link.is_self_link()
+                && !self.contains(link.name())
+                && self.files.contains(&link.name().to_lowercase()))
+    }
+
+    /// Canonical names of all notes
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.notes.keys().map(String::as_str)
+    }
+
+    /// Resolve `link`, found in the note named `source`, to the name of its target note
+    pub fn resolve(&self, source: &str, link: &Wikilink) -> Result<String, LinkError> {
+        let name = if link.is_self_link() {
+            source
+        } else {
+            self.lookup(link.name()).ok_or(LinkError::MissingNote)?
+        };
+        let anchors = self.notes.get(name).ok_or(LinkError::MissingNote)?;
+
+        if let Some(heading) = &link.heading {
+            // Nested heading paths only need their last segment to exist
+            let last = heading.rsplit('#').next().unwrap_or(heading);
+            if !anchors.headings.contains(&normalize_heading(last)) {
+                return Err(LinkError::MissingHeading(heading.clone()));
+            }
+        }
+        if let Some(block) = &link.block_ref {
+            if !anchors.block_ids.contains(block) {
+                return Err(LinkError::MissingBlock(block.clone()));
+            }
+        }
+
+        Ok(name.to_string())
+    }
+
+    /// Note links in `content` that fail to resolve; attachments are not checked
+    pub fn broken_links(&self, source: &str, content: &str) -> Vec<(Wikilink, LinkError)> {
+        parse_wikilinks(content)
+            .into_iter()
+            .filter(|l| !self.is_attachment(l))
+            .filter_map(|l| self.resolve(source, &l).err().map(|e| (l, e)))
+            .collect()
+    }
+
+    /// Canonical names of the other notes `content` links to
+    pub fn linked_notes(&self, source: &str, content: &str) -> Vec<String> {
+        parse_wikilinks(content)
+            .iter()
+            .filter(|l| !l.is_self_link() && !self.is_attachment(l))
+            .filter_map(|l| self.lookup(l.name()))
+            .filter(|name| *name != source)
+            .map(String::from)
+            .collect()
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct WikilinkReport {
     pub total_links: usize,
@@ -23,24 +387,29 @@ pub struct WikilinkReport {
     pub orphan_notes: Vec<String>,
 }
 
-pub fn analyze_wikilinks(
-    notes: &[(String, String)],
-    existing_names: &HashSet<String>,
-) -> WikilinkReport {
+/// Link statistics for `(name, content)` pairs
+pub fn analyze_wikilinks(notes: &[(String, String)]) -> WikilinkReport {
+    let resolver = LinkResolver::new(notes.iter().map(|(n, c)| (n.as_str(), c.as_str())));
     let mut report = WikilinkReport::default();
     let mut incoming_links: HashMap<String, usize> = HashMap::new();
 
     for (filename, content) in notes {
-        let links = extract_wikilinks(content);
+        let links: Vec<_> = parse_wikilinks(content)
+            .into_iter()
+            .filter(|l| !resolver.is_attachment(l))
+            .collect();
         report.total_links += links.len();
 
         let mut broken = Vec::new();
         for link in links {
-            if existing_names.contains(&link) {
-                report.valid_links += 1;
-                *incoming_links.entry(link).or_insert(0) += 1;
-            } else {
-                broken.push(link);
+            match resolver.resolve(filename, &link) {
+                Ok(target) => {
+                    report.valid_links += 1;
+                    if target != *filename {
+                        *incoming_links.entry(target).or_insert(0) += 1;
+                    }
+                }
+                Err(_) => broken.push(link.to_string()),
             }
         }
 
@@ -50,12 +419,126 @@ pub fn analyze_wikilinks(
         }
     }
 
-    for name in existing_names {
+    for name in resolver.names() {
         if !incoming_links.contains_key(name) {
-            report.orphan_notes.push(name.clone());
+            report.orphan_notes.push(name.to_string());
         }
     }
     report.orphan_notes.sort();
 
     report
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_obsidian_grammar() {
+        let content =
+            "See [[folder/Note.md#Intro|the intro]], ![[Diagram.png]] and [[Other#^abc]].\n\
+                       Inline `[[Code]]` and\n```\n[[Fenced]]\n```\n| [[Table\\|alias]] |\n";
+        let links = parse_wikilinks(content);
+        assert_eq!(links.len(), 4);
+
+        assert_eq!(links[0].target, "folder/Note");
+        assert_eq!(links[0].name(), "Note");
+        assert_eq!(links[0].heading.as_deref(), Some("Intro"));
+        assert_eq!(links[0].alias.as_deref(), Some("the intro"));
+        assert_eq!(
+            &content[links[0].span.clone()],
+            "[[folder/Note.md#Intro|the intro]]"
+        );
+
+        assert!(links[1].is_embed && links[1].is_attachment());
+        assert_eq!(links[2].block_ref.as_deref(), Some("abc"));
+        assert_eq!(links[3].target, "Table");
+        assert_eq!(links[3].alias.as_deref(), Some("alias"));
+
+        assert_eq!(extract_wikilinks(content), vec!["Note", "Other", "Table"]);
+    }
+
+    #[test]
+    fn test_resolve_headings_and_blocks() {
+        let resolver = LinkResolver::new([
+            ("Note", "# Intro\n## Setup: Linux\nText ^abc\n"),
+            ("Other", "[[#Missing]] [[Note#setup linux]]"),
+        ]);
+        let resolve = |src: &str, text: &str| {
+            let link = parse_wikilinks(text).remove(0);
+            resolver.resolve(src, &link)
+        };
+
+        assert_eq!(resolve("Other", "[[Note#Intro]]"), Ok("Note".to_string()));
+        assert!(resolve("Other", "[[Note#Setup: Linux]]").is_ok());
+        assert!(resolve("Other", "[[Note#^abc]]").is_ok());
+        assert!(resolve("Note", "[[#Intro]]").is_ok());
+        assert_eq!(
+            resolve("Other", "[[Note#Outro]]"),
+            Err(LinkError::MissingHeading("Outro".to_string()))
+        );
+        assert_eq!(
+            resolve("Other", "[[Note#^zzz]]"),
+            Err(LinkError::MissingBlock("zzz".to_string()))
+        );
+        assert_eq!(resolve("Other", "[[Nope]]"), Err(LinkError::MissingNote));
+
+        let broken = resolver.broken_links("Other", "[[#Missing]] [[Note#setup linux]]");
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].0.to_string(), "[[#Missing]]");
+    }
+
+    #[test]
+    fn test_resolve_aliases_case_insensitively() {
+        let resolver = LinkResolver::new([
+            ("Kubernetes", "---\naliases: [k8s, Kube]\n---\n# Pods\n"),
+            ("k8s", "Exact names win over aliases"),
+            ("Other", "[[K8S]] [[kube#pods]] [[kubernetes]]"),
+        ]);
+
+        assert_eq!(resolver.lookup("k8s"), Some("k8s"));
+        assert_eq!(resolver.lookup("KUBE"), Some("Kubernetes"));
+        assert_eq!(resolver.lookup("kubernetes"), Some("Kubernetes"));
+        assert!(resolver
+            .broken_links("Other", "[[kube#pods]] [[kubernetes]]")
+            .is_empty());
+        assert_eq!(
+            resolver.linked_notes("Other", "[[K8S]] [[kube#pods]]"),
+            vec!["k8s", "Kubernetes"]
+        );
+    }
+
+    #[test]
+    fn test_first_duplicate_wins() {
+        let resolver = LinkResolver::new([
+            ("Alpha", "---\naliases: [shared]\n---\n# First\n"),
+            ("Beta", "---\naliases: [Shared]\n---\n"),
+            ("Alpha", "# Second\n"),
+        ]);
+        let resolve = |text: &str| resolver.resolve("Other", &parse_wikilinks(text).remove(0));
+
+        assert_eq!(resolver.lookup("SHARED"), Some("Alpha"));
+        assert!(resolve("[[shared#First]]").is_ok());
+        assert_eq!(
+            resolve("[[Alpha#Second]]"),
+            Err(LinkError::MissingHeading("Second".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_display_keeps_escaped_pipe_and_suffix() {
+        for text in [
+            "[[Note.md#Intro\\|alias]]",
+            "![[folder/Note.MD|alias]]",
+            "[[Note#^abc]]",
+        ] {
+            assert_eq!(parse_wikilinks(text)[0].to_string(), text);
+        }
+
+        let link = Wikilink {
+            heading: None,
+            ..parse_wikilinks("| [[Note.md#Gone\\|alias]] |")[0].clone()
+        };
+        assert_eq!(link.to_string(), "[[Note.md\\|alias]]");
+    }
+}
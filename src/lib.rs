@@ -6,14 +6,19 @@
 //!
 //! - `core`: Core vault operations (notes, frontmatter, wikilinks)
 //! - `search`: Semantic search engine (Phase 1+)
+//! - `commands`: CLI command implementations
 //! - `mcp`: MCP server for Claude integration (Phase 1+)
 
+pub mod commands;
 pub mod core;
+#[cfg(feature = "mcp")]
+pub mod mcp;
 pub mod search;
 
 // Re-exports for convenience
-pub use core::frontmatter::Frontmatter;
+pub use core::config::{VaultConfig, VaultSchema};
+pub use core::frontmatter::{Frontmatter, FrontmatterUpdate};
 pub use core::note::{collect_all_notes, collect_note_names, Note};
 pub use core::paths::VaultPaths;
-pub use core::schema::{SchemaViolation, VALID_AREAS, VALID_STATUS, VALID_TYPES};
-pub use core::wikilink::extract_wikilinks;
+pub use core::schema::{SchemaViolation, DEFAULT_AREAS, DEFAULT_STATUS, DEFAULT_TYPES};
+pub use core::wikilink::{extract_wikilinks, parse_wikilinks, LinkResolver, Wikilink};
@@ -1,10 +1,12 @@
-mod commands;
-mod core;
-#[cfg(feature = "mcp")]
-mod mcp;
-mod search;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use elysium_mcp::commands;
+use elysium_mcp::core::template::NewNote;
+use elysium_mcp::search::engine::SearchMode;
+use elysium_mcp::search::filter::{parse_time, SearchFilter};
+#[cfg(feature = "mcp")]
+use elysium_mcp::mcp;
 
 #[derive(Parser)]
 #[command(name = "vault")]
@@ -25,7 +27,7 @@ enum Commands {
     Validate {
         #[arg(long, help = "Check YAML schema only")]
         schema: bool,
-        #[arg(long, help = "Check wikilinks only")]
+        #[arg(long, help = "Check links and attachments only")]
         wikilinks: bool,
         #[arg(long, help = "JSON output")]
         json: bool,
@@ -68,6 +70,11 @@ enum Commands {
         #[arg(long, help = "JSON output")]
         json: bool,
     },
+    /// Capture memos in the inbox and triage them into notes
+    Inbox {
+        #[command(subcommand)]
+        action: InboxAction,
+    },
     Fix {
         #[arg(long, help = "Fix broken wikilinks")]
         wikilinks: bool,
@@ -103,6 +110,31 @@ enum Commands {
         json: bool,
         #[arg(long, help = "Use simple string search (no AI)")]
         fallback: bool,
+        #[arg(long, default_value = "hybrid", help = "Search mode: hybrid, vector or keyword")]
+        mode: SearchMode,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Measure search quality against golden queries
+    Eval {
+        #[arg(help = "Golden queries (.yaml list or .jsonl, each with query and expected notes)")]
+        queries: PathBuf,
+        #[arg(
+            short,
+            default_value_t = 10,
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+            help = "Results scored per query (at least 1)"
+        )]
+        k: usize,
+        #[arg(
+            long,
+            help = "Search mode to evaluate (repeatable; default: vector, keyword and hybrid)"
+        )]
+        mode: Vec<SearchMode>,
+        #[arg(long, help = "Update the index before evaluating")]
+        index: bool,
+        #[arg(long, help = "JSON output")]
+        json: bool,
     },
 
     // ===== MCP Server =====
@@ -114,6 +146,81 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum InboxAction {
+    /// Append a timestamped memo
+    Capture {
+        text: String,
+        #[arg(long, help = "JSON output")]
+        json: bool,
+    },
+    /// List memos with their indices and hashes
+    List {
+        #[arg(long, help = "JSON output")]
+        json: bool,
+    },
+    /// Turn a memo into a new note and remove it from the inbox
+    Promote {
+        #[arg(help = "Memo index from `inbox list`")]
+        index: usize,
+        #[arg(long, help = "Memo hash from `inbox list`; refused if the memo changed")]
+        hash: String,
+        #[arg(long, help = "Note title")]
+        title: String,
+        #[arg(long = "type", help = "Note type")]
+        note_type: String,
+        #[arg(long, help = "Note status (default: from the template)")]
+        status: Option<String>,
+        #[arg(long, help = "Note area")]
+        area: Option<String>,
+        #[arg(long, help = "Tag (repeatable)")]
+        tag: Vec<String>,
+        #[arg(long, help = "Summary: 2-3 sentences, at most 100 words")]
+        gist: Option<String>,
+        #[arg(long, help = "JSON output")]
+        json: bool,
+    },
+}
+
+#[derive(Args)]
+struct FilterArgs {
+    #[arg(long = "type", help = "Only notes of this type")]
+    note_type: Option<String>,
+    #[arg(long, help = "Only notes with this status")]
+    status: Option<String>,
+    #[arg(long, help = "Only notes in this area")]
+    area: Option<String>,
+    #[arg(long, help = "Only notes with this tag (repeatable; all must match)")]
+    tag: Vec<String>,
+    #[arg(long, help = "Only notes with any of these tags (repeatable)")]
+    any_tag: Vec<String>,
+    #[arg(long, help = "Only notes under this path, relative to the vault root")]
+    path: Option<String>,
+    #[arg(long, help = "Only notes modified on or after this date (YYYY-MM-DD)")]
+    modified_after: Option<String>,
+    #[arg(long, help = "Only notes modified before this date (YYYY-MM-DD)")]
+    modified_before: Option<String>,
+}
+
+impl FilterArgs {
+    fn into_filter(self) -> anyhow::Result<SearchFilter> {
+        Ok(SearchFilter {
+            note_type: self.note_type,
+            status: self.status,
+            area: self.area,
+            all_tags: self.tag,
+            any_tags: self.any_tag,
+            path_prefix: self.path,
+            modified_after: self.modified_after.as_deref().map(parse_time).transpose()?,
+            modified_before: self
+                .modified_before
+                .as_deref()
+                .map(parse_time)
+                .transpose()?,
+        })
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
@@ -135,6 +242,32 @@ fn main() -> anyhow::Result<()> {
         Commands::Search { query, gist, limit } => commands::search::run(&query, gist, limit),
         Commands::Related { note, min_tags } => commands::related::run(&note, min_tags),
         Commands::Tags { analyze, json } => commands::tags::run(analyze, json),
+        Commands::Inbox { action } => match action {
+            InboxAction::Capture { text, json } => commands::inbox::capture(&text, json),
+            InboxAction::List { json } => commands::inbox::list(json),
+            InboxAction::Promote {
+                index,
+                hash,
+                title,
+                note_type,
+                status,
+                area,
+                tag,
+                gist,
+                json,
+            } => {
+                let note = NewNote {
+                    title,
+                    note_type,
+                    status,
+                    area,
+                    tags: tag,
+                    gist,
+                    body: None,
+                };
+                commands::inbox::promote(index, &hash, note, json)
+            }
+        },
         Commands::Fix {
             wikilinks,
             footer,
@@ -155,7 +288,19 @@ fn main() -> anyhow::Result<()> {
             limit,
             json,
             fallback,
-        } => commands::semantic_search::run(&query, limit, json, fallback),
+            mode,
+            filter,
+        } => {
+            let filter = filter.into_filter()?;
+            commands::semantic_search::run(&query, limit, json, fallback, mode, &filter)
+        }
+        Commands::Eval {
+            queries,
+            k,
+            mode,
+            index,
+            json,
+        } => commands::eval::run(&queries, k, mode, index, json),
 
         // MCP Server
         #[cfg(feature = "mcp")]
@@ -216,9 +361,14 @@ fn print_mcp_install_instructions() {
 }}"#, binary_path, vault_path);
     println!();
     println!("{}", "Available tools:".bold());
-    println!("  • {} - Semantic search using gist embeddings", "vault_search".green());
+    println!("  • {} - Semantic search over gists and note bodies", "vault_search".green());
     println!("  • {} - Get full note content", "vault_get_note".green());
     println!("  • {} - List notes with filters", "vault_list_notes".green());
+    println!("  • {} - Create a note from a template", "vault_create_note".green());
+    println!("  • {} - Update note frontmatter and sections", "vault_update_note".green());
+    println!("  • {} - Capture a memo in the inbox", "vault_capture".green());
+    println!("  • {} - List inbox memos", "vault_inbox_list".green());
+    println!("  • {} - Turn an inbox memo into a note", "vault_inbox_promote".green());
     println!("  • {} - Get vault health score", "vault_health".green());
     println!("  • {} - Get vault status summary", "vault_status".green());
 }
@@ -2,6 +2,8 @@
 //!
 //! Provides AI-native access to vault search and note operations.
 
+mod prompts;
+mod resources;
 mod server;
 
 pub use server::run_mcp_server;
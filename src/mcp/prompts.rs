@@ -0,0 +1,128 @@
+//! Building blocks of the MCP prompts: notes, inbox memos and search hits
+//! laid out as markdown for the model
+
+use chrono::{DateTime, Local};
+
+use crate::core::inbox::Memo;
+use crate::core::note::Note;
+use crate::search::engine::SearchResult;
+
+/// How a good gist reads, shared by the prompts that ask for one
+pub const GIST_GUIDELINES: &str = "A gist is 2-3 sentences (at most 100 words) saying what the note is about and why it matters, written so it can be found by meaning: semantic search embeds the gist.";
+
+/// Notes in `open_status` (any status when `None`) last modified before
+/// `cutoff`, oldest first
+pub fn stale_notes<'a>(
+    notes: &'a [Note],
+    open_status: Option<&str>,
+    cutoff: DateTime<Local>,
+) -> Vec<&'a Note> {
+    let mut stale: Vec<&Note> = notes
+        .iter()
+        .filter(|n| open_status.is_none_or(|status| n.status() == Some(status)))
+        .filter(|n| n.modified < cutoff)
+        .collect();
+    stale.sort_by_key(|n| n.modified);
+    stale
+}
+
+/// The full note in a fenced block under its name
+pub fn note_section(note: &Note) -> String {
+    format!(
+        "## {}\n\n````markdown\n{}\n````",
+        note.name,
+        note.content.trim_end()
+    )
+}
+
+/// One line of metadata per note
+pub fn note_line(note: &Note) -> String {
+    let mut line = format!(
+        "- [[{}]] ({}, {}, modified {})",
+        note.name,
+        note.note_type().unwrap_or("no type"),
+        note.area().unwrap_or("no area"),
+        note.modified.format("%Y-%m-%d")
+    );
+    if let Some(gist) = note.gist() {
+        line.push_str(&format!(": {}", gist));
+    }
+    line
+}
+
+/// A memo with its index and hash, as `vault_inbox_promote` takes them
+pub fn memo_section(memo: &Memo) -> String {
+    let heading = match &memo.captured {
+        Some(captured) => format!("### Memo {} ({}, hash {})", memo.index, captured, memo.hash),
+        None => format!("### Memo {} (hash {})", memo.index, memo.hash),
+    };
+    format!("{}\n\n{}", heading, memo.text)
+}
+
+/// Search hits as a list, with the matching passage where there is one
+pub fn results_list(results: &[SearchResult]) -> String {
+    if results.is_empty() {
+        return "(none found)".to_string();
+    }
+    results
+        .iter()
+        .map(|r| {
+            let mut line = format!("- [[{}]]", r.title);
+            if let Some(gist) = &r.gist {
+                line.push_str(&format!(": {}", gist));
+            }
+            if let Some(passage) = r.passage.as_ref().map(|p| &p.text).or(r.snippet.as_ref()) {
+                let passage: String = passage.split_whitespace().collect::<Vec<_>>().join(" ");
+                line.push_str(&format!("\n  > {}", passage));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn note(name: &str, status: &str, modified: i64) -> Note {
+        let content = format!(
+            "---\ntype: note\nstatus: {}\narea: tech\ngist: About {}\n---\nBody\n",
+            status, name
+        );
+        let modified = Local.timestamp_opt(modified, 0).unwrap();
+        Note {
+            path: format!("/vault/Notes/{}.md", name).into(),
+            name: name.to_string(),
+            frontmatter: crate::core::frontmatter::Frontmatter::parse(&content).unwrap(),
+            content,
+            frontmatter_error: None,
+            modified,
+            created: modified,
+        }
+    }
+
+    #[test]
+    fn test_stale_notes() {
+        let notes = vec![
+            note("Recent", "active", 2_000_000_000),
+            note("Old", "active", 1_500_000_000),
+            note("Older", "active", 1_400_000_000),
+            note("Finished", "done", 1_400_000_000),
+        ];
+        let cutoff = Local.timestamp_opt(1_900_000_000, 0).unwrap();
+
+        let stale: Vec<&str> = stale_notes(&notes, Some("active"), cutoff)
+            .iter()
+            .map(|n| n.name.as_str())
+            .collect();
+        assert_eq!(stale, vec!["Older", "Old"]);
+        assert_eq!(stale_notes(&notes, None, cutoff).len(), 3);
+
+        let line = note_line(&notes[1]);
+        assert!(line.starts_with("- [[Old]] (note, tech, modified 2017-07-"));
+        assert!(line.ends_with(": About Old"));
+        assert!(note_section(&notes[0]).contains("````markdown\n---\ntype: note"));
+    }
+}
@@ -0,0 +1,257 @@
+//! MCP resources: each note as `vault://note/<name>`, plus note listings by
+//! type (`vault://type/<type>`) and area (`vault://area/<area>`)
+//!
+//! Names in URIs are percent-encoded. Subscribed notes are polled for
+//! changes on disk, so edits made in Obsidian reach the client as
+//! `notifications/resources/updated`.
+
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use rmcp::model::ResourceUpdatedNotificationParam;
+use rmcp::service::{Peer, RoleServer};
+
+use crate::core::note::resolve_note;
+use crate::core::paths::VaultPaths;
+
+/// How often subscribed notes are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A resource URI the server understands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceUri {
+    Note(String),
+    Type(String),
+    Area(String),
+}
+
+impl ResourceUri {
+    pub const TYPE_TEMPLATE: &'static str = "vault://type/{type}";
+    pub const AREA_TEMPLATE: &'static str = "vault://area/{area}";
+
+    pub fn parse(uri: &str) -> Option<Self> {
+        let (kind, name) = uri.strip_prefix("vault://")?.split_once('/')?;
+        let name = decode(name)?;
+        if name.is_empty() {
+            return None;
+        }
+        match kind {
+            "note" => Some(Self::Note(name)),
+            "type" => Some(Self::Type(name)),
+            "area" => Some(Self::Area(name)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ResourceUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, name) = match self {
+            Self::Note(name) => ("note", name),
+            Self::Type(name) => ("type", name),
+            Self::Area(name) => ("area", name),
+        };
+        write!(f, "vault://{}/{}", kind, encode(name))
+    }
+}
+
+/// Percent-encode everything but unreserved URI characters
+fn encode(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn decode(encoded: &str) -> Option<String> {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// File state compared between polls
+type FileStamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> FileStamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// A subscribed note: its name, where it was last found and its state there
+#[derive(Debug)]
+struct Watched {
+    name: String,
+    path: PathBuf,
+    last: FileStamp,
+}
+
+/// Notes the client subscribed to, by URI
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    watched: Mutex<HashMap<String, Watched>>,
+    polling: AtomicBool,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&self, uri: String, name: String, path: PathBuf) {
+        let last = stamp(&path);
+        self.watched
+            .lock()
+            .unwrap()
+            .insert(uri, Watched { name, path, last });
+    }
+
+    pub fn unsubscribe(&self, uri: &str) {
+        self.watched.lock().unwrap().remove(uri);
+    }
+
+    /// URIs whose file was modified, moved or deleted since the last poll
+    ///
+    /// On a change the note is looked up again by name with `resolve`, so a
+    /// moved note is reported once and then followed at its new path. The
+    /// lookups run without holding the lock.
+    pub fn poll(&self, resolve: impl Fn(&str) -> Option<PathBuf>) -> Vec<String> {
+        let stale: Vec<(String, String, PathBuf)> = self
+            .watched
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, note)| stamp(&note.path) != note.last)
+            .map(|(uri, note)| (uri.clone(), note.name.clone(), note.path.clone()))
+            .collect();
+
+        let resolved: Vec<_> = stale
+            .into_iter()
+            .map(|(uri, name, path)| {
+                let path = resolve(&name).unwrap_or(path);
+                let last = stamp(&path);
+                (uri, path, last)
+            })
+            .collect();
+
+        let mut watched = self.watched.lock().unwrap();
+        let mut changed = Vec::new();
+        for (uri, path, last) in resolved {
+            // Skip notes unsubscribed while they were looked up
+            if let Some(note) = watched.get_mut(&uri) {
+                note.path = path;
+                note.last = last;
+                changed.push(uri);
+            }
+        }
+        changed.sort();
+        changed
+    }
+
+    /// Start polling for changes on the first subscription, resolving moved
+    /// notes in the vault at `vault_root`; the task ends when the client
+    /// disconnects
+    pub fn start_polling(self: &Arc<Self>, peer: Peer<RoleServer>, vault_root: PathBuf) {
+        if self.polling.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let subscriptions = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                // The vault is opened at most once per tick, when a note changed
+                let changed = {
+                    let paths = OnceCell::new();
+                    subscriptions.poll(|name| {
+                        let paths = paths
+                            .get_or_init(|| VaultPaths::open(vault_root.clone()).ok())
+                            .as_ref()?;
+                        Some(resolve_note(paths, name).ok()??.path)
+                    })
+                };
+                for uri in changed {
+                    if peer
+                        .notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_round_trip() {
+        let uri = ResourceUri::Note("Café notes (draft) #2".to_string());
+        assert_eq!(
+            uri.to_string(),
+            "vault://note/Caf%C3%A9%20notes%20%28draft%29%20%232"
+        );
+        assert_eq!(ResourceUri::parse(&uri.to_string()), Some(uri));
+        assert_eq!(
+            ResourceUri::parse("vault://area/tech"),
+            Some(ResourceUri::Area("tech".to_string()))
+        );
+        assert_eq!(ResourceUri::parse("vault://note/"), None);
+        assert_eq!(ResourceUri::parse("vault://note/%E"), None);
+        assert_eq!(ResourceUri::parse("vault://folder/Notes"), None);
+        assert_eq!(ResourceUri::parse("file:///tmp/a.md"), None);
+    }
+
+    #[test]
+    fn test_poll_reports_changes() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("Watched.md");
+        let moved = dir.path().join("Moved.md");
+        std::fs::write(&path, "first")?;
+        let resolve = |_: &str| [&path, &moved].into_iter().find(|p| p.exists()).cloned();
+
+        let subscriptions = Subscriptions::default();
+        let uri = ResourceUri::Note("Watched".to_string()).to_string();
+        subscriptions.subscribe(uri.clone(), "Watched".to_string(), path.clone());
+        assert!(subscriptions.poll(resolve).is_empty());
+
+        std::fs::write(&path, "second version")?;
+        assert_eq!(subscriptions.poll(resolve), vec![uri.clone()]);
+        assert!(subscriptions.poll(resolve).is_empty());
+
+        // A moved note is reported once and then watched where it went
+        std::fs::rename(&path, &moved)?;
+        assert_eq!(subscriptions.poll(resolve), vec![uri.clone()]);
+        assert!(subscriptions.poll(resolve).is_empty());
+        std::fs::write(&moved, "edited after the move")?;
+        assert_eq!(subscriptions.poll(resolve), vec![uri.clone()]);
+
+        std::fs::remove_file(&moved)?;
+        assert_eq!(subscriptions.poll(resolve), vec![uri.clone()]);
+        assert!(subscriptions.poll(resolve).is_empty());
+
+        subscriptions.unsubscribe(&uri);
+        std::fs::write(&path, "recreated")?;
+        assert!(subscriptions.poll(resolve).is_empty());
+        Ok(())
+    }
+}
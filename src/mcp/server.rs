@@ -2,18 +2,40 @@
 
 use anyhow::Result;
 use rmcp::{
-    model::{CallToolResult, Content, ServerInfo},
-    tool, tool_router,
-    handler::server::{tool::ToolRouter, wrapper::Parameters},
-    ErrorData as McpError, ServerHandler, ServiceExt,
+    model::{
+        AnnotateAble, CallToolResult, Content, GetPromptRequestParam, GetPromptResult,
+        ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult,
+        PaginatedRequestParam, PromptMessage, PromptMessageRole, RawResource,
+        RawResourceTemplate, ReadResourceRequestParam, ReadResourceResult, ResourceContents,
+        ServerCapabilities, ServerInfo, SubscribeRequestParam, UnsubscribeRequestParam,
+    },
+    service::RequestContext,
+    prompt, prompt_handler, prompt_router, tool, tool_handler, tool_router,
+    handler::server::{
+        router::prompt::PromptRouter, tool::ToolRouter, wrapper::Parameters,
+    },
+    ErrorData as McpError, RoleServer, ServerHandler, ServiceExt,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-
-use crate::core::note::{collect_all_notes, collect_note_names};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::prompts::{self, GIST_GUIDELINES};
+use super::resources::{ResourceUri, Subscriptions};
+use crate::core::config::VaultSchema;
+use crate::core::attachment::{AttachmentIndex, ReferenceIssue, References};
+use crate::core::edit::{content_version, BodyEdit, NoteEdit};
+use crate::core::frontmatter::FrontmatterUpdate;
+use crate::core::inbox::{self, Inbox};
+use crate::core::note::{collect_all_notes, resolve_note, Note};
 use crate::core::paths::VaultPaths;
-use crate::search::engine::SearchEngine;
+use crate::core::template::NewNote;
+use crate::core::wikilink::LinkResolver;
+use crate::search::embedder::embedder_status;
+use crate::search::engine::{simple_search, SearchEngine, SearchMode, SearchResult};
+use crate::search::filter::{parse_time, SearchFilter};
 use std::collections::HashSet;
 
 /// Parameters for vault_search tool
@@ -26,6 +48,68 @@ pub struct SearchParams {
     #[schemars(description = "Maximum number of results (default: 5)")]
     #[serde(default = "default_limit")]
     pub limit: usize,
+    /// Retrieval strategy: hybrid (default), vector or keyword
+    #[schemars(description = "Search mode: hybrid (default, vector + keyword), vector or keyword")]
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Only notes of this type
+    #[schemars(description = "Filter by type: note, term, project, log")]
+    #[serde(default)]
+    pub note_type: Option<String>,
+    /// Only notes with this status
+    #[schemars(description = "Filter by status: active, done, archived")]
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Only notes in this area
+    #[schemars(description = "Filter by area: work, tech, life, career, learning, reference")]
+    #[serde(default)]
+    pub area: Option<String>,
+    /// Notes must have all of these tags
+    #[schemars(
+        description = "Only notes with all of these tags (a tag also matches its nested tags)"
+    )]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Notes must have at least one of these tags
+    #[schemars(description = "Only notes with at least one of these tags")]
+    #[serde(default)]
+    pub any_tags: Vec<String>,
+    /// Path prefix relative to the vault root
+    #[schemars(
+        description = "Only notes under this path, relative to the vault root (e.g. \"Projects/\")"
+    )]
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    /// Modified on or after this date
+    #[schemars(description = "Only notes modified on or after this date (YYYY-MM-DD or RFC 3339)")]
+    #[serde(default)]
+    pub modified_after: Option<String>,
+    /// Modified before this date
+    #[schemars(description = "Only notes modified before this date (YYYY-MM-DD or RFC 3339)")]
+    #[serde(default)]
+    pub modified_before: Option<String>,
+}
+
+impl SearchParams {
+    fn filter(&self) -> Result<SearchFilter, McpError> {
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(parse_time)
+                .transpose()
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))
+        };
+        Ok(SearchFilter {
+            note_type: self.note_type.clone(),
+            status: self.status.clone(),
+            area: self.area.clone(),
+            all_tags: self.tags.clone(),
+            any_tags: self.any_tags.clone(),
+            path_prefix: self.path_prefix.clone(),
+            modified_after: parse(&self.modified_after)?,
+            modified_before: parse(&self.modified_before)?,
+        })
+    }
 }
 
 fn default_limit() -> usize {
@@ -35,11 +119,214 @@ fn default_limit() -> usize {
 /// Parameters for vault_get_note tool
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetNoteParams {
-    /// Note title (e.g., "GPU 기술 허브")
-    #[schemars(description = "Note title to retrieve")]
+    /// Note title or alias (e.g., "GPU 기술 허브")
+    #[schemars(description = "Note title or alias to retrieve")]
     pub note: String,
 }
 
+/// Parameters for vault_create_note tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateNoteParams {
+    /// Note title, used as the file name
+    #[schemars(description = "Note title; becomes the file name and must be unique in the vault")]
+    pub title: String,
+    /// Note type (note, term, project, log)
+    #[schemars(description = "Note type: note, term, project, log")]
+    pub note_type: String,
+    /// Note status (active, done, archived)
+    #[schemars(
+        description = "Status: active, done, archived (default: from the template, else the first allowed status)"
+    )]
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Note area (work, tech, life, career, learning, reference)
+    #[schemars(description = "Area: work, tech, life, career, learning, reference")]
+    #[serde(default)]
+    pub area: Option<String>,
+    /// Tags, lowercase
+    #[schemars(description = "Tags (lowercase, no hierarchy, at most 5 by default)")]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Summary of the note
+    #[schemars(description = "Summary of the note: 2-3 sentences, at most 100 words")]
+    #[serde(default)]
+    pub gist: Option<String>,
+    /// Markdown body
+    #[schemars(description = "Markdown body, placed before the template's footer")]
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+/// Parameters for vault_update_note tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UpdateNoteParams {
+    /// Note title or alias
+    #[schemars(description = "Exact note title or alias to update; fails if several notes match")]
+    pub note: String,
+    /// Version returned by vault_get_note
+    #[schemars(
+        description = "Version from vault_get_note; the update is refused if the note changed since"
+    )]
+    pub version: String,
+    /// New status
+    #[schemars(description = "New status: active, done, archived")]
+    #[serde(default)]
+    pub status: Option<String>,
+    /// New area
+    #[schemars(description = "New area: work, tech, life, career, learning, reference")]
+    #[serde(default)]
+    pub area: Option<String>,
+    /// New tags, replacing the current ones
+    #[schemars(description = "New tags, replacing the current ones")]
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// New gist
+    #[schemars(description = "New gist: 2-3 sentences, at most 100 words")]
+    #[serde(default)]
+    pub gist: Option<String>,
+    /// Heading of the section to edit
+    #[schemars(
+        description = "Heading of the section to edit (e.g. \"Log\"); a missing section is created when appending"
+    )]
+    #[serde(default)]
+    pub section: Option<String>,
+    /// Text for the section
+    #[schemars(
+        description = "Markdown appended to the section, or replacing it with replace_section"
+    )]
+    #[serde(default)]
+    pub section_text: Option<String>,
+    /// Replace the section instead of appending to it
+    #[schemars(description = "Replace the section's content instead of appending to it")]
+    #[serde(default)]
+    pub replace_section: bool,
+    /// Text inserted before the footer
+    #[schemars(
+        description = "Markdown inserted just before the <!-- footer_start --> marker (or at the end)"
+    )]
+    #[serde(default)]
+    pub before_footer: Option<String>,
+}
+
+impl UpdateNoteParams {
+    fn edit(&self) -> Result<NoteEdit, McpError> {
+        let mut frontmatter = FrontmatterUpdate::new();
+        for (key, value) in [
+            ("status", &self.status),
+            ("area", &self.area),
+            ("gist", &self.gist),
+        ] {
+            if let Some(value) = value {
+                frontmatter = frontmatter.set(key, value.as_str());
+            }
+        }
+        if let Some(tags) = &self.tags {
+            frontmatter = frontmatter.set_tags(tags);
+        }
+
+        let mut body = Vec::new();
+        match (&self.section, &self.section_text) {
+            (Some(heading), Some(text)) => body.push(if self.replace_section {
+                BodyEdit::ReplaceSection {
+                    heading: heading.clone(),
+                    text: text.clone(),
+                }
+            } else {
+                BodyEdit::AppendToSection {
+                    heading: heading.clone(),
+                    text: text.clone(),
+                }
+            }),
+            (None, None) => {}
+            _ => {
+                return Err(McpError::invalid_params(
+                    "section and section_text must be given together",
+                    None,
+                ))
+            }
+        }
+        if let Some(text) = &self.before_footer {
+            body.push(BodyEdit::InsertBeforeFooter(text.clone()));
+        }
+
+        let edit = NoteEdit { frontmatter, body };
+        if edit.is_empty() {
+            return Err(McpError::invalid_params("Nothing to update", None));
+        }
+        Ok(edit)
+    }
+}
+
+/// Parameters for vault_capture tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CaptureParams {
+    /// Memo text
+    #[schemars(description = "Memo text to append to the inbox")]
+    pub text: String,
+}
+
+/// Parameters for vault_inbox_promote tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PromoteParams {
+    /// Memo index from vault_inbox_list
+    #[schemars(description = "1-based memo index from vault_inbox_list")]
+    pub index: usize,
+    /// Memo hash from vault_inbox_list
+    #[schemars(
+        description = "The memo's hash from vault_inbox_list; the promotion is refused if the memo at that index has another one"
+    )]
+    pub hash: String,
+    /// Title of the new note
+    #[schemars(description = "Title of the new note; must be unique in the vault")]
+    pub title: String,
+    /// Note type (note, term, project, log)
+    #[schemars(description = "Note type: note, term, project, log")]
+    pub note_type: String,
+    /// Note status
+    #[schemars(
+        description = "Status: active, done, archived (default: from the template, else the first allowed status)"
+    )]
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Note area
+    #[schemars(description = "Area: work, tech, life, career, learning, reference")]
+    #[serde(default)]
+    pub area: Option<String>,
+    /// Tags
+    #[schemars(description = "Tags (lowercase, no hierarchy, at most 5 by default)")]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Summary of the memo
+    #[schemars(description = "Summary of the memo: 2-3 sentences, at most 100 words")]
+    #[serde(default)]
+    pub gist: Option<String>,
+}
+
+/// Arguments of the write_gist and suggest_links prompts
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NotePromptParams {
+    /// Note title or alias
+    #[schemars(description = "Note title or alias")]
+    pub note: String,
+}
+
+/// Arguments of the weekly_review prompt
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReviewPromptParams {
+    /// Days without changes after which an open note is stale
+    #[schemars(
+        description = "Days without changes after which an open note is stale (default: 30)"
+    )]
+    #[serde(default)]
+    pub days: Option<String>,
+    /// Status of open notes
+    #[schemars(
+        description = "Status of the notes to review, one of the allowed statuses (default: active)"
+    )]
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
 /// Parameters for vault_list_notes tool
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ListNotesParams {
@@ -112,6 +399,12 @@ struct SearchResultJson {
     note_type: Option<String>,
     area: Option<String>,
     score: f32,
+    /// Best-matching passage of the note body
+    passage: Option<String>,
+    /// Headings enclosing the passage, outermost first
+    heading_path: Vec<String>,
+    /// Keyword match in context
+    snippet: Option<String>,
 }
 
 /// Note info for JSON output
@@ -124,67 +417,137 @@ struct NoteInfoJson {
     area: Option<String>,
     gist: Option<String>,
     tags: Vec<String>,
+    /// Content version to pass to vault_update_note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
 }
 
 /// Vault MCP Service
 #[derive(Clone)]
 pub struct VaultService {
     vault_path: PathBuf,
-    db_path: PathBuf,
-    model_path: PathBuf,
+    /// Held while a tool writes to the vault, so concurrent calls cannot
+    /// both pass the same existence check
+    write_lock: Arc<Mutex<()>>,
+    /// Notes subscribed to as resources
+    subscriptions: Arc<Subscriptions>,
     tool_router: ToolRouter<Self>,
+    prompt_router: PromptRouter<Self>,
 }
 
 impl VaultService {
     pub fn new(vault_path: PathBuf) -> Self {
-        let tools_path = vault_path.join(".opencode/tools");
-        let db_path = tools_path.join("data/search.db");
-        let model_path = tools_path.join("models/model.onnx"); // Not used with HTP
-
         Self {
             vault_path,
-            db_path,
-            model_path,
+            write_lock: Arc::new(Mutex::new(())),
+            subscriptions: Arc::new(Subscriptions::default()),
             tool_router: Self::tool_router(),
+            prompt_router: Self::prompt_router(),
         }
     }
 
     fn get_engine(&self) -> Result<SearchEngine, McpError> {
-        SearchEngine::new(&self.vault_path, &self.db_path, &self.model_path)
+        let vault_paths = self.get_vault_paths()?;
+        SearchEngine::new(&self.vault_path, &vault_paths.search_db())
             .map_err(|e| McpError::internal_error(format!("Failed to create engine: {}", e), None))
     }
 
-    fn get_vault_paths(&self) -> VaultPaths {
-        VaultPaths::from_root(self.vault_path.clone())
+    fn get_vault_paths(&self) -> Result<VaultPaths, McpError> {
+        VaultPaths::open(self.vault_path.clone())
+            .map_err(|e| McpError::internal_error(format!("Failed to load vault config: {:#}", e), None))
+    }
+
+    /// Notes related to `query`: hybrid search, falling back to keyword
+    /// search when the embedder is unavailable and to gist matching when
+    /// there is no index
+    fn related_notes(
+        &self,
+        vault_paths: &VaultPaths,
+        query: &str,
+        limit: usize,
+    ) -> Vec<SearchResult> {
+        let filter = SearchFilter::default();
+        match self.get_engine() {
+            Ok(mut engine) => engine
+                .search_with(SearchMode::Hybrid, query, &filter, limit)
+                .or_else(|_| engine.keyword_search(query, &filter, limit))
+                .unwrap_or_default(),
+            Err(_) => simple_search(vault_paths, query, &filter, limit),
+        }
     }
 }
 
 #[tool_router]
 impl VaultService {
     /// Search notes using semantic similarity
-    #[tool(description = "Search Second Brain Vault using semantic similarity. Returns notes with similar meaning to the query based on gist field embeddings.")]
+    #[tool(description = "Search Second Brain Vault using semantic similarity. Returns notes with similar meaning to the query based on embeddings of the gist and body passages, with the best-matching passage of each note. Hybrid mode (default) fuses this with BM25 keyword matching; when the embedder is unavailable, keyword results are returned with a notice. Optional type, status, area, tag, path and modification-date filters narrow the notes searched.")]
     async fn vault_search(
         &self,
         params: Parameters<SearchParams>,
     ) -> Result<CallToolResult, McpError> {
         let mut engine = self.get_engine()?;
         // Clamp limit: default 5, max 100 (DoS prevention)
-        let limit = params.0.limit.max(1).min(100);
+        let limit = params.0.limit.clamp(1, 100);
         let limit = if limit == 1 && params.0.limit == 0 { 5 } else { limit };
 
-        let results = engine.search(&params.0.query, limit).map_err(|e| {
-            McpError::internal_error(format!("Search failed: {}", e), None)
-        })?;
+        let mode = match &params.0.mode {
+            Some(mode) => mode
+                .parse::<SearchMode>()
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?,
+            None => SearchMode::default(),
+        };
+        let filter = params.0.filter()?;
+
+        // Keyword search still works when the embedder cannot be loaded;
+        // the client is told the results are keyword-only
+        let vault_paths = self.get_vault_paths()?;
+        let embedder = embedder_status(&vault_paths.config.search.embedder, &vault_paths.root);
+        let mut degraded = None;
+        let results = if mode.uses_embedder() && !embedder.is_ready() {
+            degraded = Some(match &embedder.unavailable {
+                Some(reason) => reason.clone(),
+                None => format!(
+                    "missing {}",
+                    embedder
+                        .missing
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            });
+            engine.keyword_search(&params.0.query, &filter, limit)
+        } else {
+            engine
+                .search_with(mode, &params.0.query, &filter, limit)
+                .or_else(|e| {
+                    if !mode.uses_embedder() {
+                        return Err(e);
+                    }
+                    degraded = Some(e.to_string());
+                    engine.keyword_search(&params.0.query, &filter, limit)
+                })
+        }
+        .map_err(|e| McpError::internal_error(format!("Search failed: {}", e), None))?;
 
         let json_results: Vec<SearchResultJson> = results
             .into_iter()
-            .map(|r| SearchResultJson {
-                title: r.title,
-                path: r.path,
-                gist: r.gist,
-                note_type: r.note_type,
-                area: r.area,
-                score: r.score,
+            .map(|r| {
+                let (passage, heading_path) = match r.passage {
+                    Some(p) => (Some(p.text), p.heading_path),
+                    None => (None, Vec::new()),
+                };
+                SearchResultJson {
+                    title: r.title,
+                    path: r.path,
+                    gist: r.gist,
+                    note_type: r.note_type,
+                    area: r.area,
+                    score: r.score,
+                    passage,
+                    heading_path,
+                    snippet: r.snippet,
+                }
             })
             .collect();
 
@@ -192,25 +555,26 @@ impl VaultService {
             McpError::internal_error(format!("JSON serialization failed: {}", e), None)
         })?;
 
-        Ok(CallToolResult::success(vec![Content::text(output)]))
+        let mut content = Vec::new();
+        if let Some(reason) = degraded {
+            content.push(Content::text(format!(
+                "Embedder {} not available ({}); showing keyword results instead of {} search.",
+                embedder.id, reason, mode
+            )));
+        }
+        content.push(Content::text(output));
+        Ok(CallToolResult::success(content))
     }
 
     /// Get full content of a specific note
-    #[tool(description = "Get the full content and metadata of a specific note from Second Brain Vault.")]
+    #[tool(description = "Get the full content and metadata of a specific note from Second Brain Vault. The metadata includes the version to pass to vault_update_note.")]
     async fn vault_get_note(
         &self,
         params: Parameters<GetNoteParams>,
     ) -> Result<CallToolResult, McpError> {
-        let vault_paths = self.get_vault_paths();
-        let notes = collect_all_notes(&vault_paths);
+        let vault_paths = self.get_vault_paths()?;
         let note_name = &params.0.note;
-
-        // Find note by title or path
-        let found = notes.into_iter().find(|n| {
-            n.name == *note_name
-                || n.path.to_string_lossy().contains(note_name)
-                || n.path.file_stem().map(|s| s.to_string_lossy().to_string()) == Some(note_name.clone())
-        });
+        let found = find_note(&vault_paths, note_name);
 
         match found {
             Some(n) => {
@@ -219,13 +583,8 @@ impl VaultService {
                 })?;
 
                 let info = NoteInfoJson {
-                    title: n.name.clone(),
-                    path: n.path.to_string_lossy().to_string(),
-                    note_type: n.note_type().map(String::from),
-                    status: n.status().map(String::from),
-                    area: n.area().map(String::from),
-                    gist: n.gist().map(String::from),
-                    tags: n.tags(),
+                    version: Some(content_version(&content)),
+                    ..note_info(&n)
                 };
 
                 let output = format!(
@@ -250,33 +609,26 @@ impl VaultService {
         &self,
         params: Parameters<ListNotesParams>,
     ) -> Result<CallToolResult, McpError> {
-        let vault_paths = self.get_vault_paths();
+        let vault_paths = self.get_vault_paths()?;
         let notes = collect_all_notes(&vault_paths);
         let note_type = &params.0.note_type;
         let area = &params.0.area;
         // Clamp limit: default 50, max 500 (DoS prevention)
-        let limit = params.0.limit.max(1).min(500);
+        let limit = params.0.limit.clamp(1, 500);
         let limit = if limit == 1 && params.0.limit == 0 { 50 } else { limit };
 
         let filtered: Vec<NoteInfoJson> = notes
             .into_iter()
             .filter(|n| {
-                note_type.as_ref().map_or(true, |t| {
-                    n.note_type().map_or(false, |nt| nt == t)
-                }) && area.as_ref().map_or(true, |a| {
-                    n.area().map_or(false, |na| na == a)
-                })
+                note_type
+                    .as_ref()
+                    .is_none_or(|t| n.note_type().is_some_and(|nt| nt == t))
+                    && area
+                        .as_ref()
+                        .is_none_or(|a| n.area().is_some_and(|na| na == a))
             })
             .take(limit)
-            .map(|n| NoteInfoJson {
-                title: n.name.clone(),
-                path: n.path.to_string_lossy().to_string(),
-                note_type: n.note_type().map(String::from),
-                status: n.status().map(String::from),
-                area: n.area().map(String::from),
-                gist: n.gist().map(String::from),
-                tags: n.tags(),
-            })
+            .map(|n| note_info(&n))
             .collect();
 
         let output = serde_json::to_string_pretty(&filtered).map_err(|e| {
@@ -286,10 +638,154 @@ impl VaultService {
         Ok(CallToolResult::success(vec![Content::text(output)]))
     }
 
+    /// Create a note from the vault's template for its type
+    #[tool(description = "Create a new note in Second Brain Vault. The note is filled from the template for its type in the templates folder, placed in the folder its type and status belong in, and validated against the vault schema before writing. Fails if a note with the same title or alias exists.")]
+    async fn vault_create_note(
+        &self,
+        params: Parameters<CreateNoteParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let params = params.0;
+        let note = NewNote {
+            title: params.title,
+            note_type: params.note_type,
+            status: params.status,
+            area: params.area,
+            tags: params.tags,
+            gist: params.gist,
+            body: params.body,
+        };
+
+        let _guard = self.write_lock.lock().await;
+        let path = note.create(&vault_paths).map_err(|e| {
+            McpError::invalid_params(format!("Failed to create note: {:#}", e), None)
+        })?;
+
+        let output = serde_json::json!({
+            "title": note.title.trim(),
+            "path": path.strip_prefix(&vault_paths.root).unwrap_or(&path).to_string_lossy(),
+        });
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&output).unwrap_or_default(),
+        )]))
+    }
+
+    /// Update a note's frontmatter and body
+    #[tool(
+        description = "Update a note in Second Brain Vault: set status, area, tags or gist, append to or replace a heading section, and insert text before the footer. Requires the version returned by vault_get_note and fails if the note changed since, so edits made in Obsidian are never overwritten. Returns the new version."
+    )]
+    async fn vault_update_note(
+        &self,
+        params: Parameters<UpdateNoteParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let edit = params.0.edit()?;
+
+        let _guard = self.write_lock.lock().await;
+        let Some(mut note) = resolve_note(&vault_paths, &params.0.note)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?
+        else {
+            return Err(McpError::invalid_params(
+                format!("Note not found: {}", params.0.note),
+                None,
+            ));
+        };
+        let changed = note
+            .apply_edit(&edit, &params.0.version, &vault_paths.config.schema)
+            .map_err(|e| {
+                McpError::invalid_params(format!("Failed to update note: {:#}", e), None)
+            })?;
+
+        let output = serde_json::json!({
+            "title": note.name,
+            "path": note.path.strip_prefix(&vault_paths.root).unwrap_or(&note.path).to_string_lossy(),
+            "changed": changed,
+            "version": note.version(),
+        });
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&output).unwrap_or_default(),
+        )]))
+    }
+
+    /// Append a memo to the inbox
+    #[tool(
+        description = "Capture a quick memo in the Second Brain Vault inbox. The memo is appended with a timestamp for later triage."
+    )]
+    async fn vault_capture(
+        &self,
+        params: Parameters<CaptureParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let _guard = self.write_lock.lock().await;
+        let memo = inbox::capture(&vault_paths, &params.0.text).map_err(|e| {
+            McpError::invalid_params(format!("Failed to capture memo: {:#}", e), None)
+        })?;
+
+        let output = serde_json::to_string_pretty(&memo).map_err(|e| {
+            McpError::internal_error(format!("JSON serialization failed: {}", e), None)
+        })?;
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    /// List the memos in the inbox
+    #[tool(
+        description = "List the memos waiting in the Second Brain Vault inbox, with the indices and hashes vault_inbox_promote takes."
+    )]
+    async fn vault_inbox_list(&self) -> Result<CallToolResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let inbox = Inbox::load(&vault_paths.inbox).map_err(|e| {
+            McpError::internal_error(format!("Failed to read inbox: {:#}", e), None)
+        })?;
+
+        let output = serde_json::to_string_pretty(&inbox.memos).map_err(|e| {
+            McpError::internal_error(format!("JSON serialization failed: {}", e), None)
+        })?;
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    /// Turn an inbox memo into a note
+    #[tool(
+        description = "Promote an inbox memo to a new note: the memo becomes the body of a note created like vault_create_note (template, folder and schema validation), and is removed from the inbox. Requires the memo's index and hash from vault_inbox_list and fails if the memo at that index changed. Indices shift after a promotion; list the inbox again before promoting another memo."
+    )]
+    async fn vault_inbox_promote(
+        &self,
+        params: Parameters<PromoteParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let params = params.0;
+        let note = NewNote {
+            title: params.title,
+            note_type: params.note_type,
+            status: params.status,
+            area: params.area,
+            tags: params.tags,
+            gist: params.gist,
+            body: None,
+        };
+
+        let _guard = self.write_lock.lock().await;
+        let path = inbox::promote(&vault_paths, params.index, &params.hash, note).map_err(|e| {
+            McpError::invalid_params(format!("Failed to promote memo: {:#}", e), None)
+        })?;
+
+        let output = serde_json::json!({
+            "promoted": params.index,
+            "path": path.strip_prefix(&vault_paths.root).unwrap_or(&path).to_string_lossy(),
+        });
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&output).unwrap_or_default(),
+        )]))
+    }
+
     /// Get vault health score
     #[tool(description = "Get Second Brain Vault health score (0-100) based on schema compliance, gist coverage, and link integrity.")]
     async fn vault_health(&self) -> Result<CallToolResult, McpError> {
-        let vault_paths = self.get_vault_paths();
+        let vault_paths = self.get_vault_paths()?;
         let notes = collect_all_notes(&vault_paths);
 
         let total = notes.len();
@@ -319,7 +815,7 @@ impl VaultService {
     /// Get vault status summary
     #[tool(description = "Get Second Brain Vault status summary including note counts by type and area.")]
     async fn vault_status(&self) -> Result<CallToolResult, McpError> {
-        let vault_paths = self.get_vault_paths();
+        let vault_paths = self.get_vault_paths()?;
         let notes = collect_all_notes(&vault_paths);
 
         let mut by_type: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
@@ -346,30 +842,31 @@ impl VaultService {
     }
 
     /// Run vault policy compliance audit
-    #[tool(description = "Run vault policy compliance audit. Returns check results for schema validation, wikilinks, folder-type matching, gist coverage, tag usage, and orphan detection.")]
+    #[tool(description = "Run vault policy compliance audit. Returns check results for schema validation, wikilinks, folder-type matching, gist coverage, tag usage, orphan detection, markdown links, and attachments.")]
     async fn vault_audit(
         &self,
         params: Parameters<AuditParams>,
     ) -> Result<CallToolResult, McpError> {
-        let vault_paths = self.get_vault_paths();
+        let vault_paths = self.get_vault_paths()?;
         let notes = collect_all_notes(&vault_paths);
-        let note_names = collect_note_names(&vault_paths);
+        let attachments = AttachmentIndex::for_vault(&vault_paths);
+        let resolver = LinkResolver::from_notes(&notes).with_files(attachments.files());
         let quick = params.0.quick;
         let verbose = params.0.verbose;
 
         let mut checks = Vec::new();
 
         // Schema check
-        let schema_check = self.check_schema(&notes, verbose);
+        let schema_check = self.check_schema(&notes, &vault_paths.config.schema, verbose);
         checks.push(schema_check);
 
         // Wikilinks check
-        let wikilinks_check = self.check_wikilinks(&notes, &note_names, verbose);
+        let wikilinks_check = self.check_wikilinks(&notes, &resolver, verbose);
         checks.push(wikilinks_check);
 
         if !quick {
             // Folder-type match check
-            let folder_type_check = self.check_folder_type(&notes, verbose);
+            let folder_type_check = self.check_folder_type(&notes, &vault_paths, verbose);
             checks.push(folder_type_check);
 
             // Gist coverage check
@@ -381,8 +878,18 @@ impl VaultService {
             checks.push(tags_check);
 
             // Orphan notes check
-            let orphans_check = self.check_orphans(&notes, &note_names, verbose);
+            let orphans_check = self.check_orphans(&notes, &resolver, verbose);
             checks.push(orphans_check);
+
+            // Markdown link, attachment and orphan attachment checks
+            let references: Vec<References> = notes
+                .iter()
+                .map(|note| attachments.check_note(note, &resolver))
+                .collect();
+            checks.extend(self.check_references(&notes, &references, verbose));
+            let orphan_attachments_check =
+                self.check_orphan_attachments(&references, &attachments, verbose);
+            checks.push(orphan_attachments_check);
         }
 
         let passed = checks.iter().filter(|c| c.status == "pass").count();
@@ -404,16 +911,295 @@ impl VaultService {
     }
 }
 
+#[prompt_router]
+impl VaultService {
+    /// Ask for a gist of a note
+    #[prompt(
+        name = "write_gist",
+        description = "Write or improve the gist of a note, with the note's content and the version to save it with"
+    )]
+    async fn write_gist_prompt(
+        &self,
+        params: Parameters<NotePromptParams>,
+    ) -> Result<GetPromptResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let note = find_note(&vault_paths, &params.0.note).ok_or_else(|| {
+            McpError::invalid_params(format!("Note not found: {}", params.0.note), None)
+        })?;
+        let current = match note.gist() {
+            Some(gist) => format!(
+                "Its current gist is:\n\n> {}\n\nKeep what is accurate and sharpen the rest.",
+                gist
+            ),
+            None => "It has no gist yet.".to_string(),
+        };
+
+        let text = format!(
+            "Write a gist for the note [[{name}]]. {current}\n\n{guidelines}\n\nReply with the gist, then save it with vault_update_note (note: \"{name}\", version: \"{version}\").\n\n{note}",
+            name = note.name,
+            current = current,
+            guidelines = GIST_GUIDELINES,
+            version = note.version(),
+            note = prompts::note_section(&note),
+        );
+        Ok(GetPromptResult {
+            description: Some(format!("Write a gist for {}", note.name)),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    /// Ask for wikilinks to add to a note
+    #[prompt(
+        name = "suggest_links",
+        description = "Suggest wikilinks for a note, from the notes related to it by search that it does not link to yet"
+    )]
+    async fn suggest_links_prompt(
+        &self,
+        params: Parameters<NotePromptParams>,
+    ) -> Result<GetPromptResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let notes = collect_all_notes(&vault_paths);
+        let resolver = LinkResolver::from_notes(&notes);
+        let note = find_note(&vault_paths, &params.0.note).ok_or_else(|| {
+            McpError::invalid_params(format!("Note not found: {}", params.0.note), None)
+        })?;
+
+        let linked: HashSet<String> = resolver
+            .linked_notes(&note.name, &note.content)
+            .into_iter()
+            .collect();
+        let backlinks: Vec<&Note> = notes
+            .iter()
+            .filter(|n| n.name != note.name)
+            .filter(|n| {
+                resolver
+                    .linked_notes(&n.name, &n.content)
+                    .contains(&note.name)
+            })
+            .collect();
+        let query = note.gist().unwrap_or(&note.name);
+        let candidates: Vec<SearchResult> = self
+            .related_notes(&vault_paths, query, 10 + linked.len())
+            .into_iter()
+            .filter(|r| r.title != note.name && !linked.contains(&r.title))
+            .take(10)
+            .collect();
+
+        let mut linked: Vec<String> = linked.into_iter().map(|l| format!("- [[{}]]", l)).collect();
+        linked.sort();
+        let backlinks: Vec<String> = backlinks.iter().map(|n| prompts::note_line(n)).collect();
+        let list = |lines: Vec<String>| {
+            if lines.is_empty() {
+                "(none)".to_string()
+            } else {
+                lines.join("\n")
+            }
+        };
+
+        let text = format!(
+            "Suggest wikilinks for the note [[{name}]]. Pick up to 5 of the candidate notes below that a reader of this note would actually want to follow, say in one line why each connection holds and where in the note the link belongs, and skip candidates that only share words with it. Once I agree, add the links with vault_update_note (note: \"{name}\", version: \"{version}\").\n\n{note}\n\n## Already links to\n\n{linked}\n\n## Linked from\n\n{backlinks}\n\n## Candidates from search\n\n{candidates}",
+            name = note.name,
+            version = note.version(),
+            note = prompts::note_section(&note),
+            linked = list(linked),
+            backlinks = list(backlinks),
+            candidates = prompts::results_list(&candidates),
+        );
+        Ok(GetPromptResult {
+            description: Some(format!("Suggest links for {}", note.name)),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    /// Ask to triage the inbox memo by memo
+    #[prompt(
+        name = "triage_inbox",
+        description = "Triage the inbox: decide for each memo whether to promote it to a note, merge it into an existing one or drop it"
+    )]
+    async fn triage_inbox_prompt(&self) -> Result<GetPromptResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let inbox = Inbox::load(&vault_paths.inbox)
+            .map_err(|e| McpError::internal_error(format!("Failed to read inbox: {}", e), None))?;
+        let schema = &vault_paths.config.schema;
+        let allowed = |field: &str| {
+            schema
+                .allowed(field)
+                .map_or_else(|| "any".to_string(), |values| values.join(", "))
+        };
+
+        // Keyword search only: it needs no embedder, so one lookup per memo stays cheap
+        let engine = self.get_engine().ok();
+        let filter = SearchFilter::default();
+        let memos: Vec<String> = inbox
+            .memos
+            .iter()
+            .map(|memo| {
+                let related = engine
+                    .as_ref()
+                    .and_then(|e| e.keyword_search(&memo.text, &filter, 3).ok())
+                    .unwrap_or_default();
+                format!(
+                    "{}\n\nRelated notes:\n{}",
+                    prompts::memo_section(memo),
+                    prompts::results_list(&related)
+                )
+            })
+            .collect();
+        let memos = if memos.is_empty() {
+            "The inbox is empty.".to_string()
+        } else {
+            memos.join("\n\n")
+        };
+
+        let text = format!(
+            "Help me triage my inbox. For each memo below, suggest one of: promote it to a new note (with title, type, area, tags and gist), merge it into an existing note (name it), or drop it. Allowed types: {types}. Allowed areas: {areas}. {guidelines}\n\nWait for my go-ahead, then apply each decision: vault_inbox_promote (with the memo's index and hash) for new notes, vault_update_note for merges. Indices shift after a promotion, so check vault_inbox_list before the next one.\n\n## Inbox ({count} memos)\n\n{memos}",
+            types = allowed("type"),
+            areas = allowed("area"),
+            guidelines = GIST_GUIDELINES,
+            count = inbox.memos.len(),
+            memos = memos,
+        );
+        Ok(GetPromptResult {
+            description: Some("Triage the inbox".to_string()),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    /// Ask to review notes that have not moved in a while
+    #[prompt(
+        name = "weekly_review",
+        description = "Weekly review of stale notes: notes in a status (default: active) unchanged for a number of days, with their audit findings"
+    )]
+    async fn weekly_review_prompt(
+        &self,
+        params: Parameters<ReviewPromptParams>,
+    ) -> Result<GetPromptResult, McpError> {
+        const MAX_NOTES: usize = 30;
+
+        let days = match params.0.days.as_deref().map(str::trim) {
+            Some(days) if !days.is_empty() => {
+                days.parse::<u32>().ok().filter(|d| *d > 0).ok_or_else(|| {
+                    McpError::invalid_params(
+                        format!("Invalid days '{}': expected a positive number", days),
+                        None,
+                    )
+                })?
+            }
+            _ => 30,
+        };
+        let vault_paths = self.get_vault_paths()?;
+        let notes = collect_all_notes(&vault_paths);
+        let resolver = LinkResolver::from_notes(&notes);
+        let schema = &vault_paths.config.schema;
+        let open_status = match params.0.status.as_deref().map(str::trim) {
+            Some(status) if !status.is_empty() => status,
+            _ => "active",
+        };
+        if let Some(allowed) = schema.allowed("status") {
+            if !allowed.iter().any(|s| s == open_status) {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Invalid status '{}': expected one of {}",
+                        open_status,
+                        allowed.join(", ")
+                    ),
+                    None,
+                ));
+            }
+        }
+        let cutoff = chrono::Local::now() - chrono::Duration::days(i64::from(days));
+        let stale = prompts::stale_notes(&notes, Some(open_status), cutoff);
+
+        let checks = [
+            self.check_schema(&notes, schema, true),
+            self.check_wikilinks(&notes, &resolver, true),
+            self.check_gist(&notes, true),
+            self.check_tags(&notes, true),
+            self.check_orphans(&notes, &resolver, true),
+        ];
+        let summary: Vec<String> = checks
+            .iter()
+            .map(|c| match &c.details {
+                Some(details) => format!("- {}: {}, {}", c.name, c.status, details),
+                None => format!("- {}: {}, {} errors", c.name, c.status, c.errors),
+            })
+            .collect();
+        let stale_list: Vec<String> = stale
+            .iter()
+            .take(MAX_NOTES)
+            .map(|note| {
+                let findings = checks
+                    .iter()
+                    .flat_map(|c| c.error_list.iter().flatten())
+                    .filter(|e| e.note == note.name)
+                    .map(|e| format!("\n  - {}", e.message));
+                std::iter::once(prompts::note_line(note))
+                    .chain(findings)
+                    .collect::<String>()
+            })
+            .collect();
+        let stale_list = if stale_list.is_empty() {
+            "No stale notes.".to_string()
+        } else {
+            stale_list.join("\n")
+        };
+
+        let text = format!(
+            "Run a weekly review of my vault with me. The notes below are {status} but have not changed in {days} days, oldest first, each with its audit findings. For each, suggest whether to close it (change its status), refresh it, or link it into current work, and how to fix its findings. Go through a few at a time; apply what I agree to with vault_update_note, taking the version from vault_get_note.\n\n## Vault audit\n\n{summary}\n\n## Stale notes ({shown} of {total})\n\n{stale}",
+            status = open_status,
+            days = days,
+            summary = summary.join("\n"),
+            shown = stale.len().min(MAX_NOTES),
+            total = stale.len(),
+            stale = stale_list,
+        );
+        Ok(GetPromptResult {
+            description: Some(format!("Review notes unchanged for {} days", days)),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+}
+
+/// Metadata of a note for listings
+fn note_info(n: &Note) -> NoteInfoJson {
+    NoteInfoJson {
+        title: n.name.clone(),
+        path: n.path.to_string_lossy().to_string(),
+        note_type: n.note_type().map(String::from),
+        status: n.status().map(String::from),
+        area: n.area().map(String::from),
+        gist: n.gist().map(String::from),
+        tags: n.tags(),
+        version: None,
+    }
+}
+
+/// Find a note by title, alias (case-insensitive) or path
+fn find_note(vault_paths: &VaultPaths, name: &str) -> Option<Note> {
+    let notes = collect_all_notes(vault_paths);
+    let resolver = LinkResolver::from_notes(&notes);
+    let canonical = resolver.lookup(name).map(String::from);
+    notes.into_iter().find(|n| {
+        canonical.as_deref() == Some(n.name.as_str())
+            || (canonical.is_none() && n.path.to_string_lossy().contains(name))
+    })
+}
+
 // Audit helper methods
 impl VaultService {
-    fn check_schema(&self, notes: &[crate::core::note::Note], verbose: bool) -> AuditCheckJson {
+    fn check_schema(
+        &self,
+        notes: &[crate::core::note::Note],
+        schema: &VaultSchema,
+        verbose: bool,
+    ) -> AuditCheckJson {
         let mut errors = Vec::new();
         for note in notes {
-            let violations = note.validate_schema();
+            let violations = note.validate_schema(schema);
             for violation in violations {
                 errors.push(AuditErrorJson {
                     note: note.name.clone(),
-                    message: format!("{:?}", violation),
+                    message: violation.to_string(),
                 });
             }
         }
@@ -431,18 +1217,16 @@ impl VaultService {
     fn check_wikilinks(
         &self,
         notes: &[crate::core::note::Note],
-        note_names: &HashSet<String>,
+        resolver: &LinkResolver,
         verbose: bool,
     ) -> AuditCheckJson {
         let mut errors = Vec::new();
         for note in notes {
-            for link in note.wikilinks() {
-                if !note_names.contains(&link) {
-                    errors.push(AuditErrorJson {
-                        note: note.name.clone(),
-                        message: format!("Broken link: [[{}]]", link),
-                    });
-                }
+            for (link, err) in resolver.broken_links(&note.name, &note.content) {
+                errors.push(AuditErrorJson {
+                    note: note.name.clone(),
+                    message: format!("Broken link: {} ({})", link, err),
+                });
             }
         }
 
@@ -456,10 +1240,15 @@ impl VaultService {
         }
     }
 
-    fn check_folder_type(&self, notes: &[crate::core::note::Note], verbose: bool) -> AuditCheckJson {
+    fn check_folder_type(
+        &self,
+        notes: &[crate::core::note::Note],
+        vault_paths: &VaultPaths,
+        verbose: bool,
+    ) -> AuditCheckJson {
         let mut errors = Vec::new();
         for note in notes {
-            if !note.check_folder_type_match() {
+            if !note.check_folder_type_match(vault_paths) {
                 errors.push(AuditErrorJson {
                     note: note.name.clone(),
                     message: format!(
@@ -538,23 +1327,19 @@ impl VaultService {
     fn check_orphans(
         &self,
         notes: &[crate::core::note::Note],
-        note_names: &HashSet<String>,
+        resolver: &LinkResolver,
         verbose: bool,
     ) -> AuditCheckJson {
         let mut linked: HashSet<String> = HashSet::new();
         for note in notes {
-            for link in note.wikilinks() {
-                if note_names.contains(&link) {
-                    linked.insert(link);
-                }
-            }
+            linked.extend(resolver.linked_notes(&note.name, &note.content));
         }
 
         let mut errors = Vec::new();
-        for name in note_names {
-            if !linked.contains(name) {
+        for note in notes {
+            if !linked.contains(&note.name) {
                 errors.push(AuditErrorJson {
-                    note: name.clone(),
+                    note: note.name.clone(),
                     message: "Orphan note (no incoming links)".to_string(),
                 });
             }
@@ -573,17 +1358,262 @@ impl VaultService {
             error_list: if verbose && !errors.is_empty() { Some(errors) } else { None },
         }
     }
+
+    fn check_references(
+        &self,
+        notes: &[crate::core::note::Note],
+        references: &[References],
+        verbose: bool,
+    ) -> [AuditCheckJson; 2] {
+        let mut broken_links = Vec::new();
+        let mut missing_attachments = Vec::new();
+        for (note, refs) in notes.iter().zip(references) {
+            for issue in refs.issues.iter().cloned() {
+                let error = AuditErrorJson {
+                    note: note.name.clone(),
+                    message: issue.to_string(),
+                };
+                match issue {
+                    ReferenceIssue::BrokenMarkdownLink(_) => broken_links.push(error),
+                    ReferenceIssue::MissingAttachment(_) => missing_attachments.push(error),
+                }
+            }
+        }
+
+        [
+            AuditCheckJson {
+                id: "markdown_links".to_string(),
+                name: "Markdown Links".to_string(),
+                status: if broken_links.is_empty() {
+                    "pass"
+                } else {
+                    "fail"
+                }
+                .to_string(),
+                errors: broken_links.len(),
+                details: None,
+                error_list: if verbose && !broken_links.is_empty() {
+                    Some(broken_links)
+                } else {
+                    None
+                },
+            },
+            AuditCheckJson {
+                id: "attachments".to_string(),
+                name: "Attachments".to_string(),
+                status: if missing_attachments.is_empty() {
+                    "pass"
+                } else {
+                    "fail"
+                }
+                .to_string(),
+                errors: missing_attachments.len(),
+                details: None,
+                error_list: if verbose && !missing_attachments.is_empty() {
+                    Some(missing_attachments)
+                } else {
+                    None
+                },
+            },
+        ]
+    }
+
+    fn check_orphan_attachments(
+        &self,
+        references: &[References],
+        attachments: &AttachmentIndex,
+        verbose: bool,
+    ) -> AuditCheckJson {
+        let errors: Vec<AuditErrorJson> = attachments
+            .orphaned(references)
+            .into_iter()
+            .map(|path| AuditErrorJson {
+                note: path.to_string_lossy().to_string(),
+                message: "Orphan attachment (not referenced by any note)".to_string(),
+            })
+            .collect();
+
+        let total = attachments.attachments().count();
+        let orphans = errors.len();
+        let ratio = if total > 0 {
+            orphans as f64 / total as f64
+        } else {
+            0.0
+        };
+
+        AuditCheckJson {
+            id: "orphan_attachments".to_string(),
+            name: "Orphan Attachments".to_string(),
+            status: if ratio < 0.3 { "pass" } else { "fail" }.to_string(),
+            errors: orphans,
+            details: Some(format!("{} of {} attachments unreferenced", orphans, total)),
+            error_list: if verbose && !errors.is_empty() {
+                Some(errors)
+            } else {
+                None
+            },
+        }
+    }
 }
 
+#[tool_handler]
+#[prompt_handler]
 impl ServerHandler for VaultService {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some(
-                "Second Brain Vault MCP Server. Provides semantic search and note access for Obsidian vault.".to_string()
+                "Second Brain Vault MCP Server. Provides semantic search, note access, creation and editing, and inbox capture for Obsidian vault. Notes are also resources (vault://note/<name>) that can be subscribed to for changes, and prompts cover gist writing, link suggestions, inbox triage and weekly review.".to_string()
             ),
+            capabilities: ServerCapabilities::builder()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_tools()
+                .build(),
             ..Default::default()
         }
     }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let resources = collect_all_notes(&vault_paths)
+            .iter()
+            .map(|n| {
+                let mut resource = RawResource::new(
+                    ResourceUri::Note(n.name.clone()).to_string(),
+                    n.name.clone(),
+                );
+                resource.description = n.gist().map(String::from);
+                resource.mime_type = Some("text/markdown".to_string());
+                resource.size = u32::try_from(n.content.len()).ok();
+                resource.no_annotation()
+            })
+            .collect();
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        let template = |uri_template: &str, name: &str, description: &str| {
+            RawResourceTemplate {
+                uri_template: uri_template.to_string(),
+                name: name.to_string(),
+                title: None,
+                description: Some(description.to_string()),
+                mime_type: Some("application/json".to_string()),
+            }
+            .no_annotation()
+        };
+        Ok(ListResourceTemplatesResult::with_all_items(vec![
+            template(
+                ResourceUri::TYPE_TEMPLATE,
+                "notes-by-type",
+                "Metadata of all notes of a type (e.g. project, note)",
+            ),
+            template(
+                ResourceUri::AREA_TEMPLATE,
+                "notes-by-area",
+                "Metadata of all notes in an area",
+            ),
+        ]))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let vault_paths = self.get_vault_paths()?;
+        let not_found =
+            || McpError::resource_not_found(format!("Resource not found: {}", request.uri), None);
+        let (mime_type, text) = match ResourceUri::parse(&request.uri).ok_or_else(not_found)? {
+            ResourceUri::Note(name) => {
+                let note = resolve_note(&vault_paths, &name)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?
+                    .ok_or_else(not_found)?;
+                let content = std::fs::read_to_string(&note.path).map_err(|e| {
+                    McpError::internal_error(format!("Failed to read note: {}", e), None)
+                })?;
+                ("text/markdown", content)
+            }
+            ResourceUri::Type(wanted) => {
+                let notes = collect_all_notes(&vault_paths);
+                let listing: Vec<NoteInfoJson> = notes
+                    .iter()
+                    .filter(|n| n.note_type() == Some(wanted.as_str()))
+                    .map(note_info)
+                    .collect();
+                (
+                    "application/json",
+                    serde_json::to_string_pretty(&listing).unwrap_or_default(),
+                )
+            }
+            ResourceUri::Area(wanted) => {
+                let notes = collect_all_notes(&vault_paths);
+                let listing: Vec<NoteInfoJson> = notes
+                    .iter()
+                    .filter(|n| n.area() == Some(wanted.as_str()))
+                    .map(note_info)
+                    .collect();
+                (
+                    "application/json",
+                    serde_json::to_string_pretty(&listing).unwrap_or_default(),
+                )
+            }
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: request.uri,
+                mime_type: Some(mime_type.to_string()),
+                text,
+                meta: None,
+            }],
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        let Some(ResourceUri::Note(name)) = ResourceUri::parse(&request.uri) else {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Only note resources (vault://note/<name>) can be subscribed to: {}",
+                    request.uri
+                ),
+                None,
+            ));
+        };
+        let vault_paths = self.get_vault_paths()?;
+        let note = resolve_note(&vault_paths, &name)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?
+            .ok_or_else(|| {
+                McpError::resource_not_found(format!("Resource not found: {}", request.uri), None)
+            })?;
+
+        self.subscriptions.subscribe(request.uri, name, note.path);
+        self.subscriptions
+            .start_polling(context.peer, self.vault_path.clone());
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.subscriptions.unsubscribe(&request.uri);
+        Ok(())
+    }
 }
 
 /// Run the MCP server
@@ -0,0 +1,621 @@
+//! Approximate nearest neighbour index (HNSW)
+//!
+//! A hierarchical navigable small world graph over gist and chunk
+//! embeddings, persisted as a sidecar file next to `search.db`. Vectors are
+//! normalized and encoded with the index's quantization on insert, so a
+//! quantized graph holds only the codes and its similarities are estimates,
+//! like those of the exact scan over the same codes. Removed vectors stay in
+//! the graph as tombstones, still routable but never returned, until the
+//! index is compacted.
+
+use anyhow::{bail, Context, Result};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::quantize::{code_similarity, decode, encode, encoded_len};
+use crate::core::config::Quantization;
+
+/// Links per node on upper layers; layer 0 keeps twice as many
+const M: usize = 16;
+const EF_CONSTRUCTION: usize = 100;
+/// Minimum candidate list size when searching
+pub const EF_SEARCH: usize = 64;
+const MAX_LEVEL: usize = 16;
+const MAGIC: &[u8; 8] = b"ELYHNSW2";
+
+/// What an indexed vector embeds: a note's gist or one of its body chunks
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VectorKey {
+    pub note_id: String,
+    /// Chunk index, `None` for the gist
+    pub chunk: Option<usize>,
+}
+
+/// A normalized vector as the graph keeps it
+#[derive(Clone)]
+enum Stored {
+    Full(Vec<f32>),
+    /// Encoded with the index's quantization
+    Code(Vec<u8>),
+}
+
+struct Node {
+    key: VectorKey,
+    vector: Stored,
+    /// Neighbour ids per layer, layer 0 first
+    links: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+/// Candidate ordered by similarity, ties broken by node id
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scored {
+    similarity: f32,
+    id: u32,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.similarity
+            .total_cmp(&other.similarity)
+            .then(other.id.cmp(&self.id))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// HNSW graph over unit-length vectors
+pub struct HnswIndex {
+    dimension: usize,
+    quantization: Quantization,
+    nodes: Vec<Node>,
+    entry: Option<u32>,
+    by_note: HashMap<String, Vec<u32>>,
+    deleted: usize,
+    rng: u64,
+}
+
+impl HnswIndex {
+    pub fn new(dimension: usize, quantization: Quantization) -> Self {
+        Self {
+            dimension,
+            quantization,
+            nodes: Vec::new(),
+            entry: None,
+            by_note: HashMap::new(),
+            deleted: 0,
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    pub fn quantization(&self) -> Quantization {
+        self.quantization
+    }
+
+    /// Number of live (not removed) vectors
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.deleted
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Share of graph nodes that are tombstones
+    pub fn tombstone_ratio(&self) -> f32 {
+        if self.nodes.is_empty() {
+            0.0
+        } else {
+            self.deleted as f32 / self.nodes.len() as f32
+        }
+    }
+
+    /// Add a vector under `key`
+    pub fn insert(&mut self, key: VectorKey, vector: &[f32]) -> Result<()> {
+        if vector.len() != self.dimension {
+            bail!(
+                "Vector has {} dimensions, index expects {}",
+                vector.len(),
+                self.dimension
+            );
+        }
+        let vector = self.store(vector);
+        self.insert_stored(key, vector);
+        Ok(())
+    }
+
+    /// Add a vector already encoded with the index's quantization, such as
+    /// a quantized embedding read from the database
+    pub fn insert_code(&mut self, key: VectorKey, code: Vec<u8>) -> Result<()> {
+        if code.len() != encoded_len(self.quantization, self.dimension) {
+            bail!(
+                "Encoded vector has {} bytes, index expects {} dimensions as {}",
+                code.len(),
+                self.dimension,
+                self.quantization.as_str()
+            );
+        }
+        let vector = match self.quantization {
+            Quantization::None => Stored::Full(normalize(&decode(Quantization::None, &code))),
+            _ => Stored::Code(code),
+        };
+        self.insert_stored(key, vector);
+        Ok(())
+    }
+
+    fn insert_stored(&mut self, key: VectorKey, vector: Stored) {
+        let id = self.nodes.len() as u32;
+        let level = self.random_level();
+        self.by_note
+            .entry(key.note_id.clone())
+            .or_default()
+            .push(id);
+        self.nodes.push(Node {
+            key,
+            vector: vector.clone(),
+            links: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        let query = vector;
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(id);
+            return;
+        };
+
+        let top = self.nodes[entry as usize].links.len() - 1;
+        let mut nearest = self.score(&query, entry);
+        for layer in (level + 1..=top).rev() {
+            nearest = self.greedy(&query, nearest, layer);
+        }
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&query, nearest, EF_CONSTRUCTION, layer);
+            let neighbours = self.select(&candidates, M);
+            for &neighbour in &neighbours {
+                self.link(neighbour, id, layer);
+            }
+            self.nodes[id as usize].links[layer] = neighbours;
+            nearest = candidates[0];
+        }
+        if level > top {
+            self.entry = Some(id);
+        }
+    }
+
+    /// Remove every vector of a note
+    pub fn remove_note(&mut self, note_id: &str) {
+        for id in self.by_note.remove(note_id).unwrap_or_default() {
+            let node = &mut self.nodes[id as usize];
+            if !node.deleted {
+                node.deleted = true;
+                self.deleted += 1;
+            }
+        }
+    }
+
+    /// The `k` vectors most similar to `query`, best first
+    ///
+    /// `ef` is the candidate list size; larger values trade speed for recall.
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Result<Vec<(VectorKey, f32)>> {
+        if query.len() != self.dimension {
+            bail!(
+                "Query has {} dimensions, index expects {}",
+                query.len(),
+                self.dimension
+            );
+        }
+        let Some(entry) = self.entry else {
+            return Ok(Vec::new());
+        };
+
+        let query = self.store(query);
+        let top = self.nodes[entry as usize].links.len() - 1;
+        let mut nearest = self.score(&query, entry);
+        for layer in (1..=top).rev() {
+            nearest = self.greedy(&query, nearest, layer);
+        }
+
+        Ok(self
+            .search_layer(&query, nearest, ef.max(k), 0)
+            .into_iter()
+            .filter(|c| !self.nodes[c.id as usize].deleted)
+            .take(k)
+            .map(|c| (self.nodes[c.id as usize].key.clone(), c.similarity))
+            .collect())
+    }
+
+    /// A fresh index over the live vectors, without tombstones
+    pub fn compacted(&self) -> Result<Self> {
+        let mut index = Self::new(self.dimension, self.quantization);
+        for node in self.nodes.iter().filter(|n| !n.deleted) {
+            index.insert_stored(node.key.clone(), node.vector.clone());
+        }
+        Ok(index)
+    }
+
+    /// Write the index to `path` together with `stamp`, which ties the file
+    /// to the database state it was saved with
+    pub fn save(&self, path: &Path, stamp: u64) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&stamp.to_le_bytes())?;
+        write_u32(&mut out, self.dimension as u32)?;
+        let quantization = self.quantization.as_str();
+        write_u32(&mut out, quantization.len() as u32)?;
+        out.write_all(quantization.as_bytes())?;
+        write_u32(&mut out, self.nodes.len() as u32)?;
+        write_u32(&mut out, self.entry.unwrap_or(u32::MAX))?;
+        for node in &self.nodes {
+            write_u32(&mut out, node.key.note_id.len() as u32)?;
+            out.write_all(node.key.note_id.as_bytes())?;
+            write_u32(&mut out, node.key.chunk.map_or(u32::MAX, |c| c as u32))?;
+            out.write_all(&[node.deleted as u8])?;
+            match &node.vector {
+                Stored::Full(vector) => {
+                    for value in vector {
+                        out.write_all(&value.to_le_bytes())?;
+                    }
+                }
+                Stored::Code(code) => out.write_all(code)?,
+            }
+            write_u32(&mut out, node.links.len() as u32)?;
+            for links in &node.links {
+                write_u32(&mut out, links.len() as u32)?;
+                for &link in links {
+                    write_u32(&mut out, link)?;
+                }
+            }
+        }
+        out.into_inner()?.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Read an index and its stamp written by [`HnswIndex::save`]
+    pub fn load(path: &Path) -> Result<(Self, u64)> {
+        let mut input = BufReader::new(
+            File::open(path).with_context(|| format!("Cannot open {}", path.display()))?,
+        );
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("{} is not an ANN index", path.display());
+        }
+        let mut stamp = [0u8; 8];
+        input.read_exact(&mut stamp)?;
+        let dimension = read_u32(&mut input)? as usize;
+        let mut quantization = vec![0u8; read_u32(&mut input)? as usize];
+        input.read_exact(&mut quantization)?;
+        let Some(quantization) = Quantization::from_name(&String::from_utf8_lossy(&quantization))
+        else {
+            bail!("Corrupt ANN index {}", path.display());
+        };
+        let count = read_u32(&mut input)? as usize;
+        let entry = read_u32(&mut input)?;
+
+        let mut index = Self::new(dimension, quantization);
+        for id in 0..count as u32 {
+            let mut note_id = vec![0u8; read_u32(&mut input)? as usize];
+            input.read_exact(&mut note_id)?;
+            let chunk = read_u32(&mut input)?;
+            let mut deleted = [0u8; 1];
+            input.read_exact(&mut deleted)?;
+            let mut code = vec![0u8; encoded_len(quantization, dimension)];
+            input.read_exact(&mut code)?;
+            let vector = match quantization {
+                Quantization::None => Stored::Full(decode(Quantization::None, &code)),
+                _ => Stored::Code(code),
+            };
+            let mut links = Vec::new();
+            for _ in 0..read_u32(&mut input)? {
+                let mut layer = Vec::new();
+                for _ in 0..read_u32(&mut input)? {
+                    let link = read_u32(&mut input)?;
+                    if link as usize >= count {
+                        bail!("Corrupt ANN index {}", path.display());
+                    }
+                    layer.push(link);
+                }
+                links.push(layer);
+            }
+            if links.is_empty() {
+                bail!("Corrupt ANN index {}", path.display());
+            }
+
+            let key = VectorKey {
+                note_id: String::from_utf8(note_id)?,
+                chunk: (chunk != u32::MAX).then_some(chunk as usize),
+            };
+            if deleted[0] != 0 {
+                index.deleted += 1;
+            } else {
+                index
+                    .by_note
+                    .entry(key.note_id.clone())
+                    .or_default()
+                    .push(id);
+            }
+            index.nodes.push(Node {
+                key,
+                vector,
+                links,
+                deleted: deleted[0] != 0,
+            });
+        }
+        if entry != u32::MAX {
+            if entry as usize >= count {
+                bail!("Corrupt ANN index {}", path.display());
+            }
+            index.entry = Some(entry);
+        }
+        index.rng ^= count as u64;
+
+        Ok((index, u64::from_le_bytes(stamp)))
+    }
+
+    /// `vector` normalized and encoded like the stored vectors
+    fn store(&self, vector: &[f32]) -> Stored {
+        let vector = normalize(vector);
+        match self.quantization {
+            Quantization::None => Stored::Full(vector),
+            quantization => Stored::Code(encode(quantization, &vector)),
+        }
+    }
+
+    /// Cosine similarity of two stored vectors, estimated when quantized
+    fn similarity(&self, a: &Stored, b: &Stored) -> f32 {
+        match (a, b) {
+            (Stored::Full(a), Stored::Full(b)) => dot(a, b),
+            (Stored::Code(a), Stored::Code(b)) => code_similarity(self.quantization, a, b),
+            _ => 0.0,
+        }
+    }
+
+    fn score(&self, query: &Stored, id: u32) -> Scored {
+        Scored {
+            similarity: self.similarity(query, &self.nodes[id as usize].vector),
+            id,
+        }
+    }
+
+    /// Walk `layer` towards `query` until no neighbour is closer
+    fn greedy(&self, query: &Stored, mut nearest: Scored, layer: usize) -> Scored {
+        loop {
+            let best = self.nodes[nearest.id as usize].links[layer]
+                .iter()
+                .map(|&n| self.score(query, n))
+                .max()
+                .filter(|best| *best > nearest);
+            match best {
+                Some(best) => nearest = best,
+                None => return nearest,
+            }
+        }
+    }
+
+    /// Beam search of `layer` from `start`, returning up to `ef` candidates
+    /// best first
+    fn search_layer(&self, query: &Stored, start: Scored, ef: usize, layer: usize) -> Vec<Scored> {
+        let mut visited = HashSet::from([start.id]);
+        let mut candidates = BinaryHeap::from([start]);
+        let mut found = BinaryHeap::from([Reverse(start)]);
+
+        while let Some(candidate) = candidates.pop() {
+            let worst = found.peek().map(|r| r.0);
+            if found.len() >= ef && worst.is_some_and(|w| candidate < w) {
+                break;
+            }
+            for &n in &self.nodes[candidate.id as usize].links[layer] {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let scored = self.score(query, n);
+                if found.len() < ef || found.peek().is_some_and(|w| scored > w.0) {
+                    candidates.push(scored);
+                    found.push(Reverse(scored));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        let mut found: Vec<Scored> = found.into_iter().map(|r| r.0).collect();
+        found.sort_by(|a, b| b.cmp(a));
+        found
+    }
+
+    /// Pick up to `m` neighbours from candidates sorted best first, preferring
+    /// ones that are not already covered by a closer pick
+    fn select(&self, candidates: &[Scored], m: usize) -> Vec<u32> {
+        let mut selected: Vec<Scored> = Vec::with_capacity(m);
+        for &candidate in candidates {
+            if selected.len() == m {
+                break;
+            }
+            let vector = &self.nodes[candidate.id as usize].vector;
+            if selected.iter().all(|s| {
+                self.similarity(vector, &self.nodes[s.id as usize].vector) < candidate.similarity
+            }) {
+                selected.push(candidate);
+            }
+        }
+        for &candidate in candidates {
+            if selected.len() == m {
+                break;
+            }
+            if !selected.contains(&candidate) {
+                selected.push(candidate);
+            }
+        }
+        selected.into_iter().map(|s| s.id).collect()
+    }
+
+    /// Add a link from `from` to `to`, pruning `from`'s links if over budget
+    fn link(&mut self, from: u32, to: u32, layer: usize) {
+        let max_links = if layer == 0 { 2 * M } else { M };
+        self.nodes[from as usize].links[layer].push(to);
+        if self.nodes[from as usize].links[layer].len() <= max_links {
+            return;
+        }
+
+        let node = &self.nodes[from as usize];
+        let mut candidates: Vec<Scored> = node.links[layer]
+            .iter()
+            .map(|&n| self.score(&node.vector, n))
+            .collect();
+        candidates.sort_by(|a, b| b.cmp(a));
+        self.nodes[from as usize].links[layer] = self.select(&candidates, max_links);
+    }
+
+    /// Exponentially distributed layer, so each layer holds ~1/M of the one below
+    fn random_level(&mut self) -> usize {
+        // xorshift64*
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        let uniform = (bits as f64 + 1.0) / (1u64 << 53) as f64;
+        ((-uniform.ln() / (M as f64).ln()) as usize).min(MAX_LEVEL)
+    }
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter().map(|x| x / norm).collect()
+    } else {
+        vector.to_vec()
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn write_u32(out: &mut impl Write, value: u32) -> Result<()> {
+    out.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn read_u32(input: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::embedding::cosine_similarity;
+
+    fn random_vectors(count: usize, dimension: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+        };
+        (0..count)
+            .map(|_| (0..dimension).map(|_| next()).collect())
+            .collect()
+    }
+
+    fn key(i: usize) -> VectorKey {
+        VectorKey {
+            note_id: format!("note-{}", i),
+            chunk: None,
+        }
+    }
+
+    fn recall_against_exact(quantization: Quantization) -> Result<f32> {
+        let vectors = random_vectors(3000, 32, 7);
+        let mut index = HnswIndex::new(32, quantization);
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(key(i), vector)?;
+        }
+
+        let k = 10;
+        let queries = random_vectors(50, 32, 99);
+        let mut hits = 0;
+        for query in &queries {
+            let mut exact: Vec<(usize, f32)> = vectors
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, cosine_similarity(query, v)))
+                .collect();
+            exact.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let expected: HashSet<VectorKey> = exact[..k].iter().map(|(i, _)| key(*i)).collect();
+
+            let found = index.search(query, k, EF_SEARCH)?;
+            hits += found
+                .iter()
+                .filter(|(key, _)| expected.contains(key))
+                .count();
+        }
+
+        Ok(hits as f32 / (k * queries.len()) as f32)
+    }
+
+    #[test]
+    fn test_recall_against_exact() -> Result<()> {
+        let recall = recall_against_exact(Quantization::None)?;
+        assert!(recall >= 0.9, "recall@10 was {}", recall);
+        // A graph over int8 codes loses little to the quantization
+        let recall = recall_against_exact(Quantization::Int8)?;
+        assert!(recall >= 0.85, "int8 recall@10 was {}", recall);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_and_persist() -> Result<()> {
+        let vectors = random_vectors(200, 8, 3);
+        let mut index = HnswIndex::new(8, Quantization::Int8);
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(key(i), vector)?;
+        }
+        index.insert(
+            VectorKey {
+                note_id: "note-0".to_string(),
+                chunk: Some(0),
+            },
+            &vectors[1],
+        )?;
+
+        index.remove_note("note-0");
+        assert_eq!(index.len(), 199);
+        let found = index.search(&vectors[0], 200, 200)?;
+        assert!(found.iter().all(|(key, _)| key.note_id != "note-0"));
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("search.hnsw");
+        index.save(&path, 42)?;
+        let (loaded, stamp) = HnswIndex::load(&path)?;
+        assert_eq!(stamp, 42);
+        assert_eq!(loaded.quantization(), Quantization::Int8);
+        assert_eq!(loaded.len(), index.len());
+        assert_eq!(
+            loaded.search(&vectors[5], 5, EF_SEARCH)?,
+            index.search(&vectors[5], 5, EF_SEARCH)?
+        );
+
+        let compacted = loaded.compacted()?;
+        assert_eq!((compacted.len(), compacted.tombstone_ratio()), (199, 0.0));
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+//! Split note bodies into passages for embedding
+//!
+//! Every heading starts a new section. Within a section, paragraphs are packed
+//! into chunks of at most `MAX_CHUNK_BYTES`; a longer paragraph is split at
+//! whitespace. Blank lines inside fenced code blocks do not end a paragraph.
+
+use std::ops::Range;
+
+use crate::core::frontmatter::body_offset;
+use crate::core::markdown::{code_ranges, headings, in_ranges};
+
+/// Upper bound on the length of a chunk, in bytes
+pub const MAX_CHUNK_BYTES: usize = 1000;
+
+/// A passage of a note body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// Headings enclosing the passage, outermost first
+    pub heading_path: Vec<String>,
+    /// Byte offsets of the passage in the note content
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Chunk {
+    /// Text handed to the embedder; the title and headings give the passage context
+    pub fn embedding_text(&self, title: &str) -> String {
+        let mut context = vec![title];
+        context.extend(self.heading_path.iter().map(String::as_str));
+        format!("{}\n\n{}", context.join(" > "), self.text)
+    }
+}
+
+/// Chunk the body of a note, skipping its frontmatter
+pub fn chunk_note(content: &str) -> Vec<Chunk> {
+    chunk_with_limit(content, MAX_CHUNK_BYTES)
+}
+
+fn chunk_with_limit(content: &str, max: usize) -> Vec<Chunk> {
+    let body_start = body_offset(content);
+    let code = code_ranges(content);
+    let mut chunks = Vec::new();
+    let mut path: Vec<(usize, String)> = Vec::new();
+    let mut section_start = body_start;
+
+    for heading in headings(content) {
+        if heading.offset < body_start {
+            continue;
+        }
+        pack(
+            content,
+            section_start..heading.offset,
+            &path,
+            &code,
+            max,
+            &mut chunks,
+        );
+
+        while path
+            .last()
+            .is_some_and(|(level, _)| *level >= heading.level)
+        {
+            path.pop();
+        }
+        path.push((heading.level, heading.text));
+        section_start = content[heading.offset..]
+            .find('\n')
+            .map_or(content.len(), |i| heading.offset + i + 1);
+    }
+    pack(
+        content,
+        section_start..content.len(),
+        &path,
+        &code,
+        max,
+        &mut chunks,
+    );
+
+    chunks
+}
+
+/// Pack the paragraphs of one section into chunks
+fn pack(
+    content: &str,
+    section: Range<usize>,
+    path: &[(usize, String)],
+    code: &[Range<usize>],
+    max: usize,
+    chunks: &mut Vec<Chunk>,
+) {
+    let heading_path: Vec<String> = path.iter().map(|(_, text)| text.clone()).collect();
+    let mut push = |range: Range<usize>| {
+        chunks.push(Chunk {
+            heading_path: heading_path.clone(),
+            start: range.start,
+            end: range.end,
+            text: content[range].to_string(),
+        })
+    };
+
+    let mut current: Option<Range<usize>> = None;
+    for paragraph in paragraphs(content, section, code) {
+        for piece in split_long(content, paragraph, max) {
+            match &mut current {
+                Some(chunk) if piece.end - chunk.start <= max => chunk.end = piece.end,
+                _ => {
+                    if let Some(chunk) = current.replace(piece) {
+                        push(chunk);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(chunk) = current {
+        push(chunk);
+    }
+}
+
+/// Runs of non-blank lines in `section`, without trailing whitespace
+fn paragraphs(content: &str, section: Range<usize>, code: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut result = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    let mut offset = section.start;
+
+    for line in content[section].split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        if line.trim().is_empty() && !in_ranges(code, line_start) {
+            result.extend(current.take());
+            continue;
+        }
+        let end = line_start + line.trim_end().len();
+        match &mut current {
+            Some(paragraph) => paragraph.end = end,
+            None => current = Some(line_start..end),
+        }
+    }
+    result.extend(current);
+
+    result
+}
+
+/// Split `range` into pieces of at most `max` bytes, breaking at whitespace
+/// where possible
+fn split_long(content: &str, range: Range<usize>, max: usize) -> Vec<Range<usize>> {
+    let mut pieces = Vec::new();
+    let mut start = range.start;
+
+    while range.end - start > max {
+        let mut limit = start + max;
+        while !content.is_char_boundary(limit) {
+            limit -= 1;
+        }
+        if limit == start {
+            // A single character wider than `max`
+            limit = start + content[start..].chars().next().map_or(1, char::len_utf8);
+        }
+
+        let split = content[start..limit]
+            .rfind(char::is_whitespace)
+            .filter(|&i| i > 0)
+            .map_or(limit, |i| start + i);
+        pieces.push(start..start + content[start..split].trim_end().len());
+
+        let rest = &content[split..range.end];
+        start = split + rest.len() - rest.trim_start().len();
+    }
+    if start < range.end {
+        pieces.push(start..range.end);
+    }
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_and_paragraphs() {
+        let content = "---\ngist: g\n---\nIntro line\n\n# Setup\n## Install\nRun it.\n\n```sh\na\n\nb\n```\n# Usage\nUse it.\n";
+        let chunks = chunk_with_limit(content, 20);
+
+        let summary: Vec<(Vec<&str>, &str)> = chunks
+            .iter()
+            .map(|c| {
+                (
+                    c.heading_path.iter().map(String::as_str).collect(),
+                    c.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (vec![], "Intro line"),
+                (vec!["Setup", "Install"], "Run it."),
+                (vec!["Setup", "Install"], "```sh\na\n\nb\n```"),
+                (vec!["Usage"], "Use it."),
+            ]
+        );
+        assert_eq!(&content[chunks[1].start..chunks[1].end], "Run it.");
+    }
+
+    #[test]
+    fn test_long_paragraphs_split_at_whitespace() {
+        let content = "alpha beta gamma delta\n\nepsilon";
+        let texts: Vec<String> = chunk_with_limit(content, 12)
+            .into_iter()
+            .map(|c| c.text)
+            .collect();
+        assert_eq!(texts, vec!["alpha beta", "gamma delta", "epsilon"]);
+
+        // Short paragraphs are packed together
+        let packed = chunk_with_limit("one\n\ntwo\n\nthree", 100);
+        assert_eq!(packed.len(), 1);
+        assert_eq!(packed[0].text, "one\n\ntwo\n\nthree");
+    }
+}
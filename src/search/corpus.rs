@@ -0,0 +1,61 @@
+//! Corpus statistics for IDF-weighted pooling
+//!
+//! Document frequencies are collected over the vault's notes while indexing
+//! and stored in the index, so queries are embedded with the same weights as
+//! the notes they are compared against.
+
+use std::collections::{HashMap, HashSet};
+
+/// Number of documents and, per token, how many documents contain it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CorpusStats {
+    pub documents: usize,
+    pub document_frequency: HashMap<String, u32>,
+}
+
+impl CorpusStats {
+    /// Statistics over documents given as their tokens
+    pub fn collect<I>(documents: I) -> Self
+    where
+        I: IntoIterator<Item = Vec<String>>,
+    {
+        let mut stats = Self::default();
+        for tokens in documents {
+            stats.documents += 1;
+            for token in tokens.into_iter().collect::<HashSet<_>>() {
+                *stats.document_frequency.entry(token).or_default() += 1;
+            }
+        }
+        stats
+    }
+
+    /// Smoothed inverse document frequency; tokens the corpus has never seen
+    /// weigh the most
+    pub fn idf(&self, token: &str) -> f32 {
+        let df = self.document_frequency.get(token).copied().unwrap_or(0);
+        ((1.0 + self.documents as f32) / (1.0 + df as f32)).ln() + 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_idf() {
+        let stats = CorpusStats::collect([
+            tokens("notes on gpu memory"),
+            tokens("notes on tea notes"),
+            tokens("notes on rust"),
+        ]);
+        assert_eq!(stats.documents, 3);
+        assert_eq!(stats.document_frequency["notes"], 3);
+        assert_eq!(stats.idf("notes"), 1.0);
+        assert!(stats.idf("gpu") > stats.idf("notes"));
+        assert!(stats.idf("unseen") > stats.idf("gpu"));
+    }
+}
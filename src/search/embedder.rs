@@ -0,0 +1,249 @@
+//! Embedder trait and backend selection
+//!
+//! Every backend turns text into a fixed-size vector. The index records the
+//! backend's `name` and `dimension` so vectors from different models are
+//! never compared against each other.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use super::corpus::CorpusStats;
+use super::embedding::EmbeddingModel;
+use super::hashed::HashedEmbedder;
+use crate::core::config::EmbedderConfig;
+
+/// Text embedding backend
+pub trait Embedder: Send + Sync {
+    /// Stable model identifier recorded in the index
+    fn name(&self) -> &str;
+
+    /// Length of every vector this embedder returns
+    fn dimension(&self) -> usize;
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>>;
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        texts.iter().map(|t| self.embed(t)).collect()
+    }
+
+    /// Whether embeddings depend on statistics of the indexed corpus
+    fn uses_corpus_stats(&self) -> bool {
+        false
+    }
+
+    /// Document frequencies over `documents`, as this embedder tokenizes them
+    fn corpus_stats(&self, _documents: &[&str]) -> CorpusStats {
+        CorpusStats::default()
+    }
+
+    /// Weight tokens by `stats` from now on
+    fn set_corpus_stats(&mut self, _stats: CorpusStats) {}
+}
+
+/// The embedder a vault is configured for and whether it can be loaded
+#[derive(Debug, Clone)]
+pub struct EmbedderStatus {
+    pub id: String,
+    pub dimension: usize,
+    /// Model files the embedder needs that do not exist
+    pub missing: Vec<PathBuf>,
+    /// Why the embedder cannot be used by this build, if it cannot
+    pub unavailable: Option<String>,
+}
+
+impl EmbedderStatus {
+    /// Whether the embedder can be loaded without further setup
+    pub fn is_ready(&self) -> bool {
+        self.missing.is_empty() && self.unavailable.is_none()
+    }
+}
+
+/// Report which embedder `config` selects and whether its assets are present,
+/// without loading it; relative paths resolve against `root`
+pub fn embedder_status(config: &EmbedderConfig, root: &Path) -> EmbedderStatus {
+    let (id, dimension) = embedder_id(config);
+    let mut missing = Vec::new();
+    let mut unavailable = None;
+
+    if let EmbedderConfig::Onnx {
+        model, tokenizer, ..
+    } = config
+    {
+        missing = [model, tokenizer]
+            .into_iter()
+            .map(|file| root.join(file))
+            .filter(|path| !path.exists())
+            .collect();
+        if !cfg!(feature = "onnx") {
+            unavailable = Some(ONNX_FEATURE_HINT.to_string());
+        }
+    }
+    if matches!(config, EmbedderConfig::OpenAi { .. }) && !cfg!(feature = "openai") {
+        unavailable = Some(OPENAI_FEATURE_HINT.to_string());
+    }
+
+    EmbedderStatus {
+        id,
+        dimension,
+        missing,
+        unavailable,
+    }
+}
+
+/// Create the embedder selected by `config`; relative paths resolve against `root`
+pub fn create_embedder(config: &EmbedderConfig, root: &Path) -> Result<Box<dyn Embedder>> {
+    Ok(match config {
+        EmbedderConfig::Htp { tokenizer, pooling } => {
+            Box::new(EmbeddingModel::with_tokenizer(tokenizer.clone()).with_pooling(*pooling))
+        }
+        EmbedderConfig::Hashed { dimension } => Box::new(HashedEmbedder::new(*dimension)),
+        EmbedderConfig::Onnx {
+            model,
+            tokenizer,
+            dimension,
+            max_length,
+        } => create_onnx(
+            onnx_model_id(model),
+            &root.join(model),
+            &root.join(tokenizer),
+            *dimension,
+            *max_length,
+        )?,
+        EmbedderConfig::OpenAi {
+            url,
+            model,
+            dimension,
+            api_key_env,
+        } => {
+            let api_key = api_key_env.as_ref().and_then(|var| std::env::var(var).ok());
+            create_openai(url, model, *dimension, api_key)?
+        }
+    })
+}
+
+/// Model id and dimension `config` produces, without loading the model
+pub fn embedder_id(config: &EmbedderConfig) -> (String, usize) {
+    match config {
+        EmbedderConfig::Htp { tokenizer, pooling } => (
+            super::embedding::htp_model_id(tokenizer, *pooling),
+            super::embedding::EMBEDDING_DIM,
+        ),
+        EmbedderConfig::Hashed { dimension } => {
+            (super::hashed::HASHED_MODEL_ID.to_string(), *dimension)
+        }
+        EmbedderConfig::Onnx {
+            model, dimension, ..
+        } => (onnx_model_id(model), *dimension),
+        EmbedderConfig::OpenAi {
+            model, dimension, ..
+        } => (openai_model_id(model), *dimension),
+    }
+}
+
+const ONNX_FEATURE_HINT: &str =
+    "The onnx embedder requires elysium to be built with `--features onnx`";
+
+fn onnx_model_id(model: &str) -> String {
+    let file = Path::new(model)
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_else(|| model.to_string());
+    format!("onnx:{}", file)
+}
+
+#[cfg(feature = "onnx")]
+fn create_onnx(
+    id: String,
+    model: &Path,
+    tokenizer: &Path,
+    dimension: usize,
+    max_length: usize,
+) -> Result<Box<dyn Embedder>> {
+    Ok(Box::new(super::onnx::OnnxEmbedder::load(
+        id, model, tokenizer, dimension, max_length,
+    )?))
+}
+
+#[cfg(not(feature = "onnx"))]
+fn create_onnx(
+    _id: String,
+    _model: &Path,
+    _tokenizer: &Path,
+    _dimension: usize,
+    _max_length: usize,
+) -> Result<Box<dyn Embedder>> {
+    anyhow::bail!(ONNX_FEATURE_HINT)
+}
+
+const OPENAI_FEATURE_HINT: &str =
+    "The openai embedder requires elysium to be built with `--features openai`";
+
+pub(crate) fn openai_model_id(model: &str) -> String {
+    format!("openai:{}", model)
+}
+
+#[cfg(feature = "openai")]
+fn create_openai(
+    url: &str,
+    model: &str,
+    dimension: usize,
+    api_key: Option<String>,
+) -> Result<Box<dyn Embedder>> {
+    Ok(Box::new(super::http::HttpEmbedder::new(
+        url, model, dimension, api_key,
+    )))
+}
+
+#[cfg(not(feature = "openai"))]
+fn create_openai(
+    _url: &str,
+    _model: &str,
+    _dimension: usize,
+    _api_key: Option<String>,
+) -> Result<Box<dyn Embedder>> {
+    anyhow::bail!(OPENAI_FEATURE_HINT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedder_status() {
+        let root = Path::new("/tmp/nonexistent-vault");
+        let htp = embedder_status(&EmbedderConfig::default(), root);
+        assert_eq!(htp.id, "htp-v2+tok1-cjk2-bigram-stop-stem+idf");
+        assert!(htp.is_ready());
+
+        let onnx = embedder_status(
+            &EmbedderConfig::Onnx {
+                model: "models/model.onnx".to_string(),
+                tokenizer: "models/tokenizer.json".to_string(),
+                dimension: 384,
+                max_length: 256,
+            },
+            root,
+        );
+        assert_eq!(onnx.id, "onnx:model.onnx");
+        assert_eq!(
+            onnx.missing,
+            vec![
+                root.join("models/model.onnx"),
+                root.join("models/tokenizer.json")
+            ]
+        );
+        assert!(!onnx.is_ready());
+
+        let openai = embedder_status(
+            &EmbedderConfig::OpenAi {
+                url: "http://localhost:8080/v1".to_string(),
+                model: "nomic-embed-text".to_string(),
+                dimension: 768,
+                api_key_env: None,
+            },
+            root,
+        );
+        assert_eq!(openai.id, "openai:nomic-embed-text");
+        assert_eq!(openai.is_ready(), cfg!(feature = "openai"));
+    }
+}
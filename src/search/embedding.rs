@@ -14,7 +14,16 @@
 
 use anyhow::Result;
 use std::f64::consts::PI;
-use std::path::Path;
+
+use super::corpus::CorpusStats;
+use super::embedder::Embedder;
+use super::hashed::fnv1a;
+use super::tokenizer::Tokenizer;
+use crate::core::config::{Pooling, TokenizerConfig};
+
+/// Model id recorded in the index for HTP embeddings, before the tokenizer
+/// id and pooling
+pub const HTP_MODEL_ID: &str = "htp-v2";
 
 /// Embedding dimension (2 * number of coprime moduli)
 /// Using 192 moduli → 384 dimensions (matching common transformer dims)
@@ -23,8 +32,9 @@ pub const EMBEDDING_DIM: usize = 384;
 /// Number of coprime moduli for harmonic projection
 const NUM_MODULI: usize = EMBEDDING_DIM / 2;
 
-/// Maximum token length (Unicode code points)
-const MAX_TOKEN_LENGTH: usize = 64;
+/// Tokens up to this many code points are encoded exactly in base 2^16;
+/// longer ones are hashed so no characters are dropped
+const MAX_EXACT_TOKEN_LENGTH: usize = 4;
 
 /// Coprime moduli for modular decomposition
 /// Using first NUM_MODULI primes for guaranteed coprimality
@@ -49,66 +59,44 @@ static COPRIME_MODULI: &[u64] = &[
 /// Implements Harmonic Token Projection for deterministic text embeddings
 pub struct EmbeddingModel {
     moduli: Vec<u64>,
+    tokenizer: Tokenizer,
+    pooling: Pooling,
+    /// Document frequencies for IDF pooling; without them every token
+    /// weighs the same
+    corpus: Option<CorpusStats>,
+    id: String,
 }
 
 impl EmbeddingModel {
-    /// Create new HTP embedding model
-    ///
-    /// Note: model_path is ignored (no model file needed)
-    /// Kept for API compatibility
-    pub fn load(_model_path: &Path) -> Result<Self> {
-        Ok(Self::new())
+    /// Create new HTP embedding model with the default tokenizer and pooling
+    pub fn new() -> Self {
+        Self::with_tokenizer(TokenizerConfig::default())
     }
 
-    /// Create new HTP embedding model
-    pub fn new() -> Self {
+    pub fn with_tokenizer(config: TokenizerConfig) -> Self {
+        let tokenizer = Tokenizer::new(config);
+        let pooling = Pooling::default();
         Self {
             moduli: COPRIME_MODULI[..NUM_MODULI].to_vec(),
+            id: model_id(&tokenizer, pooling),
+            tokenizer,
+            pooling,
+            corpus: None,
         }
     }
 
-    /// Generate embedding for a single text
-    ///
-    /// Algorithm:
-    /// 1. Tokenize text into words
-    /// 2. Embed each token using harmonic projection
-    /// 3. Average token embeddings (mean pooling)
-    /// 4. L2 normalize result
-    pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        let tokens = tokenize(text);
-
-        if tokens.is_empty() {
-            return Ok(vec![0.0; EMBEDDING_DIM]);
-        }
-
-        // Embed each token
-        let mut sum_embedding = vec![0.0f64; EMBEDDING_DIM];
-        let mut count = 0;
+    pub fn with_pooling(mut self, pooling: Pooling) -> Self {
+        self.pooling = pooling;
+        self.id = model_id(&self.tokenizer, pooling);
+        self
+    }
 
-        for token in &tokens {
-            let token_emb = self.embed_token(token);
-            for (i, val) in token_emb.iter().enumerate() {
-                sum_embedding[i] += val;
-            }
-            count += 1;
+    /// Pooling weight of `token`
+    fn token_weight(&self, token: &str) -> f64 {
+        match (self.pooling, &self.corpus) {
+            (Pooling::Idf, Some(corpus)) => corpus.idf(token) as f64,
+            _ => 1.0,
         }
-
-        // Mean pooling
-        if count > 0 {
-            for val in &mut sum_embedding {
-                *val /= count as f64;
-            }
-        }
-
-        // L2 normalize and convert to f32
-        let norm: f64 = sum_embedding.iter().map(|x| x * x).sum::<f64>().sqrt();
-        let embedding: Vec<f32> = if norm > 0.0 {
-            sum_embedding.iter().map(|x| (*x / norm) as f32).collect()
-        } else {
-            sum_embedding.iter().map(|x| *x as f32).collect()
-        };
-
-        Ok(embedding)
     }
 
     /// Embed a single token using Harmonic Token Projection
@@ -137,23 +125,96 @@ impl EmbeddingModel {
 
     /// Convert token to integer using Unicode encoding
     ///
-    /// N = Σ u_j * B^(L-j) where B = 2^16
+    /// N = Σ u_j * B^(L-j) where B = 2^16, for tokens short enough to fit
+    /// in 64 bits; longer tokens use a 64-bit hash
     fn token_to_integer(&self, token: &str) -> u64 {
-        let chars: Vec<char> = token.chars().take(MAX_TOKEN_LENGTH).collect();
+        if token.chars().count() > MAX_EXACT_TOKEN_LENGTH {
+            return fnv1a(token.as_bytes());
+        }
 
-        // Use wrapping arithmetic to handle overflow gracefully
         let mut n: u64 = 0;
-        for c in chars {
+        for c in token.chars() {
             // Shift and add (base 2^16)
             n = n.wrapping_mul(65536).wrapping_add(c as u64);
         }
 
         n
     }
+}
+
+/// Model id for HTP with the given tokenizer settings and pooling
+pub fn htp_model_id(config: &TokenizerConfig, pooling: Pooling) -> String {
+    model_id(&Tokenizer::new(config.clone()), pooling)
+}
+
+fn model_id(tokenizer: &Tokenizer, pooling: Pooling) -> String {
+    format!("{}+{}+{}", HTP_MODEL_ID, tokenizer.id(), pooling.as_str())
+}
+
+impl Embedder for EmbeddingModel {
+    fn name(&self) -> &str {
+        &self.id
+    }
 
-    /// Generate embeddings for multiple texts
-    pub fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
-        texts.iter().map(|t| self.embed(t)).collect()
+    fn dimension(&self) -> usize {
+        EMBEDDING_DIM
+    }
+
+    /// Generate embedding for a single text
+    ///
+    /// Algorithm:
+    /// 1. Tokenize text (see [`Tokenizer`])
+    /// 2. Embed each token using harmonic projection
+    /// 3. Average token embeddings, weighted by IDF when pooling by IDF
+    /// 4. L2 normalize result
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let tokens = self.tokenizer.tokenize(text);
+
+        if tokens.is_empty() {
+            return Ok(vec![0.0; EMBEDDING_DIM]);
+        }
+
+        // Embed each token
+        let mut sum_embedding = vec![0.0f64; EMBEDDING_DIM];
+        let mut total_weight = 0.0;
+
+        for token in &tokens {
+            let weight = self.token_weight(token);
+            let token_emb = self.embed_token(token);
+            for (i, val) in token_emb.iter().enumerate() {
+                sum_embedding[i] += weight * val;
+            }
+            total_weight += weight;
+        }
+
+        // Weighted mean pooling
+        if total_weight > 0.0 {
+            for val in &mut sum_embedding {
+                *val /= total_weight;
+            }
+        }
+
+        // L2 normalize and convert to f32
+        let norm: f64 = sum_embedding.iter().map(|x| x * x).sum::<f64>().sqrt();
+        let embedding: Vec<f32> = if norm > 0.0 {
+            sum_embedding.iter().map(|x| (*x / norm) as f32).collect()
+        } else {
+            sum_embedding.iter().map(|x| *x as f32).collect()
+        };
+
+        Ok(embedding)
+    }
+
+    fn uses_corpus_stats(&self) -> bool {
+        self.pooling == Pooling::Idf
+    }
+
+    fn corpus_stats(&self, documents: &[&str]) -> CorpusStats {
+        CorpusStats::collect(documents.iter().map(|d| self.tokenizer.tokenize(d)))
+    }
+
+    fn set_corpus_stats(&mut self, stats: CorpusStats) {
+        self.corpus = Some(stats);
     }
 }
 
@@ -163,16 +224,6 @@ impl Default for EmbeddingModel {
     }
 }
 
-/// Simple tokenization
-///
-/// Splits text into words, normalizes to lowercase
-fn tokenize(text: &str) -> Vec<String> {
-    text.split(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_lowercase())
-        .collect()
-}
-
 /// Cosine similarity between two embeddings
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() {
@@ -193,6 +244,7 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::search::embedder::Embedder;
 
     #[test]
     fn test_htp_basic() {
@@ -225,7 +277,12 @@ mod tests {
         let sim_different = cosine_similarity(&emb_gpu1, &emb_unrelated);
 
         // Note: HTP doesn't understand semantics, but shared tokens help
-        println!("Similar: {}, Different: {}", sim_similar, sim_different);
+        assert!(
+            sim_similar > sim_different,
+            "similar {} <= different {}",
+            sim_similar,
+            sim_different
+        );
     }
 
     #[test]
@@ -254,6 +311,45 @@ mod tests {
         assert!((cosine_similarity(&a, &d) + 1.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_hangul_particles() {
+        let model = EmbeddingModel::new();
+        let old_tokenizer = EmbeddingModel::with_tokenizer(TokenizerConfig {
+            hangul: crate::core::config::HangulMode::Word,
+            ..TokenizerConfig::default()
+        });
+
+        let query = "GPU 메모리";
+        let inflected = "메모리를";
+        let unrelated = "요리법";
+        let sim = |m: &EmbeddingModel, a: &str, b: &str| {
+            cosine_similarity(&m.embed(a).unwrap(), &m.embed(b).unwrap())
+        };
+
+        // Syllable bigrams let a particle-suffixed word match its stem
+        assert!(sim(&model, query, inflected) > sim(&old_tokenizer, query, inflected));
+        assert!(sim(&model, query, inflected) > sim(&model, query, unrelated));
+        assert_ne!(model.name(), old_tokenizer.name());
+        assert!(model.name().starts_with("htp-v2+tok"));
+    }
+
+    #[test]
+    fn test_idf_pooling() {
+        let documents = ["rust notes", "tea notes", "gpu notes", "notes"];
+        let mut model = EmbeddingModel::new();
+        let stats = model.corpus_stats(&documents);
+        model.set_corpus_stats(stats);
+        let mean = EmbeddingModel::new().with_pooling(Pooling::Mean);
+        assert!(model.uses_corpus_stats() && !mean.uses_corpus_stats());
+        assert_ne!(model.name(), mean.name());
+
+        // "notes" is in every document, so IDF pooling leans on "rust"
+        let sim = |m: &EmbeddingModel| {
+            cosine_similarity(&m.embed("rust notes").unwrap(), &m.embed("rust").unwrap())
+        };
+        assert!(sim(&model) > sim(&mean));
+    }
+
     #[test]
     fn test_korean_support() {
         let model = EmbeddingModel::new();
@@ -1,15 +1,24 @@
 //! Search Engine - combines embedding model and vector database
 //!
-//! Phase 1: gist-based semantic search
+//! Phase 1: semantic search over gists and body chunks
+//! Phase 2: BM25 keyword search, fused with vector results in hybrid mode
 
-use anyhow::{Context, Result};
-use std::path::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
 
-use super::embedding::EmbeddingModel;
-use super::vectordb::{IndexStats, NoteRecord, VectorDB};
+use super::chunker::{chunk_note, Chunk};
+use super::embedder::{create_embedder, embedder_id, Embedder};
+use super::embedding::cosine_similarity;
+use super::filter::SearchFilter;
+use super::vectordb::{IndexStats, NoteRecord, Passage, SearchHit, VectorDB};
+use crate::core::config::{FieldWeights, Quantization};
+use crate::core::edit::content_version;
+use crate::core::frontmatter::body_offset;
 use crate::core::note::{collect_all_notes, Note};
 use crate::core::paths::VaultPaths;
-use std::path::PathBuf as StdPathBuf;
 
 /// Search result with note metadata and similarity score
 #[derive(Debug, Clone)]
@@ -21,10 +30,14 @@ pub struct SearchResult {
     pub note_type: Option<String>,
     pub area: Option<String>,
     pub score: f32,
+    /// Best-matching body passage, if the note has indexed chunks
+    pub passage: Option<Passage>,
+    /// Keyword match in context, with matches wrapped in `**`
+    pub snippet: Option<String>,
 }
 
-impl From<(NoteRecord, f32)> for SearchResult {
-    fn from((record, score): (NoteRecord, f32)) -> Self {
+impl From<(NoteRecord, f32, Option<Passage>)> for SearchResult {
+    fn from((record, score, passage): (NoteRecord, f32, Option<Passage>)) -> Self {
         Self {
             id: record.id,
             path: record.path,
@@ -33,150 +46,494 @@ impl From<(NoteRecord, f32)> for SearchResult {
             note_type: record.note_type,
             area: record.area,
             score,
+            passage,
+            snippet: None,
         }
     }
 }
 
+/// How `search_with` retrieves notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    /// Vector and keyword results combined with reciprocal rank fusion
+    #[default]
+    Hybrid,
+    /// Embedding similarity only
+    Vector,
+    /// BM25 keyword matching only
+    Keyword,
+}
+
+impl SearchMode {
+    /// Whether the mode needs the embedder
+    pub fn uses_embedder(self) -> bool {
+        self != Self::Keyword
+    }
+}
+
+impl FromStr for SearchMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "hybrid" => Ok(Self::Hybrid),
+            "vector" => Ok(Self::Vector),
+            "keyword" => Ok(Self::Keyword),
+            _ => bail!(
+                "Unknown search mode '{}' (expected hybrid, vector or keyword)",
+                s
+            ),
+        }
+    }
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Hybrid => "hybrid",
+            Self::Vector => "vector",
+            Self::Keyword => "keyword",
+        })
+    }
+}
+
 /// Indexing statistics
 #[derive(Debug)]
 pub struct IndexingStats {
-    pub indexed: usize,
+    /// Notes embedded for the first time
+    pub added: usize,
+    /// Notes re-embedded because their content changed
+    pub updated: usize,
+    /// Notes dropped because their file vanished or has nothing to index
+    pub removed: usize,
+    /// Notes whose indexed content was still current
+    pub unchanged: usize,
     pub skipped: usize,
     pub failed: usize,
+    /// The index was cleared because it was built with a different embedder,
+    /// field weights or an older index format, or because the corpus
+    /// statistics its embeddings depend on were refreshed
+    pub rebuilt: bool,
     pub duration_ms: u128,
 }
 
-/// Search engine combining embedding model and vector database
+impl IndexingStats {
+    /// Notes in the index after the run
+    pub fn indexed(&self) -> usize {
+        self.added + self.updated + self.unchanged
+    }
+}
+
+/// Index metadata keys identifying the embedder that built the index
+pub const META_EMBEDDER: &str = "embedder";
+pub const META_DIMENSION: &str = "dimension";
+
+/// Index metadata key for the field weights of gist-level embeddings
+pub const META_FIELDS: &str = "fields";
+
+/// Index metadata key for the layout of the stored embeddings
+pub const META_FORMAT: &str = "format";
+/// Gist and body chunk embeddings plus the FTS5 keyword index; v2 indexes
+/// kept full-precision copies of quantized embeddings, which a rebuild drops
+pub const INDEX_FORMAT: &str = "chunks-fts-v3";
+
+/// Rank offset in reciprocal rank fusion; damps the weight of the top ranks
+const RRF_K: f32 = 60.0;
+
+/// Minimum candidates fetched from each retriever before fusion
+const HYBRID_CANDIDATES: usize = 30;
+
+/// Candidates re-scored at full precision per result, for quantized indexes
+const RESCORE_FACTOR: usize = 4;
+
+/// Texts embedded per `embed_batch` call while indexing
+const INDEX_BATCH_SIZE: usize = 32;
+
+/// Relative change in the number of notes after which corpus statistics
+/// are collected again, re-embedding every note
+const CORPUS_DRIFT: f32 = 0.2;
+
+/// Search engine combining an embedder and the vector database
 pub struct SearchEngine {
-    model: Option<EmbeddingModel>,
+    embedder: Option<Box<dyn Embedder>>,
+    /// Whether the corpus statistics stored in the index were handed to the embedder
+    corpus_loaded: bool,
     db: VectorDB,
     vault_paths: VaultPaths,
-    model_path: PathBuf,
 }
 
 impl SearchEngine {
     /// Create new search engine
     ///
-    /// Note: Model is loaded lazily on first search/index operation
-    pub fn new(vault_path: &Path, db_path: &Path, model_path: &Path) -> Result<Self> {
-        let vault_paths = VaultPaths::from_root(vault_path.to_path_buf());
+    /// The embedder configured in the vault is loaded lazily on first search/index operation
+    pub fn new(vault_path: &Path, db_path: &Path) -> Result<Self> {
+        let vault_paths = VaultPaths::open(vault_path.to_path_buf())?;
         let db = VectorDB::open(db_path)?;
 
         Ok(Self {
-            model: None,
+            embedder: None,
+            corpus_loaded: false,
             db,
             vault_paths,
-            model_path: model_path.to_path_buf(),
         })
     }
 
     /// Create with in-memory database (for testing)
-    pub fn new_in_memory(vault_path: &Path, model_path: &Path) -> Result<Self> {
-        let vault_paths = VaultPaths::from_root(vault_path.to_path_buf());
+    pub fn new_in_memory(vault_path: &Path) -> Result<Self> {
+        let vault_paths = VaultPaths::open(vault_path.to_path_buf())?;
         let db = VectorDB::open_in_memory()?;
 
         Ok(Self {
-            model: None,
+            embedder: None,
+            corpus_loaded: false,
             db,
             vault_paths,
-            model_path: model_path.to_path_buf(),
         })
     }
 
-    /// Ensure model is loaded
-    fn ensure_model(&mut self) -> Result<&EmbeddingModel> {
-        if self.model.is_none() {
-            let model = EmbeddingModel::load(&self.model_path)
-                .context("Failed to load embedding model")?;
-            self.model = Some(model);
+    /// Use `embedder` instead of the one configured in the vault
+    pub fn with_embedder(mut self, embedder: Box<dyn Embedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// Ensure the embedder is loaded, with the index's corpus statistics
+    fn ensure_embedder(&mut self) -> Result<()> {
+        if self.embedder.is_none() {
+            let embedder = create_embedder(
+                &self.vault_paths.config.search.embedder,
+                &self.vault_paths.root,
+            )
+            .context("Failed to load embedding model")?;
+            self.embedder = Some(embedder);
         }
-        Ok(self.model.as_ref().unwrap())
+        if !self.corpus_loaded {
+            let embedder = self.embedder.as_deref_mut().unwrap();
+            if embedder.uses_corpus_stats() {
+                if let Some(stats) = self.db.load_corpus_stats()? {
+                    embedder.set_corpus_stats(stats);
+                }
+            }
+            self.corpus_loaded = true;
+        }
+        Ok(())
+    }
+
+    /// Model id and dimension of the active embedder, without loading it
+    pub fn embedder_id(&self) -> (String, usize) {
+        match &self.embedder {
+            Some(e) => (e.name().to_string(), e.dimension()),
+            None => embedder_id(&self.vault_paths.config.search.embedder),
+        }
+    }
+
+    /// Model id and dimension the index was built with, if recorded
+    pub fn index_embedder(&self) -> Result<Option<(String, usize)>> {
+        let name = self.db.get_meta(META_EMBEDDER)?;
+        let dimension = self.db.get_meta(META_DIMENSION)?;
+        Ok(match (name, dimension) {
+            (Some(name), Some(dimension)) => Some((name, dimension.parse()?)),
+            _ => None,
+        })
     }
 
-    /// Search for notes similar to query
-    pub fn search(&mut self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let model = self.ensure_model()?;
+    /// Search for notes similar to query among notes passing `filter`
+    pub fn search(
+        &mut self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let current = self.embedder_id();
+        if let Some(indexed) = self.index_embedder()? {
+            if indexed != current {
+                bail!(
+                    "Index was built with {} ({} dims) but the vault is configured for {} ({} dims); run `elysium index` to rebuild it",
+                    indexed.0,
+                    indexed.1,
+                    current.0,
+                    current.1
+                );
+            }
+        }
+
+        self.ensure_embedder()?;
+        let embedder = self.embedder.as_deref().unwrap();
 
         // Generate query embedding
-        let query_embedding = model.embed(query)?;
+        let query_embedding = embedder.embed(query)?;
 
-        // Search in vector database
-        let results = self.db.search(&query_embedding, limit)?;
+        // Search in vector database; quantized scores are only estimates, so
+        // fetch extra candidates and re-score them at full precision
+        let filter = filter.resolve(&self.vault_paths.root);
+        let results = if self.db.quantization() == Quantization::None {
+            self.db.search(&query_embedding, &filter, limit)?
+        } else {
+            let candidates = self.db.search(
+                &query_embedding,
+                &filter,
+                limit.saturating_mul(RESCORE_FACTOR),
+            )?;
+            rescore(
+                embedder,
+                &self.vault_paths.config.search.fields,
+                &query_embedding,
+                candidates,
+                limit,
+            )?
+        };
 
         // Convert to SearchResult
         Ok(results.into_iter().map(SearchResult::from).collect())
     }
 
+    /// Search with the retrieval strategy `mode`
+    pub fn search_with(
+        &mut self,
+        mode: SearchMode,
+        query: &str,
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        match mode {
+            SearchMode::Hybrid => self.hybrid_search(query, filter, limit),
+            SearchMode::Vector => self.search(query, filter, limit),
+            SearchMode::Keyword => self.keyword_search(query, filter, limit),
+        }
+    }
+
+    /// BM25 keyword search over title, gist, tags and body
+    ///
+    /// Scores are BM25 relevance (higher is better) and are not comparable
+    /// with similarity scores. Does not need the embedder.
+    pub fn keyword_search(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let filter = filter.resolve(&self.vault_paths.root);
+        let hits = self.db.keyword_search(query, None, &filter, limit)?;
+        Ok(hits
+            .into_iter()
+            .map(|(record, score, snippet)| SearchResult {
+                snippet: Some(snippet),
+                ..SearchResult::from((record, score, None))
+            })
+            .collect())
+    }
+
+    /// Vector and keyword search fused with reciprocal rank fusion
+    ///
+    /// Each note scores `1 / (RRF_K + rank)` per result list it appears in,
+    /// so notes found by both retrievers rise to the top even when one of
+    /// them ranks a lexical near-miss poorly. Scores are scaled so a note
+    /// ranked first by both retrievers scores 1.0.
+    pub fn hybrid_search(
+        &mut self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let candidates = (limit * 3).max(HYBRID_CANDIDATES);
+        let vector = self.search(query, filter, candidates)?;
+        let keyword = self.keyword_search(query, filter, candidates)?;
+        Ok(reciprocal_rank_fusion([vector, keyword], limit))
+    }
+
     /// Index all notes in vault
+    ///
+    /// Each note is embedded as its gist, mixed with its title and tags, plus
+    /// one embedding per body chunk, and the ANN index is brought up to date.
+    /// Embedders that weight tokens by corpus statistics get them collected
+    /// over all notes on a rebuild or once the vault size drifted by more than
+    /// `CORPUS_DRIFT`, which re-embeds every note.
+    /// Only new notes and notes whose content changed are embedded; a note
+    /// whose mtime moved but whose content hash did not is left as is. Rows
+    /// for deleted and empty notes are removed. An index built by a different
+    /// embedder or in an older format is cleared and rebuilt from scratch.
     pub fn index_all(&mut self) -> Result<IndexingStats> {
         let start = std::time::Instant::now();
 
-        // Collect all notes
+        self.ensure_embedder()?;
+        let current = self.embedder_id();
+        let current_format = self.db.get_meta(META_FORMAT)?.as_deref() == Some(INDEX_FORMAT);
+        let quantization = self.vault_paths.config.search.quantization;
+        let fields = self.vault_paths.config.search.fields.clone();
+        let current_fields = self.db.get_meta(META_FIELDS)?.as_deref() == Some(&*fields.id());
+        let mut rebuilt = match self.index_embedder()? {
+            Some(indexed) => {
+                indexed != current
+                    || !current_format
+                    || !current_fields
+                    || self.db.quantization() != quantization
+            }
+            None => self.db.get_stats()?.note_count > 0,
+        };
+
         let notes = collect_all_notes(&self.vault_paths);
 
-        let mut indexed = 0;
+        let embedder = self.embedder.as_deref_mut().unwrap();
+        let mut corpus = None;
+        if embedder.uses_corpus_stats() {
+            let collected = self.db.corpus_documents()?;
+            if rebuilt || collected.is_none_or(|n| corpus_drifted(n, notes.len())) {
+                let documents: Vec<String> = notes.iter().map(corpus_document).collect();
+                let documents: Vec<&str> = documents.iter().map(String::as_str).collect();
+                corpus = Some(embedder.corpus_stats(&documents));
+                // Embeddings made with other statistics are not comparable
+                rebuilt = rebuilt || self.db.get_stats()?.note_count > 0;
+            }
+        }
+
+        if rebuilt {
+            self.db.clear()?;
+        }
+        if let Some(stats) = corpus {
+            self.db.save_corpus_stats(&stats)?;
+            embedder.set_corpus_stats(stats);
+        }
+        self.db.set_quantization(quantization)?;
+        self.db.set_meta(META_EMBEDDER, &current.0)?;
+        self.db.set_meta(META_DIMENSION, &current.1.to_string())?;
+        self.db.set_meta(META_FIELDS, &fields.id())?;
+        self.db.set_meta(META_FORMAT, INDEX_FORMAT)?;
+        let mut existing = self.db.get_indexed_notes()?;
+        let mut seen = HashSet::new();
+
+        let mut unchanged = 0;
         let mut skipped = 0;
         let mut failed = 0;
+        let mut pending: Vec<PendingNote> = Vec::new();
+
+        for note in &notes {
+            let Some(texts) = NoteTexts::of(note, &fields) else {
+                skipped += 1;
+                continue;
+            };
+            if !seen.insert(note.name.as_str()) {
+                eprintln!(
+                    "Skipping {}: another note named {} is already indexed",
+                    note.path.display(),
+                    note.name
+                );
+                skipped += 1;
+                continue;
+            }
+
+            let path = note.path.to_string_lossy();
+            let mtime = note.modified.timestamp();
+            match existing.remove(&note.name) {
+                Some(row) if row.path == path && row.mtime == mtime => unchanged += 1,
+                Some(row) => {
+                    let hash = content_version(&note.content);
+                    if row.content_hash.as_deref() == Some(hash.as_str()) {
+                        // Moved or touched; the embeddings are still valid
+                        self.db.update_location(&note.name, &path, mtime)?;
+                        unchanged += 1;
+                    } else {
+                        pending.push(PendingNote {
+                            texts,
+                            hash,
+                            existed: true,
+                        });
+                    }
+                }
+                None => pending.push(PendingNote {
+                    texts,
+                    hash: content_version(&note.content),
+                    existed: false,
+                }),
+            }
+        }
+
+        // Whatever is left vanished from the vault or has nothing to index.
+        // Removing it first frees paths that renamed notes now occupy.
+        let removed = existing.len();
+        for id in existing.keys() {
+            self.db.delete_note(id)?;
+        }
+
+        let mut added = 0;
+        let mut updated = 0;
+        let embedder = self.embedder.as_deref().unwrap();
+
+        // Group notes so each group fills at least one embedding batch
+        let mut groups: Vec<Vec<PendingNote>> = vec![Vec::new()];
+        let mut group_size = 0;
+        for item in pending {
+            if group_size >= INDEX_BATCH_SIZE {
+                groups.push(Vec::new());
+                group_size = 0;
+            }
+            group_size += item.texts.len();
+            groups.last_mut().unwrap().push(item);
+        }
 
-        for note in notes {
-            match self.index_note(&note) {
-                Ok(true) => indexed += 1,
-                Ok(false) => skipped += 1,
+        for group in groups.iter().filter(|g| !g.is_empty()) {
+            let texts: Vec<String> = group.iter().flat_map(|p| p.texts.texts()).collect();
+            let mut embeddings = match embed_texts(embedder, &texts) {
+                Ok(embeddings) => embeddings.into_iter(),
                 Err(e) => {
-                    eprintln!("Failed to index {}: {}", note.name, e);
-                    failed += 1;
+                    eprintln!("Failed to embed {} notes: {}", group.len(), e);
+                    failed += group.len();
+                    continue;
+                }
+            };
+
+            for item in group {
+                let note_embeddings: Vec<Vec<f32>> =
+                    embeddings.by_ref().take(item.texts.len()).collect();
+                match item.texts.store(&self.db, &item.hash, note_embeddings) {
+                    Ok(()) if item.existed => updated += 1,
+                    Ok(()) => added += 1,
+                    Err(e) => {
+                        eprintln!("Failed to index {}: {}", item.texts.note.name, e);
+                        failed += 1;
+                    }
                 }
             }
         }
 
-        let duration_ms = start.elapsed().as_millis();
+        // Large indexes are searched through the ANN graph
+        self.db.save_ann()?;
+
+        let stats = IndexingStats {
+            added,
+            updated,
+            removed,
+            unchanged,
+            skipped,
+            failed,
+            rebuilt,
+            duration_ms: start.elapsed().as_millis(),
+        };
 
         // Update metadata
-        self.db.set_meta("indexed_count", &indexed.to_string())?;
+        self.db
+            .set_meta("indexed_count", &stats.indexed().to_string())?;
         self.db.set_meta(
             "last_full_index",
             &chrono::Utc::now().timestamp().to_string(),
         )?;
 
-        Ok(IndexingStats {
-            indexed,
-            skipped,
-            failed,
-            duration_ms,
-        })
+        Ok(stats)
     }
 
     /// Index a single note
     ///
-    /// Returns Ok(true) if indexed, Ok(false) if skipped (no gist)
+    /// Returns Ok(true) if indexed, Ok(false) if skipped (no gist and empty body)
     pub fn index_note(&mut self, note: &Note) -> Result<bool> {
-        // Skip notes without gist
-        let gist = match note.gist() {
-            Some(g) if !g.is_empty() => g,
-            _ => return Ok(false),
+        let Some(texts) = NoteTexts::of(note, &self.vault_paths.config.search.fields) else {
+            return Ok(false);
         };
 
-        // Ensure model is loaded
-        let model = self.ensure_model()?;
-
-        // Generate embedding from gist
-        let embedding = model.embed(gist)?;
-
-        // Create note record
-        let record = NoteRecord {
-            id: note.name.clone(),
-            path: note.path.to_string_lossy().to_string(),
-            title: note.name.clone(),
-            gist: Some(gist.to_string()),
-            note_type: note.note_type().map(String::from),
-            status: note.status().map(String::from),
-            area: note.area().map(String::from),
-            tags: note.tags(),
-            mtime: note.modified.timestamp(),
-        };
+        // Ensure embedder is loaded
+        self.ensure_embedder()?;
+        let embedder = self.embedder.as_deref().unwrap();
 
-        // Upsert to database
-        self.db.upsert_note(&record, &embedding)?;
+        let embeddings = embed_texts(embedder, &texts.texts())?;
+        texts.store(&self.db, &content_version(&note.content), embeddings)?;
 
         Ok(true)
     }
@@ -186,11 +543,6 @@ impl SearchEngine {
         self.db.get_stats()
     }
 
-    /// Check if model is available
-    pub fn model_exists(&self) -> bool {
-        self.model_path.exists()
-    }
-
     /// Get database path
     pub fn db_path(&self) -> &Path {
         // We can't easily get this from rusqlite Connection
@@ -199,14 +551,265 @@ impl SearchEngine {
     }
 }
 
-/// Simple search without ONNX model (for testing or fallback)
+/// Merge ranked result lists by reciprocal rank fusion
+///
+/// A note keeps the passage and snippet of whichever list supplied them.
+fn reciprocal_rank_fusion<const N: usize>(
+    lists: [Vec<SearchResult>; N],
+    limit: usize,
+) -> Vec<SearchResult> {
+    let mut fused: HashMap<String, SearchResult> = HashMap::new();
+
+    for list in lists {
+        for (rank, result) in list.into_iter().enumerate() {
+            let score = 1.0 / (RRF_K + rank as f32 + 1.0);
+            match fused.get_mut(&result.id) {
+                Some(existing) => {
+                    existing.score += score;
+                    existing.passage = existing.passage.take().or(result.passage);
+                    existing.snippet = existing.snippet.take().or(result.snippet);
+                }
+                None => {
+                    fused.insert(result.id.clone(), SearchResult { score, ..result });
+                }
+            }
+        }
+    }
+
+    let best = N as f32 / (RRF_K + 1.0);
+    let mut results: Vec<SearchResult> = fused
+        .into_values()
+        .map(|r| SearchResult {
+            score: r.score / best,
+            ..r
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    results.truncate(limit);
+    results
+}
+
+/// The texts a note is embedded from: the fields mixed into its gist-level
+/// embedding, then its body chunks
+struct NoteTexts<'a> {
+    note: &'a Note,
+    fields: Vec<(f32, String)>,
+    chunks: Vec<Chunk>,
+}
+
+impl<'a> NoteTexts<'a> {
+    /// None if the note has neither a gist nor any body text
+    fn of(note: &'a Note, weights: &FieldWeights) -> Option<Self> {
+        let fields = note_fields(weights, &note.name, &note.tags(), note.gist());
+        let chunks = chunk_note(&note.content);
+        if fields.is_empty() && chunks.is_empty() {
+            return None;
+        }
+        Some(Self {
+            note,
+            fields,
+            chunks,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.fields.len() + self.chunks.len()
+    }
+
+    fn texts(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .map(|(_, text)| text.clone())
+            .chain(
+                self.chunks
+                    .iter()
+                    .map(|c| c.embedding_text(&self.note.name)),
+            )
+            .collect()
+    }
+
+    /// Write the note and its embeddings, given in `texts()` order
+    fn store(&self, db: &VectorDB, hash: &str, embeddings: Vec<Vec<f32>>) -> Result<()> {
+        if embeddings.len() != self.len() {
+            bail!("Embedder returned fewer vectors than texts");
+        }
+        let mut embeddings = embeddings.into_iter();
+        let field_embeddings: Vec<Vec<f32>> = embeddings.by_ref().take(self.fields.len()).collect();
+        let gist_embedding = (!self.fields.is_empty()).then(|| {
+            let weights: Vec<f32> = self.fields.iter().map(|(weight, _)| *weight).collect();
+            mix(&weights, &field_embeddings)
+        });
+        let chunks: Vec<(Chunk, Vec<f32>)> = self.chunks.iter().cloned().zip(embeddings).collect();
+
+        db.upsert_note(
+            &note_record(self.note),
+            &self.note.content[body_offset(&self.note.content)..],
+            hash,
+            gist_embedding.as_deref(),
+            &chunks,
+        )
+    }
+}
+
+/// A changed note waiting to be embedded
+struct PendingNote<'a> {
+    texts: NoteTexts<'a>,
+    hash: String,
+    /// Whether the note already had a row in the index
+    existed: bool,
+}
+
+/// Embed `texts` in batches of `INDEX_BATCH_SIZE`
+fn embed_texts(embedder: &dyn Embedder, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(INDEX_BATCH_SIZE) {
+        let batch: Vec<&str> = batch.iter().map(String::as_str).collect();
+        embeddings.extend(embedder.embed_batch(&batch)?);
+    }
+    Ok(embeddings)
+}
+
+/// The weighted texts mixed into a note's gist-level embedding; empty for
+/// notes without a gist
+fn note_fields(
+    weights: &FieldWeights,
+    title: &str,
+    tags: &[String],
+    gist: Option<&str>,
+) -> Vec<(f32, String)> {
+    let Some(gist) = gist.filter(|g| !g.is_empty()) else {
+        return Vec::new();
+    };
+    [
+        (weights.gist, gist.to_string()),
+        (weights.title, title.to_string()),
+        (weights.tags, tags.join(" ")),
+    ]
+    .into_iter()
+    .filter(|(weight, text)| *weight > 0.0 && !text.is_empty())
+    .collect()
+}
+
+/// Weighted sum of the normalized `embeddings`, normalized
+fn mix(weights: &[f32], embeddings: &[Vec<f32>]) -> Vec<f32> {
+    let mut mixed = vec![0.0f32; embeddings.first().map_or(0, Vec::len)];
+    for (weight, embedding) in weights.iter().zip(embeddings) {
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for (m, x) in mixed.iter_mut().zip(embedding) {
+                *m += weight * x / norm;
+            }
+        }
+    }
+    let norm = mixed.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        mixed.iter_mut().for_each(|x| *x /= norm);
+    }
+    mixed
+}
+
+/// Re-score candidates from a quantized index against freshly embedded
+/// full-precision vectors of the gist-level fields and the passage each one
+/// matched on, all embedded in one batch
+fn rescore(
+    embedder: &dyn Embedder,
+    weights: &FieldWeights,
+    query: &[f32],
+    candidates: Vec<SearchHit>,
+    limit: usize,
+) -> Result<Vec<SearchHit>> {
+    let mut texts = Vec::new();
+    let mut field_weights = Vec::with_capacity(candidates.len());
+    for (note, _, passage) in &candidates {
+        let fields = note_fields(weights, &note.title, &note.tags, note.gist.as_deref());
+        texts.extend(fields.iter().map(|(_, text)| text.clone()));
+        if let Some(passage) = passage {
+            let chunk = Chunk {
+                heading_path: passage.heading_path.clone(),
+                start: passage.start,
+                end: passage.end,
+                text: passage.text.clone(),
+            };
+            texts.push(chunk.embedding_text(&note.title));
+        }
+        field_weights.push(
+            fields
+                .into_iter()
+                .map(|(weight, _)| weight)
+                .collect::<Vec<f32>>(),
+        );
+    }
+
+    let mut embeddings = embed_texts(embedder, &texts)?.into_iter();
+    let mut rescored = Vec::with_capacity(candidates.len());
+    for ((note, estimate, passage), weights) in candidates.into_iter().zip(field_weights) {
+        let fields: Vec<Vec<f32>> = embeddings.by_ref().take(weights.len()).collect();
+        let mut score =
+            (!weights.is_empty()).then(|| cosine_similarity(query, &mix(&weights, &fields)));
+        if passage.is_some() {
+            if let Some(embedding) = embeddings.next() {
+                let similarity = cosine_similarity(query, &embedding);
+                score = Some(score.map_or(similarity, |s| s.max(similarity)));
+            }
+        }
+        rescored.push((note, score.unwrap_or(estimate), passage));
+    }
+    rescored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    rescored.truncate(limit);
+    Ok(rescored)
+}
+
+fn note_record(note: &Note) -> NoteRecord {
+    NoteRecord {
+        id: note.name.clone(),
+        path: note.path.to_string_lossy().to_string(),
+        title: note.name.clone(),
+        gist: note.gist().map(String::from),
+        note_type: note.note_type().map(String::from),
+        status: note.status().map(String::from),
+        area: note.area().map(String::from),
+        tags: note.tags(),
+        mtime: note.modified.timestamp(),
+    }
+}
+
+/// Text a note contributes to corpus statistics
+fn corpus_document(note: &Note) -> String {
+    format!(
+        "{}\n{}\n{}\n{}",
+        note.name,
+        note.tags().join(" "),
+        note.gist().unwrap_or(""),
+        &note.content[body_offset(&note.content)..]
+    )
+}
+
+/// Whether the vault size moved too far from the `collected` notes that
+/// corpus statistics were taken over
+fn corpus_drifted(collected: usize, current: usize) -> bool {
+    collected.abs_diff(current) as f32 > CORPUS_DRIFT * collected as f32
+}
+
+/// Simple search without an embedder or index (for testing or fallback)
 /// Uses basic string matching on gist
-pub fn simple_search(vault_paths: &VaultPaths, query: &str, limit: usize) -> Vec<SearchResult> {
+pub fn simple_search(
+    vault_paths: &VaultPaths,
+    query: &str,
+    filter: &SearchFilter,
+    limit: usize,
+) -> Vec<SearchResult> {
     let notes = collect_all_notes(vault_paths);
     let query_lower = query.to_lowercase();
+    let filter = filter.resolve(&vault_paths.root);
 
     let mut results: Vec<SearchResult> = notes
         .iter()
+        .filter(|note| filter.matches(note))
         .filter_map(|note| {
             let gist = note.gist()?;
             let gist_lower = gist.to_lowercase();
@@ -232,6 +835,8 @@ pub fn simple_search(vault_paths: &VaultPaths, query: &str, limit: usize) -> Vec
                 note_type: note.note_type().map(String::from),
                 area: note.area().map(String::from),
                 score,
+                passage: None,
+                snippet: None,
             })
         })
         .collect();
@@ -246,13 +851,197 @@ pub fn simple_search(vault_paths: &VaultPaths, query: &str, limit: usize) -> Vec
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::search::hashed::HashedEmbedder;
 
     #[test]
     fn test_simple_search() {
         // This test requires actual vault files
         // Just verify the function compiles and returns expected type
         let vault_paths = VaultPaths::from_root(std::path::PathBuf::from("/tmp/nonexistent"));
-        let results = simple_search(&vault_paths, "test query", 5);
+        let results = simple_search(&vault_paths, "test query", &SearchFilter::default(), 5);
         assert!(results.is_empty()); // No files in nonexistent path
     }
+
+    fn write_note(dir: &Path, rel: &str, gist: &str, mtime: u64) {
+        let path = dir.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, format!("---\ngist: {}\n---\nBody\n", gist)).unwrap();
+        let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(mtime);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_incremental_indexing() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path();
+        write_note(dir, "Notes/A.md", "alpha", 1_000);
+        write_note(dir, "Notes/B.md", "beta", 1_000);
+        std::fs::write(dir.join("Notes/C.md"), "---\ntype: note\n---\n").unwrap();
+
+        let mut engine =
+            SearchEngine::new_in_memory(dir)?.with_embedder(Box::new(HashedEmbedder::new(64)));
+        let first = engine.index_all()?;
+        let second = engine.index_all()?;
+
+        // Edit A, move B without changing it, add D
+        write_note(dir, "Notes/A.md", "alpha edited", 2_000);
+        std::fs::create_dir_all(dir.join("Archive")).unwrap();
+        std::fs::rename(dir.join("Notes/B.md"), dir.join("Archive/B.md")).unwrap();
+        write_note(dir, "Notes/D.md", "delta", 2_000);
+        let third = engine.index_all()?;
+
+        std::fs::remove_file(dir.join("Notes/D.md")).unwrap();
+        let fourth = engine.index_all()?;
+        let b_path = engine.db.get_note("B")?.map(|n| n.path);
+
+        assert_eq!((first.added, first.skipped), (2, 1));
+        assert_eq!((second.added, second.unchanged), (0, 2));
+        assert_eq!(
+            (third.added, third.updated, third.removed, third.unchanged),
+            (1, 1, 0, 1)
+        );
+        assert_eq!((fourth.removed, fourth.indexed()), (1, 2));
+        assert_eq!(
+            b_path,
+            Some(dir.join("Archive/B.md").to_string_lossy().into_owned())
+        );
+        assert_eq!(engine.get_stats()?.embedding_count, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantized_search_rescored() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path();
+        write_note(
+            dir,
+            "Notes/Gpu.md",
+            "GPU memory sharing between containers",
+            1_000,
+        );
+        write_note(dir, "Notes/Rust.md", "Rust ownership and borrowing", 1_000);
+        write_note(dir, "Notes/Tea.md", "Brewing green tea", 1_000);
+        let search = |config: &str| -> Result<(Vec<SearchResult>, Quantization)> {
+            std::fs::write(dir.join("elysium.yaml"), config)?;
+            let mut engine =
+                SearchEngine::new_in_memory(dir)?.with_embedder(Box::new(HashedEmbedder::new(128)));
+            engine.index_all()?;
+            let results = engine.search("gpu memory", &SearchFilter::default(), 2)?;
+            Ok((results, engine.db.quantization()))
+        };
+
+        let (exact, _) = search("search:\n  quantization: none\n")?;
+        let (int8, quantization) = search("search:\n  quantization: int8\n")?;
+        let (binary, _) = search("search:\n  quantization: binary\n")?;
+
+        // Re-scoring restores the full-precision scores
+        assert_eq!(quantization, Quantization::Int8);
+        for quantized in [&int8, &binary] {
+            assert_eq!(quantized[0].id, "Gpu");
+            assert!((quantized[0].score - exact[0].score).abs() < 1e-5);
+        }
+        Ok(())
+    }
+
+    /// Mean reciprocal rank of vector search over the retrieval fixture
+    fn fixture_mrr(search: crate::core::config::SearchConfig) -> Result<f64> {
+        use crate::search::eval::{evaluate, load_queries};
+
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/retrieval");
+        let mut engine = SearchEngine::new_in_memory(&root)?;
+        engine.vault_paths.config.search = search;
+        engine.index_all()?;
+
+        let queries = load_queries(&root.join("queries.yaml"))?;
+        let reports = evaluate(&mut engine, &queries, &[SearchMode::Vector], 10)?;
+        Ok(reports[0].metrics.mrr)
+    }
+
+    #[test]
+    fn test_idf_and_field_weights_improve_retrieval() -> Result<()> {
+        use crate::core::config::{EmbedderConfig, Pooling, SearchConfig, TokenizerConfig};
+
+        let config = |pooling: Pooling, fields: FieldWeights| SearchConfig {
+            embedder: EmbedderConfig::Htp {
+                tokenizer: TokenizerConfig::default(),
+                pooling,
+            },
+            fields,
+            ..SearchConfig::default()
+        };
+        let gist_only = FieldWeights {
+            title: 0.0,
+            tags: 0.0,
+            gist: 1.0,
+        };
+        let baseline = fixture_mrr(config(Pooling::Mean, gist_only.clone()))?;
+
+        // Only the pooling differs
+        let idf = fixture_mrr(config(Pooling::Idf, gist_only))?;
+        assert!(
+            idf > baseline,
+            "MRR with IDF pooling {} <= mean pooling {}",
+            idf,
+            baseline
+        );
+
+        // Only the field weights differ
+        let fields = fixture_mrr(config(Pooling::Mean, FieldWeights::default()))?;
+        assert!(
+            fields > baseline,
+            "MRR with field weights {} <= gist only {}",
+            fields,
+            baseline
+        );
+
+        let weighted = fixture_mrr(SearchConfig::default())?;
+        assert!(
+            weighted > baseline,
+            "MRR with IDF pooling and field weights {} <= mean pooling {}",
+            weighted,
+            baseline
+        );
+        Ok(())
+    }
+
+    fn result(id: &str) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            path: format!("Notes/{}.md", id),
+            title: id.to_string(),
+            gist: None,
+            note_type: None,
+            area: None,
+            score: 0.0,
+            passage: None,
+            snippet: Some(format!("**{}**", id)),
+        }
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let vector = vec![result("a"), result("b"), result("c")];
+        let keyword = vec![result("c"), result("d"), result("b")];
+
+        let fused = reciprocal_rank_fusion([vector, keyword], 3);
+        let ids: Vec<&str> = fused.iter().map(|r| r.id.as_str()).collect();
+
+        // c (ranks 3 and 1) and b (ranks 2 and 3) beat single-list hits
+        assert_eq!(ids, vec!["c", "b", "a"]);
+        assert!(fused[0].score < 1.0);
+        assert_eq!(
+            reciprocal_rank_fusion([vec![result("x")], vec![result("x")]], 1)[0].score,
+            1.0
+        );
+        assert_eq!(
+            "Keyword".parse::<SearchMode>().unwrap(),
+            SearchMode::Keyword
+        );
+        assert!("fuzzy".parse::<SearchMode>().is_err());
+    }
 }
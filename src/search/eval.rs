@@ -0,0 +1,280 @@
+//! Retrieval evaluation against golden queries
+//!
+//! A golden file lists queries with the notes a good search should return.
+//! Each search mode is scored with recall@k, MRR and nDCG@k (binary
+//! relevance), so embedder and chunking changes can be compared objectively.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::engine::{SearchEngine, SearchMode, SearchResult};
+use super::filter::SearchFilter;
+
+/// A query and the notes expected among its results
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoldenQuery {
+    pub query: String,
+    /// Note names or vault-relative paths (with or without `.md`)
+    pub expected: Vec<String>,
+}
+
+/// Load golden queries from a `.jsonl` file (one query per line) or a YAML
+/// file holding a list of queries
+pub fn load_queries(path: &Path) -> Result<Vec<GoldenQuery>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let queries = if path.extension().is_some_and(|ext| ext == "jsonl") {
+        parse_jsonl(&content)?
+    } else {
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Invalid golden queries in {}", path.display()))?
+    };
+    validate(&queries)?;
+    Ok(queries)
+}
+
+fn parse_jsonl(content: &str) -> Result<Vec<GoldenQuery>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Invalid golden query on line {}", i + 1))
+        })
+        .collect()
+}
+
+fn validate(queries: &[GoldenQuery]) -> Result<()> {
+    if queries.is_empty() {
+        bail!("No golden queries found");
+    }
+    if let Some(query) = queries.iter().find(|q| q.expected.is_empty()) {
+        bail!("Golden query '{}' expects no notes", query.query);
+    }
+    Ok(())
+}
+
+/// Retrieval quality, averaged over queries when aggregated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Metrics {
+    /// Share of expected notes found in the top k
+    pub recall: f64,
+    /// Reciprocal rank of the first expected note in the top k
+    pub mrr: f64,
+    /// Discounted cumulative gain of the top k, relative to the ideal ranking
+    pub ndcg: f64,
+}
+
+impl Metrics {
+    /// Metrics of one ranked result list; `relevant` flags each result and
+    /// `expected` is the number of notes that should have been found
+    pub fn score(relevant: &[bool], expected: usize, k: usize) -> Self {
+        if expected == 0 {
+            return Self::default();
+        }
+        let top = &relevant[..relevant.len().min(k)];
+        let found = top.iter().filter(|&&r| r).count();
+        let mrr = top
+            .iter()
+            .position(|&r| r)
+            .map_or(0.0, |rank| 1.0 / (rank + 1) as f64);
+        let dcg: f64 = top
+            .iter()
+            .enumerate()
+            .filter(|(_, &r)| r)
+            .map(|(rank, _)| discount(rank))
+            .sum();
+        let ideal: f64 = (0..expected.min(k)).map(discount).sum();
+        Self {
+            recall: found as f64 / expected as f64,
+            mrr,
+            ndcg: dcg / ideal,
+        }
+    }
+
+    fn mean(metrics: &[Self]) -> Self {
+        let n = metrics.len().max(1) as f64;
+        Self {
+            recall: metrics.iter().map(|m| m.recall).sum::<f64>() / n,
+            mrr: metrics.iter().map(|m| m.mrr).sum::<f64>() / n,
+            ndcg: metrics.iter().map(|m| m.ndcg).sum::<f64>() / n,
+        }
+    }
+}
+
+/// DCG discount of the 0-based `rank`
+fn discount(rank: usize) -> f64 {
+    1.0 / ((rank + 2) as f64).log2()
+}
+
+/// How one query fared in one mode
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryReport {
+    pub query: String,
+    /// 1-based ranks of the expected notes found in the top k
+    pub ranks: Vec<usize>,
+    /// Expected notes missing from the top k
+    pub missed: Vec<String>,
+    #[serde(flatten)]
+    pub metrics: Metrics,
+}
+
+/// Metrics of one search mode over all golden queries
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeReport {
+    #[serde(serialize_with = "serialize_mode")]
+    pub mode: SearchMode,
+    #[serde(flatten)]
+    pub metrics: Metrics,
+    pub queries: Vec<QueryReport>,
+}
+
+fn serialize_mode<S: serde::Serializer>(mode: &SearchMode, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(mode)
+}
+
+/// Run every query in each of `modes` and score the top `k` results
+pub fn evaluate(
+    engine: &mut SearchEngine,
+    queries: &[GoldenQuery],
+    modes: &[SearchMode],
+    k: usize,
+) -> Result<Vec<ModeReport>> {
+    if k == 0 {
+        bail!("k must be at least 1");
+    }
+    let filter = SearchFilter::default();
+    modes
+        .iter()
+        .map(|&mode| {
+            let queries = queries
+                .iter()
+                .map(|golden| {
+                    let results = engine.search_with(mode, &golden.query, &filter, k)?;
+                    Ok(judge(golden, &results, k))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let metrics: Vec<Metrics> = queries.iter().map(|q| q.metrics).collect();
+            Ok(ModeReport {
+                mode,
+                metrics: Metrics::mean(&metrics),
+                queries,
+            })
+        })
+        .collect()
+}
+
+/// Score `results` against the notes `golden` expects
+fn judge(golden: &GoldenQuery, results: &[SearchResult], k: usize) -> QueryReport {
+    let relevant: Vec<bool> = results
+        .iter()
+        .map(|r| golden.expected.iter().any(|e| matches(r, e)))
+        .collect();
+    let ranks = relevant
+        .iter()
+        .take(k)
+        .enumerate()
+        .filter(|(_, &r)| r)
+        .map(|(rank, _)| rank + 1)
+        .collect();
+    let missed = golden
+        .expected
+        .iter()
+        .filter(|e| !results.iter().take(k).any(|r| matches(r, e)))
+        .cloned()
+        .collect();
+    QueryReport {
+        query: golden.query.clone(),
+        ranks,
+        missed,
+        metrics: Metrics::score(&relevant, golden.expected.len(), k),
+    }
+}
+
+/// Whether `result` is the note named by `expected`
+fn matches(result: &SearchResult, expected: &str) -> bool {
+    let expected = expected.trim_start_matches("./");
+    if result.id.eq_ignore_ascii_case(expected) {
+        return true;
+    }
+    let stem = result.path.strip_suffix(".md").unwrap_or(&result.path);
+    let expected = expected.strip_suffix(".md").unwrap_or(expected);
+    stem.ends_with(&format!("/{}", expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let perfect = Metrics::score(&[true, true, false], 2, 10);
+        assert_eq!(
+            perfect,
+            Metrics {
+                recall: 1.0,
+                mrr: 1.0,
+                ndcg: 1.0
+            }
+        );
+
+        let second = Metrics::score(&[false, true, false], 1, 10);
+        assert_eq!((second.recall, second.mrr), (1.0, 0.5));
+        assert!((second.ndcg - 1.0 / 3f64.log2()).abs() < 1e-9);
+
+        // Hits beyond k do not count
+        let missed = Metrics::score(&[false, false, true], 2, 2);
+        assert_eq!(missed, Metrics::default());
+
+        let half = Metrics::score(&[true, false], 2, 10);
+        assert_eq!((half.recall, half.mrr), (0.5, 1.0));
+        assert!(half.ndcg > 0.5 && half.ndcg < 1.0);
+    }
+
+    #[test]
+    fn test_load_queries() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        let jsonl = dir.path().join("queries.jsonl");
+        std::fs::write(
+            &jsonl,
+            "{\"query\": \"brew tea\", \"expected\": [\"Green Tea\"]}\n\n{\"query\": \"gpu\", \"expected\": [\"Notes/GPU.md\"]}\n",
+        )?;
+        let queries = load_queries(&jsonl)?;
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[1].expected, vec!["Notes/GPU.md"]);
+
+        let yaml = dir.path().join("queries.yaml");
+        std::fs::write(&yaml, "- query: brew tea\n  expected: [Green Tea]\n")?;
+        assert_eq!(load_queries(&yaml)?[0].query, "brew tea");
+
+        std::fs::write(&yaml, "- query: brew tea\n  expected: []\n")?;
+        assert!(load_queries(&yaml).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate_fixture() -> Result<()> {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/retrieval");
+        let queries = load_queries(&root.join("queries.yaml"))?;
+        let mut engine = SearchEngine::new_in_memory(&root)?;
+        engine.index_all()?;
+
+        let modes = [SearchMode::Vector, SearchMode::Keyword, SearchMode::Hybrid];
+        let reports = evaluate(&mut engine, &queries, &modes, 10)?;
+        assert_eq!(reports.len(), 3);
+        for report in &reports {
+            assert_eq!(report.queries.len(), queries.len());
+            assert!(
+                report.metrics.recall > 0.5,
+                "{} recall too low",
+                report.mode
+            );
+        }
+        assert!(evaluate(&mut engine, &queries, &modes, 0).is_err());
+        Ok(())
+    }
+}
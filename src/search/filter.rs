@@ -0,0 +1,212 @@
+//! Metadata filters for search
+//!
+//! The same filter runs as SQL against the index (before any similarity is
+//! computed) and in Rust against loaded notes for the index-less fallback.
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use rusqlite::types::Value;
+
+use crate::core::note::Note;
+
+/// A note tag equal to `?`, or nested below it (`work` matches `work/meetings`)
+const TAG_MATCH_SQL: &str = "EXISTS (SELECT 1 FROM json_each(n.tags) \
+     WHERE lower(json_each.value) = ? OR substr(lower(json_each.value), 1, length(?) + 1) = ? || '/')";
+
+/// A note path equal to `?`, or inside the folder `?`
+const PATH_MATCH_SQL: &str = "(n.path = ? OR substr(n.path, 1, length(?) + 1) = ? || '/')";
+
+/// Constraints on note metadata; empty fields match everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilter {
+    pub note_type: Option<String>,
+    pub status: Option<String>,
+    pub area: Option<String>,
+    /// Notes must carry every one of these tags
+    pub all_tags: Vec<String>,
+    /// Notes must carry at least one of these tags
+    pub any_tags: Vec<String>,
+    /// Folder or file path, relative to the vault root (e.g. `Projects/`);
+    /// matched whole path components at a time, so `Projects` does not
+    /// match `ProjectsOld/`
+    pub path_prefix: Option<String>,
+    /// Unix timestamps bounding the note's modification time
+    pub modified_after: Option<i64>,
+    pub modified_before: Option<i64>,
+}
+
+impl SearchFilter {
+    /// Copy with the path prefix made absolute under `root`, the form paths
+    /// are stored in
+    pub fn resolve(&self, root: &Path) -> Self {
+        let mut filter = self.clone();
+        if let Some(prefix) = &self.path_prefix {
+            if !Path::new(prefix).is_absolute() {
+                filter.path_prefix = Some(format!(
+                    "{}/{}",
+                    root.to_string_lossy().trim_end_matches('/'),
+                    prefix.trim_start_matches("./")
+                ));
+            }
+        }
+        filter
+    }
+
+    /// SQL condition over the `notes` table aliased `n`, with its positional
+    /// (`?`) parameters
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut clauses = vec!["1 = 1".to_string()];
+        let mut params = Vec::new();
+
+        for (column, value) in [
+            ("n.note_type", &self.note_type),
+            ("n.status", &self.status),
+            ("n.area", &self.area),
+        ] {
+            if let Some(value) = value {
+                clauses.push(format!("lower({}) = lower(?)", column));
+                params.push(Value::Text(value.clone()));
+            }
+        }
+
+        let mut tag_match = |tag: &str| {
+            let tag = normalize_tag(tag);
+            params.extend(std::iter::repeat_n(Value::Text(tag), 3));
+            TAG_MATCH_SQL.to_string()
+        };
+        let all: Vec<String> = self.all_tags.iter().map(|t| tag_match(t)).collect();
+        let any: Vec<String> = self.any_tags.iter().map(|t| tag_match(t)).collect();
+        clauses.extend(all);
+        if !any.is_empty() {
+            clauses.push(format!("({})", any.join(" OR ")));
+        }
+
+        if let Some(prefix) = &self.path_prefix {
+            clauses.push(PATH_MATCH_SQL.to_string());
+            let prefix = prefix.trim_end_matches('/');
+            params.extend(std::iter::repeat_n(Value::Text(prefix.to_string()), 3));
+        }
+        if let Some(after) = self.modified_after {
+            clauses.push("n.mtime >= ?".to_string());
+            params.push(Value::Integer(after));
+        }
+        if let Some(before) = self.modified_before {
+            clauses.push("n.mtime < ?".to_string());
+            params.push(Value::Integer(before));
+        }
+
+        (clauses.join(" AND "), params)
+    }
+
+    /// Whether a loaded note passes the filter; expects a resolved path prefix
+    pub fn matches(&self, note: &Note) -> bool {
+        let eq = |expected: &Option<String>, actual: Option<&str>| {
+            expected
+                .as_ref()
+                .is_none_or(|e| actual.is_some_and(|a| a.eq_ignore_ascii_case(e)))
+        };
+        let tags: Vec<String> = note.tags().iter().map(|t| normalize_tag(t)).collect();
+        let has_tag = |tag: &String| {
+            let tag = normalize_tag(tag);
+            tags.iter()
+                .any(|t| *t == tag || t.starts_with(&format!("{}/", tag)))
+        };
+        let mtime = note.modified.timestamp();
+
+        eq(&self.note_type, note.note_type())
+            && eq(&self.status, note.status())
+            && eq(&self.area, note.area())
+            && self.all_tags.iter().all(has_tag)
+            && (self.any_tags.is_empty() || self.any_tags.iter().any(has_tag))
+            && self.path_prefix.as_ref().is_none_or(|p| {
+                let path = note.path.to_string_lossy();
+                let prefix = p.trim_end_matches('/');
+                path == prefix || path.starts_with(&format!("{}/", prefix))
+            })
+            && self.modified_after.is_none_or(|t| mtime >= t)
+            && self.modified_before.is_none_or(|t| mtime < t)
+    }
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim_start_matches('#').to_lowercase()
+}
+
+/// Parse `YYYY-MM-DD` (local midnight) or an RFC 3339 timestamp into Unix seconds
+pub fn parse_time(value: &str) -> Result<i64> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+        if let Some(time) = Local.from_local_datetime(&midnight).earliest() {
+            return Ok(time.timestamp());
+        }
+    }
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => Ok(time.timestamp()),
+        Err(_) => bail!("Invalid date '{}' (expected YYYY-MM-DD or RFC 3339)", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_filter_matches_notes() {
+        let note = Note {
+            path: PathBuf::from("/vault/Projects/Alpha.md"),
+            name: "Alpha".to_string(),
+            content: String::new(),
+            frontmatter: crate::core::frontmatter::Frontmatter::parse(
+                "---\ntype: project\nstatus: active\narea: work\ntags: [Work/Meetings, rust]\n---\n",
+            )
+            .unwrap(),
+            frontmatter_error: None,
+            modified: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+            created: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+        };
+
+        let filter = SearchFilter {
+            note_type: Some("Project".to_string()),
+            area: Some("work".to_string()),
+            all_tags: vec!["#work".to_string()],
+            any_tags: vec!["go".to_string(), "rust".to_string()],
+            path_prefix: Some("Projects/".to_string()),
+            modified_after: Some(1_600_000_000),
+            ..Default::default()
+        }
+        .resolve(Path::new("/vault"));
+        assert!(filter.matches(&note));
+
+        let archived = SearchFilter {
+            status: Some("done".to_string()),
+            ..Default::default()
+        };
+        assert!(!archived.matches(&note));
+        let partial_tag = SearchFilter {
+            all_tags: vec!["wor".to_string()],
+            ..Default::default()
+        };
+        assert!(!partial_tag.matches(&note));
+
+        for (prefix, expected) in [
+            ("Projects", true),
+            ("Projects/Alpha.md", true),
+            ("Project", false),
+            ("Projects/Al", false),
+        ] {
+            let filter = SearchFilter {
+                path_prefix: Some(prefix.to_string()),
+                ..Default::default()
+            }
+            .resolve(Path::new("/vault"));
+            assert_eq!(filter.matches(&note), expected, "{}", prefix);
+        }
+
+        assert!(parse_time("2024-01-01").is_ok());
+        assert_eq!(parse_time("2024-01-01T00:00:00Z").unwrap(), 1_704_067_200);
+        assert!(parse_time("January").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Hashed n-gram embeddings
+//!
+//! Word unigrams and character trigrams are hashed into a fixed number of
+//! buckets with a sign bit (the "hashing trick"). Cheap, deterministic and
+//! robust to typos and inflections thanks to the character n-grams.
+
+use anyhow::Result;
+
+use super::embedder::Embedder;
+
+/// Model id recorded in the index for hashed n-gram embeddings
+pub const HASHED_MODEL_ID: &str = "hashed-ngram-v1";
+
+const WORD_WEIGHT: f32 = 1.0;
+const TRIGRAM_WEIGHT: f32 = 0.5;
+
+pub struct HashedEmbedder {
+    dimension: usize,
+}
+
+impl HashedEmbedder {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension: dimension.max(1),
+        }
+    }
+
+    fn add_feature(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = fnv1a(feature.as_bytes());
+        let bucket = (hash % self.dimension as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[bucket] += sign * weight;
+    }
+}
+
+impl Embedder for HashedEmbedder {
+    fn name(&self) -> &str {
+        HASHED_MODEL_ID
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut vector = vec![0.0f32; self.dimension];
+
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+        {
+            let word = word.to_lowercase();
+            self.add_feature(&mut vector, &format!("w:{}", word), WORD_WEIGHT);
+
+            let padded: Vec<char> = format!(" {} ", word).chars().collect();
+            for trigram in padded.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                self.add_feature(&mut vector, &format!("c:{}", trigram), TRIGRAM_WEIGHT);
+            }
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        Ok(vector)
+    }
+}
+
+/// 64-bit FNV-1a; stable across platforms and Rust versions, unlike `DefaultHasher`
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::embedding::cosine_similarity;
+
+    #[test]
+    fn test_hashed_similarity() {
+        let embedder = HashedEmbedder::new(256);
+        let a = embedder.embed("Kubernetes cluster networking").unwrap();
+        let b = embedder.embed("kubernetes clusters network").unwrap();
+        let c = embedder.embed("sourdough bread recipe").unwrap();
+
+        assert_eq!(a.len(), 256);
+        assert_eq!(a, embedder.embed("Kubernetes cluster networking").unwrap());
+        assert!(cosine_similarity(&a, &b) > cosine_similarity(&a, &c));
+    }
+}
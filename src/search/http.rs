@@ -0,0 +1,172 @@
+//! Embeddings from an OpenAI-compatible `/embeddings` endpoint
+//!
+//! Works with OpenAI itself and with local servers that speak the same API
+//! (llama.cpp, Ollama, text-embeddings-inference, LM Studio, ...).
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::embedder::{openai_model_id, Embedder};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct HttpEmbedder {
+    id: String,
+    endpoint: String,
+    model: String,
+    dimension: usize,
+    api_key: Option<String>,
+    agent: ureq::Agent,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+impl HttpEmbedder {
+    /// `url` is the API base (e.g. `http://localhost:8080/v1`) or the full
+    /// `/embeddings` endpoint
+    pub fn new(url: &str, model: &str, dimension: usize, api_key: Option<String>) -> Self {
+        let url = url.trim_end_matches('/');
+        let endpoint = if url.ends_with("/embeddings") {
+            url.to_string()
+        } else {
+            format!("{}/embeddings", url)
+        };
+
+        Self {
+            id: openai_model_id(model),
+            endpoint,
+            model: model.to_string(),
+            dimension,
+            api_key,
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+        }
+    }
+}
+
+impl Embedder for HttpEmbedder {
+    fn name(&self) -> &str {
+        &self.id
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_batch(&[text])?
+            .pop()
+            .context("Embedding endpoint returned no data")
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut request = self.agent.post(&self.endpoint);
+        if let Some(key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", key));
+        }
+        let response: EmbeddingResponse = request
+            .send_json(serde_json::json!({
+                "model": self.model,
+                "input": texts,
+            }))
+            .with_context(|| format!("Embedding request to {} failed", self.endpoint))?
+            .into_json()
+            .context("Invalid embedding response")?;
+
+        let mut data = response.data;
+        if data.len() != texts.len() {
+            bail!(
+                "Embedding endpoint returned {} vectors for {} inputs",
+                data.len(),
+                texts.len()
+            );
+        }
+        data.sort_by_key(|d| d.index);
+
+        for d in &data {
+            if d.embedding.len() != self.dimension {
+                bail!(
+                    "Embedding endpoint returned dimension {} but {} is configured",
+                    d.embedding.len(),
+                    self.dimension
+                );
+            }
+        }
+
+        Ok(data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve one `/v1/embeddings` request with a fixed response
+    fn stub_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            assert!(request_line.starts_with("POST /v1/embeddings"));
+
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["model"], "stub");
+
+            // Out of order on purpose; `index` decides placement
+            let response = r#"{"data":[{"index":1,"embedding":[0.0,1.0,0.0]},{"index":0,"embedding":[1.0,0.0,0.0]}]}"#;
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        });
+
+        format!("http://{}/v1", addr)
+    }
+
+    #[test]
+    fn test_local_stub_endpoint() {
+        let embedder = HttpEmbedder::new(&stub_server(), "stub", 3, None);
+        assert_eq!(embedder.name(), "openai:stub");
+
+        let vectors = embedder.embed_batch(&["a", "b"]).unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0]]);
+    }
+}
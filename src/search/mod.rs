@@ -1,13 +1,27 @@
 //! Semantic Search Engine for Second Brain
 //!
-//! Phase 1: Vector search using gist embeddings
-//! Phase 2: + BM25 hybrid search (future)
+//! Phase 1: Vector search using gist and body chunk embeddings
+//! Phase 2: + BM25 keyword search (FTS5); hybrid ranking (future)
 //! Phase 3: + Knowledge graph (future)
 
+pub mod ann;
+pub mod chunker;
+pub mod corpus;
+pub mod embedder;
 pub mod embedding;
 pub mod engine;
+pub mod eval;
+pub mod filter;
+pub mod hashed;
+#[cfg(feature = "openai")]
+pub mod http;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+pub mod quantize;
+pub mod tokenizer;
 pub mod vectordb;
 
+pub use embedder::{create_embedder, Embedder, EmbedderStatus};
 pub use embedding::EmbeddingModel;
 pub use engine::{SearchEngine, SearchResult};
 pub use vectordb::VectorDB;
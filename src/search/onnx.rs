@@ -0,0 +1,155 @@
+//! Local sentence-transformer embeddings through ONNX Runtime
+//!
+//! Only built with `--features onnx`. ONNX Runtime itself is loaded at run
+//! time (set `ORT_DYLIB_PATH` if it is not on the library path), so no
+//! binaries are downloaded at build time.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Mutex;
+
+use ort::session::{Session, SessionInputValue};
+use ort::value::Tensor;
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+use super::embedder::Embedder;
+
+/// Mean-pooled, L2-normalized sentence embeddings from a transformer model
+pub struct OnnxEmbedder {
+    id: String,
+    dimension: usize,
+    session: Mutex<Session>,
+    tokenizer: Tokenizer,
+    uses_token_type_ids: bool,
+}
+
+impl OnnxEmbedder {
+    /// Load `model` (`model.onnx`) and its `tokenizer.json`
+    pub fn load(
+        id: String,
+        model: &Path,
+        tokenizer: &Path,
+        dimension: usize,
+        max_length: usize,
+    ) -> Result<Self> {
+        let session = Session::builder()?
+            .commit_from_file(model)
+            .with_context(|| format!("Failed to load ONNX model {}", model.display()))?;
+        let uses_token_type_ids = session.inputs.iter().any(|i| i.name == "token_type_ids");
+
+        let mut tokenizer = Tokenizer::from_file(tokenizer)
+            .map_err(|e| anyhow!("Failed to load tokenizer {}: {}", tokenizer.display(), e))?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length,
+                ..Default::default()
+            }))
+            .map_err(|e| anyhow!("Invalid truncation settings: {}", e))?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+
+        Ok(Self {
+            id,
+            dimension,
+            session: Mutex::new(session),
+            tokenizer,
+            uses_token_type_ids,
+        })
+    }
+}
+
+impl Embedder for OnnxEmbedder {
+    fn name(&self) -> &str {
+        &self.id
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_batch(&[text])?
+            .pop()
+            .ok_or_else(|| anyhow!("ONNX model returned no embedding"))
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| anyhow!("Tokenization failed: {}", e))?;
+        let batch = encodings.len();
+        let seq_len = encodings[0].get_ids().len();
+
+        let mut ids = Vec::with_capacity(batch * seq_len);
+        let mut mask = Vec::with_capacity(batch * seq_len);
+        let mut type_ids = Vec::with_capacity(batch * seq_len);
+        for encoding in &encodings {
+            ids.extend(encoding.get_ids().iter().map(|&x| x as i64));
+            mask.extend(encoding.get_attention_mask().iter().map(|&x| x as i64));
+            type_ids.extend(encoding.get_type_ids().iter().map(|&x| x as i64));
+        }
+
+        let shape = [batch, seq_len];
+        let mut inputs: Vec<(Cow<str>, SessionInputValue)> = vec![
+            ("input_ids".into(), Tensor::from_array((shape, ids))?.into()),
+            (
+                "attention_mask".into(),
+                Tensor::from_array((shape, mask.clone()))?.into(),
+            ),
+        ];
+        if self.uses_token_type_ids {
+            inputs.push((
+                "token_type_ids".into(),
+                Tensor::from_array((shape, type_ids))?.into(),
+            ));
+        }
+
+        let mut session = self
+            .session
+            .lock()
+            .map_err(|_| anyhow!("ONNX session lock poisoned"))?;
+        let outputs = session.run(inputs)?;
+        let (out_shape, hidden) = outputs[0].try_extract_tensor::<f32>()?;
+
+        // last_hidden_state: [batch, seq_len, dimension]
+        if out_shape.len() != 3 || out_shape[2] as usize != self.dimension {
+            bail!(
+                "ONNX model output shape {:?} does not match configured dimension {}",
+                &out_shape[..],
+                self.dimension
+            );
+        }
+
+        let dim = self.dimension;
+        let mut embeddings = Vec::with_capacity(batch);
+        for b in 0..batch {
+            let mut pooled = vec![0.0f32; dim];
+            let mut count = 0.0f32;
+            for t in 0..seq_len {
+                if mask[b * seq_len + t] == 0 {
+                    continue;
+                }
+                let offset = (b * seq_len + t) * dim;
+                for (p, h) in pooled.iter_mut().zip(&hidden[offset..offset + dim]) {
+                    *p += h;
+                }
+                count += 1.0;
+            }
+            if count > 0.0 {
+                pooled.iter_mut().for_each(|p| *p /= count);
+            }
+            let norm = pooled.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                pooled.iter_mut().for_each(|p| *p /= norm);
+            }
+            embeddings.push(pooled);
+        }
+
+        Ok(embeddings)
+    }
+}
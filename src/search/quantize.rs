@@ -0,0 +1,227 @@
+//! Compact storage formats for embeddings
+//!
+//! Vectors are stored as raw f32, or normalized and reduced to int8 codes
+//! with a scale or to sign bits. Quantized BLOBs carry a 4-byte header, so
+//! they can be decoded without knowing the embedder's dimension. Scores computed from
+//! quantized vectors are approximate; the engine re-scores the top
+//! candidates by re-embedding them at full precision, so only the codes are
+//! kept.
+
+use crate::core::config::Quantization;
+use crate::search::embedding::cosine_similarity;
+
+/// Encode an embedding for storage
+pub fn encode(quantization: Quantization, embedding: &[f32]) -> Vec<u8> {
+    match quantization {
+        Quantization::None => embedding.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        Quantization::Int8 => {
+            let (scale, codes) = int8_codes(&normalize(embedding));
+            let mut blob = Vec::with_capacity(4 + codes.len());
+            blob.extend_from_slice(&scale.to_le_bytes());
+            blob.extend(codes.iter().map(|&c| c as u8));
+            blob
+        }
+        Quantization::Binary => {
+            let mut blob = Vec::with_capacity(4 + embedding.len().div_ceil(8));
+            blob.extend_from_slice(&(embedding.len() as u32).to_le_bytes());
+            blob.extend(sign_bits(embedding));
+            blob
+        }
+    }
+}
+
+/// Decode a stored embedding back to (approximate) f32 values
+pub fn decode(quantization: Quantization, blob: &[u8]) -> Vec<f32> {
+    match quantization {
+        Quantization::None => blob
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect(),
+        Quantization::Int8 => {
+            let Some((scale, codes)) = split_header(blob) else {
+                return Vec::new();
+            };
+            let scale = f32::from_le_bytes(scale);
+            codes.iter().map(|&c| c as i8 as f32 * scale).collect()
+        }
+        Quantization::Binary => {
+            let Some((dimension, bits)) = split_header(blob) else {
+                return Vec::new();
+            };
+            let dimension = u32::from_le_bytes(dimension) as usize;
+            let magnitude = 1.0 / (dimension.max(1) as f32).sqrt();
+            (0..dimension.min(bits.len() * 8))
+                .map(|i| {
+                    if bits[i / 8] & (1 << (i % 8)) != 0 {
+                        magnitude
+                    } else {
+                        -magnitude
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/// Length of an encoded `dimension`-dimensional embedding
+pub fn encoded_len(quantization: Quantization, dimension: usize) -> usize {
+    match quantization {
+        Quantization::None => 4 * dimension,
+        Quantization::Int8 => 4 + dimension,
+        Quantization::Binary => 4 + dimension.div_ceil(8),
+    }
+}
+
+/// Similarity of two encoded embeddings, computed on the codes
+///
+/// Quantized codes estimate the cosine similarity; raw f32 BLOBs give the
+/// dot product, which is the cosine for unit-length vectors.
+pub fn code_similarity(quantization: Quantization, a: &[u8], b: &[u8]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    match quantization {
+        Quantization::None => a
+            .chunks_exact(4)
+            .zip(b.chunks_exact(4))
+            .map(|(x, y)| {
+                f32::from_le_bytes([x[0], x[1], x[2], x[3]])
+                    * f32::from_le_bytes([y[0], y[1], y[2], y[3]])
+            })
+            .sum(),
+        Quantization::Int8 => {
+            let (Some((scale_a, a)), Some((scale_b, b))) = (split_header(a), split_header(b))
+            else {
+                return 0.0;
+            };
+            let dot: i32 = a
+                .iter()
+                .zip(b)
+                .map(|(&x, &y)| x as i8 as i32 * y as i8 as i32)
+                .sum();
+            dot as f32 * f32::from_le_bytes(scale_a) * f32::from_le_bytes(scale_b)
+        }
+        Quantization::Binary => {
+            let (Some((dimension, a)), Some((_, b))) = (split_header(a), split_header(b)) else {
+                return 0.0;
+            };
+            let dimension = u32::from_le_bytes(dimension) as usize;
+            if dimension == 0 {
+                return 0.0;
+            }
+            // Hamming distance approximates the angle between the vectors
+            let differing: u32 = a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum();
+            1.0 - 2.0 * differing as f32 / dimension as f32
+        }
+    }
+}
+
+/// A query prepared for scoring against stored embeddings
+pub struct QueryScorer {
+    quantization: Quantization,
+    query: Vec<f32>,
+    /// The query encoded like the stored embeddings, when quantized
+    code: Vec<u8>,
+}
+
+impl QueryScorer {
+    pub fn new(quantization: Quantization, query: &[f32]) -> Self {
+        let code = match quantization {
+            Quantization::None => Vec::new(),
+            _ => encode(quantization, query),
+        };
+        Self {
+            quantization,
+            query: query.to_vec(),
+            code,
+        }
+    }
+
+    /// Cosine similarity to a stored embedding, estimated from its codes
+    /// when quantized
+    pub fn similarity(&self, blob: &[u8]) -> f32 {
+        match self.quantization {
+            Quantization::None => cosine_similarity(&self.query, &decode(Quantization::None, blob)),
+            quantization => code_similarity(quantization, &self.code, blob),
+        }
+    }
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter().map(|x| x / norm).collect()
+    } else {
+        vector.to_vec()
+    }
+}
+
+/// Symmetric int8 codes and the scale that maps them back
+fn int8_codes(vector: &[f32]) -> (f32, Vec<i8>) {
+    let max = vector.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+    let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+    let codes = vector
+        .iter()
+        .map(|v| (v / scale).round().clamp(-127.0, 127.0) as i8)
+        .collect();
+    (scale, codes)
+}
+
+/// One bit per dimension, set where the value is positive
+fn sign_bits(vector: &[f32]) -> Vec<u8> {
+    let mut bits = vec![0u8; vector.len().div_ceil(8)];
+    for (i, v) in vector.iter().enumerate() {
+        if *v > 0.0 {
+            bits[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bits
+}
+
+/// A 4-byte header and the bytes after it
+fn split_header(blob: &[u8]) -> Option<([u8; 4], &[u8])> {
+    let (header, rest) = blob.split_first_chunk::<4>()?;
+    Some((*header, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantized_similarity() {
+        let a: Vec<f32> = (0..64)
+            .map(|i| ((i * 37 % 23) as f32 - 11.0) / 7.0)
+            .collect();
+        let b: Vec<f32> = (0..64)
+            .map(|i| ((i * 29 % 19) as f32 - 9.0) / 5.0)
+            .collect();
+        let exact = cosine_similarity(&a, &b);
+
+        let none = encode(Quantization::None, &a);
+        assert_eq!(none.len(), 64 * 4);
+        assert_eq!(decode(Quantization::None, &none), a);
+
+        let int8 = encode(Quantization::Int8, &a);
+        assert_eq!(int8.len(), 4 + 64);
+        let scorer = QueryScorer::new(Quantization::Int8, &b);
+        assert!((scorer.similarity(&int8) - exact).abs() < 0.02);
+        let decoded = decode(Quantization::Int8, &int8);
+        assert!(cosine_similarity(&decoded, &a) > 0.999);
+
+        let binary = encode(Quantization::Binary, &a);
+        assert_eq!(binary.len(), 4 + 8);
+        let scorer = QueryScorer::new(Quantization::Binary, &a);
+        assert_eq!(scorer.similarity(&binary), 1.0);
+        let scorer = QueryScorer::new(Quantization::Binary, &b);
+        assert!((scorer.similarity(&binary) - exact).abs() < 0.5);
+        assert_eq!(decode(Quantization::Binary, &binary).len(), 64);
+
+        for quantization in [Quantization::None, Quantization::Int8, Quantization::Binary] {
+            assert_eq!(
+                encode(quantization, &a).len(),
+                encoded_len(quantization, 64)
+            );
+        }
+    }
+}
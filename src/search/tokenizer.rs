@@ -0,0 +1,523 @@
+//! Text tokenization for HTP embeddings
+//!
+//! Text is split on every non-alphanumeric character and wherever the
+//! script changes, so "GPU메모리" yields a Latin and a Hangul token. Chinese
+//! and Japanese runs become character n-grams, since those scripts do not
+//! separate words with spaces. Hangul runs are kept whole, split into
+//! syllable bigrams or decomposed into jamo trigrams, so an inflected form
+//! ("메모리를") shares most of its tokens with the stem ("메모리"). Other
+//! words may drop English stopwords and be stemmed.
+
+use crate::core::config::{HangulMode, TokenizerConfig};
+
+/// Bumped whenever tokenization changes, so indexes built with an older
+/// tokenizer are rebuilt
+const TOKENIZER_VERSION: u32 = 1;
+
+/// Common English function words
+const STOPWORDS: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "am",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "by",
+    "can",
+    "did",
+    "do",
+    "does",
+    "doing",
+    "down",
+    "during",
+    "each",
+    "few",
+    "for",
+    "from",
+    "further",
+    "had",
+    "has",
+    "have",
+    "having",
+    "he",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i",
+    "if",
+    "in",
+    "into",
+    "is",
+    "it",
+    "its",
+    "itself",
+    "just",
+    "me",
+    "more",
+    "most",
+    "my",
+    "myself",
+    "no",
+    "nor",
+    "not",
+    "now",
+    "of",
+    "off",
+    "on",
+    "once",
+    "only",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "same",
+    "she",
+    "should",
+    "so",
+    "some",
+    "such",
+    "than",
+    "that",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "to",
+    "too",
+    "under",
+    "until",
+    "up",
+    "very",
+    "was",
+    "we",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "would",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    /// Han ideographs and kana
+    Cjk,
+    Hangul,
+    Other,
+}
+
+fn script(c: char) -> Script {
+    match c as u32 {
+        0xAC00..=0xD7A3 | 0x1100..=0x11FF | 0x3130..=0x318F => Script::Hangul,
+        0x3040..=0x30FF
+        | 0x31F0..=0x31FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xF900..=0xFAFF
+        | 0x20000..=0x2FA1F => Script::Cjk,
+        _ => Script::Other,
+    }
+}
+
+/// Splits text into the tokens HTP embeds
+pub struct Tokenizer {
+    config: TokenizerConfig,
+}
+
+impl Tokenizer {
+    pub fn new(config: TokenizerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Tokenizer version and options, recorded as part of the model id
+    pub fn id(&self) -> String {
+        format!(
+            "tok{}-cjk{}-{}{}{}",
+            TOKENIZER_VERSION,
+            self.config.cjk_ngram,
+            self.config.hangul.as_str(),
+            if self.config.stopwords { "-stop" } else { "" },
+            if self.config.stemming { "-stem" } else { "" }
+        )
+    }
+
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        for word in text.split(|c: char| !c.is_alphanumeric()) {
+            let word = word.to_lowercase();
+            for (script, run) in script_runs(&word) {
+                match script {
+                    Script::Cjk => tokens.extend(ngrams(&run, self.config.cjk_ngram)),
+                    Script::Hangul => match self.config.hangul {
+                        HangulMode::Word => tokens.push(run.iter().collect()),
+                        HangulMode::Bigram => tokens.extend(ngrams(&run, 2)),
+                        HangulMode::Jamo => tokens.extend(ngrams(&decompose_hangul(&run), 3)),
+                    },
+                    Script::Other => {
+                        let run: String = run.iter().collect();
+                        if self.config.stopwords && STOPWORDS.binary_search(&run.as_str()).is_ok() {
+                            continue;
+                        }
+                        tokens.push(if self.config.stemming {
+                            stem(&run)
+                        } else {
+                            run
+                        });
+                    }
+                }
+            }
+        }
+        tokens
+    }
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self::new(TokenizerConfig::default())
+    }
+}
+
+/// Maximal runs of characters in the same script
+fn script_runs(word: &str) -> Vec<(Script, Vec<char>)> {
+    let mut runs: Vec<(Script, Vec<char>)> = Vec::new();
+    for c in word.chars() {
+        let script = script(c);
+        match runs.last_mut() {
+            Some((last, run)) if *last == script => run.push(c),
+            _ => runs.push((script, vec![c])),
+        }
+    }
+    runs
+}
+
+/// Overlapping n-grams, or the run itself when it is not longer than `n`
+fn ngrams(run: &[char], n: usize) -> Vec<String> {
+    if run.len() <= n.max(1) {
+        return vec![run.iter().collect()];
+    }
+    run.windows(n.max(1)).map(|w| w.iter().collect()).collect()
+}
+
+/// Decompose precomposed Hangul syllables into conjoining jamo
+fn decompose_hangul(run: &[char]) -> Vec<char> {
+    let mut jamo = Vec::with_capacity(run.len() * 3);
+    for &c in run {
+        let code = c as u32;
+        if !(0xAC00..=0xD7A3).contains(&code) {
+            jamo.push(c);
+            continue;
+        }
+        let index = code - 0xAC00;
+        let lead = 0x1100 + index / 588;
+        let vowel = 0x1161 + (index % 588) / 28;
+        let tail = index % 28;
+        jamo.extend([lead, vowel].into_iter().filter_map(char::from_u32));
+        if tail > 0 {
+            jamo.extend(char::from_u32(0x11A7 + tail));
+        }
+    }
+    jamo
+}
+
+/// Porter stem of a lowercase ASCII word; other words are returned as is
+pub fn stem(word: &str) -> String {
+    if word.len() <= 2 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return word.to_string();
+    }
+    let mut stemmer = Stemmer {
+        b: word.as_bytes().to_vec(),
+    };
+    stemmer.step1ab();
+    stemmer.step1c();
+    stemmer.step2();
+    stemmer.step3();
+    stemmer.step4();
+    stemmer.step5();
+    String::from_utf8(stemmer.b).unwrap_or_else(|_| word.to_string())
+}
+
+/// The Porter (1980) suffix-stripping algorithm
+struct Stemmer {
+    b: Vec<u8>,
+}
+
+impl Stemmer {
+    fn is_consonant(&self, i: usize) -> bool {
+        match self.b[i] {
+            b'a' | b'e' | b'i' | b'o' | b'u' => false,
+            b'y' => i == 0 || !self.is_consonant(i - 1),
+            _ => true,
+        }
+    }
+
+    /// Number of vowel-consonant sequences in the first `len` letters
+    fn measure(&self, len: usize) -> usize {
+        let mut m = 0;
+        let mut i = 0;
+        while i < len && self.is_consonant(i) {
+            i += 1;
+        }
+        loop {
+            while i < len && !self.is_consonant(i) {
+                i += 1;
+            }
+            if i >= len {
+                return m;
+            }
+            while i < len && self.is_consonant(i) {
+                i += 1;
+            }
+            m += 1;
+        }
+    }
+
+    fn has_vowel(&self, len: usize) -> bool {
+        (0..len).any(|i| !self.is_consonant(i))
+    }
+
+    fn ends_double_consonant(&self, len: usize) -> bool {
+        len >= 2 && self.b[len - 1] == self.b[len - 2] && self.is_consonant(len - 1)
+    }
+
+    /// Ends consonant-vowel-consonant, the last not w, x or y
+    fn ends_cvc(&self, len: usize) -> bool {
+        len >= 3
+            && self.is_consonant(len - 1)
+            && !self.is_consonant(len - 2)
+            && self.is_consonant(len - 3)
+            && !matches!(self.b[len - 1], b'w' | b'x' | b'y')
+    }
+
+    /// Length of the stem if the word ends with `suffix`
+    fn stem_len(&self, suffix: &str) -> Option<usize> {
+        self.b
+            .ends_with(suffix.as_bytes())
+            .then(|| self.b.len() - suffix.len())
+    }
+
+    fn replace(&mut self, stem: usize, with: &str) {
+        self.b.truncate(stem);
+        self.b.extend_from_slice(with.as_bytes());
+    }
+
+    /// Replace the first matching suffix when the stem's measure exceeds `min_measure`
+    fn replace_suffix(&mut self, rules: &[(&str, &str)], min_measure: usize) {
+        for (suffix, with) in rules {
+            if let Some(stem) = self.stem_len(suffix) {
+                if self.measure(stem) > min_measure {
+                    self.replace(stem, with);
+                }
+                return;
+            }
+        }
+    }
+
+    fn step1ab(&mut self) {
+        if self.b.ends_with(b"sses") || self.b.ends_with(b"ies") {
+            self.b.truncate(self.b.len() - 2);
+        } else if self.b.ends_with(b"s") && !self.b.ends_with(b"ss") {
+            self.b.pop();
+        }
+
+        if let Some(stem) = self.stem_len("eed") {
+            if self.measure(stem) > 0 {
+                self.b.pop();
+            }
+            return;
+        }
+        let stem = match self.stem_len("ed").or_else(|| self.stem_len("ing")) {
+            Some(stem) if self.has_vowel(stem) => stem,
+            _ => return,
+        };
+        self.b.truncate(stem);
+        if self.b.ends_with(b"at") || self.b.ends_with(b"bl") || self.b.ends_with(b"iz") {
+            self.b.push(b'e');
+        } else if self.ends_double_consonant(stem)
+            && !matches!(self.b[stem - 1], b'l' | b's' | b'z')
+        {
+            self.b.pop();
+        } else if self.measure(stem) == 1 && self.ends_cvc(stem) {
+            self.b.push(b'e');
+        }
+    }
+
+    fn step1c(&mut self) {
+        if let Some(stem) = self.stem_len("y") {
+            if self.has_vowel(stem) {
+                self.b[stem] = b'i';
+            }
+        }
+    }
+
+    fn step2(&mut self) {
+        self.replace_suffix(
+            &[
+                ("ational", "ate"),
+                ("tional", "tion"),
+                ("enci", "ence"),
+                ("anci", "ance"),
+                ("izer", "ize"),
+                ("bli", "ble"),
+                ("alli", "al"),
+                ("entli", "ent"),
+                ("eli", "e"),
+                ("ousli", "ous"),
+                ("ization", "ize"),
+                ("ation", "ate"),
+                ("ator", "ate"),
+                ("alism", "al"),
+                ("iveness", "ive"),
+                ("fulness", "ful"),
+                ("ousness", "ous"),
+                ("aliti", "al"),
+                ("iviti", "ive"),
+                ("biliti", "ble"),
+                ("logi", "log"),
+            ],
+            0,
+        );
+    }
+
+    fn step3(&mut self) {
+        self.replace_suffix(
+            &[
+                ("icate", "ic"),
+                ("ative", ""),
+                ("alize", "al"),
+                ("iciti", "ic"),
+                ("ical", "ic"),
+                ("ful", ""),
+                ("ness", ""),
+            ],
+            0,
+        );
+    }
+
+    fn step4(&mut self) {
+        const SUFFIXES: &[&str] = &[
+            "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ion",
+            "ou", "ism", "ate", "iti", "ous", "ive", "ize",
+        ];
+        // Longest match first, so "ement" wins over "ment" and "ent"
+        let Some(stem) = SUFFIXES
+            .iter()
+            .filter_map(|suffix| self.stem_len(suffix))
+            .min()
+        else {
+            return;
+        };
+        let is_ion = self.b[stem..] == *b"ion";
+        if self.measure(stem) > 1
+            && (!is_ion || (stem > 0 && matches!(self.b[stem - 1], b's' | b't')))
+        {
+            self.b.truncate(stem);
+        }
+    }
+
+    fn step5(&mut self) {
+        if let Some(stem) = self.stem_len("e") {
+            let m = self.measure(stem);
+            if m > 1 || (m == 1 && !self.ends_cvc(stem)) {
+                self.b.pop();
+            }
+        }
+        let len = self.b.len();
+        if self.measure(len) > 1 && self.ends_double_consonant(len) && self.b[len - 1] == b'l' {
+            self.b.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_scripts() {
+        let tokenizer = Tokenizer::default();
+        assert_eq!(
+            tokenizer.tokenize("The GPU메모리를 sharing"),
+            vec!["gpu", "메모", "모리", "리를", "share"]
+        );
+        assert_eq!(tokenizer.tokenize("東京大学"), vec!["東京", "京大", "大学"]);
+
+        let jamo = Tokenizer::new(TokenizerConfig {
+            hangul: HangulMode::Jamo,
+            stopwords: false,
+            stemming: false,
+            ..TokenizerConfig::default()
+        });
+        let stem = jamo.tokenize("메모리");
+        let inflected = jamo.tokenize("메모리를");
+        assert!(stem.iter().all(|t| inflected.contains(t)));
+        assert_eq!(jamo.tokenize("The")[0], "the");
+        assert_ne!(jamo.id(), tokenizer.id());
+    }
+
+    #[test]
+    fn test_porter_stem() {
+        assert!(STOPWORDS.windows(2).all(|w| w[0] < w[1]));
+        for (word, expected) in [
+            ("caresses", "caress"),
+            ("ponies", "poni"),
+            ("agreed", "agre"),
+            ("hopping", "hop"),
+            ("filing", "file"),
+            ("happy", "happi"),
+            ("relational", "relat"),
+            ("generalization", "gener"),
+            ("hopefulness", "hope"),
+            ("adjustment", "adjust"),
+            ("controlling", "control"),
+            ("embeddings", "embed"),
+            ("gpu", "gpu"),
+        ] {
+            assert_eq!(stem(word), expected, "stem of {}", word);
+        }
+    }
+}
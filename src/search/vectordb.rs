@@ -2,16 +2,53 @@
 //!
 //! Stores embeddings as BLOBs and computes similarity in Rust.
 //! Can be upgraded to sqlite-vec for native vector operations later.
+//! An FTS5 table over title, gist, tags and body provides BM25 keyword search.
+//! Large indexes also keep an HNSW graph in a sidecar file for approximate
+//! vector search; the exact scan remains the fallback.
 
-use anyhow::{Context, Result};
-use rusqlite::{params, Connection, OptionalExtension};
-use std::path::Path;
+use anyhow::{bail, Result};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use std::cell::{Cell, RefCell, RefMut};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use super::embedding::{cosine_similarity, EMBEDDING_DIM};
+use super::ann::{HnswIndex, VectorKey, EF_SEARCH};
+use super::chunker::Chunk;
+use super::corpus::CorpusStats;
+use super::filter::SearchFilter;
+use super::quantize::{decode, encode, QueryScorer};
+use crate::core::config::Quantization;
+
+/// Indexes with fewer vectors are scanned exactly; below this size the
+/// graph costs more to maintain than it saves
+pub const ANN_MIN_VECTORS: usize = 5_000;
+/// Candidates fetched from the graph per requested note, since a note has
+/// several vectors and the filter may drop some
+const ANN_OVERFETCH: usize = 8;
+/// Compact the graph once this share of its nodes are tombstones
+const ANN_MAX_TOMBSTONES: f32 = 0.25;
+const META_ANN_STAMP: &str = "ann_stamp";
+const META_QUANTIZATION: &str = "quantization";
+const META_CORPUS_DOCUMENTS: &str = "corpus_documents";
 
 /// Vector database for note embeddings
 pub struct VectorDB {
     conn: Connection,
+    /// Sidecar file of the ANN index; `None` for in-memory databases
+    ann_path: Option<PathBuf>,
+    ann: RefCell<AnnCache>,
+    /// How embeddings are stored, as recorded in `index_meta`
+    quantization: Cell<Quantization>,
+}
+
+/// The ANN index, loaded on first use
+#[derive(Default)]
+struct AnnCache {
+    loaded: bool,
+    index: Option<HnswIndex>,
+    /// The database changed since the sidecar file was written
+    dirty: bool,
 }
 
 /// Note metadata stored alongside embeddings
@@ -28,11 +65,39 @@ pub struct NoteRecord {
     pub mtime: i64,
 }
 
+/// The chunk of a note that best matched a query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Passage {
+    pub text: String,
+    /// Headings enclosing the passage, outermost first
+    pub heading_path: Vec<String>,
+    /// Byte offsets of the passage in the note content
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A note, its similarity to the query and its best-matching passage
+pub type SearchHit = (NoteRecord, f32, Option<Passage>);
+
+/// Where a note was indexed from and the content it was indexed with
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedNote {
+    pub path: String,
+    pub mtime: i64,
+    /// `None` for rows written before content hashes were recorded
+    pub content_hash: Option<String>,
+}
+
 impl VectorDB {
     /// Open or create database at path
     pub fn open(db_path: &Path) -> Result<Self> {
         let conn = Connection::open(db_path)?;
-        let db = Self { conn };
+        let db = Self {
+            conn,
+            ann_path: Some(Self::ann_path(db_path)),
+            ann: RefCell::default(),
+            quantization: Cell::default(),
+        };
         db.init_schema()?;
         Ok(db)
     }
@@ -40,7 +105,12 @@ impl VectorDB {
     /// Open in-memory database (for testing)
     pub fn open_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
-        let db = Self { conn };
+        let db = Self {
+            conn,
+            ann_path: None,
+            ann: RefCell::default(),
+            quantization: Cell::default(),
+        };
         db.init_schema()?;
         Ok(db)
     }
@@ -49,6 +119,8 @@ impl VectorDB {
     fn init_schema(&self) -> Result<()> {
         self.conn.execute_batch(
             r#"
+            PRAGMA foreign_keys = ON;
+
             -- Notes metadata
             CREATE TABLE IF NOT EXISTS notes (
                 id TEXT PRIMARY KEY,
@@ -60,7 +132,8 @@ impl VectorDB {
                 area TEXT,
                 tags TEXT,  -- JSON array
                 mtime INTEGER NOT NULL,
-                indexed_at INTEGER NOT NULL
+                indexed_at INTEGER NOT NULL,
+                content_hash TEXT
             );
 
             -- Embeddings (stored as BLOB for now, can migrate to sqlite-vec later)
@@ -70,6 +143,25 @@ impl VectorDB {
                 FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
             );
 
+            -- Body passages and their embeddings
+            CREATE TABLE IF NOT EXISTS chunks (
+                note_id TEXT NOT NULL,
+                idx INTEGER NOT NULL,
+                start INTEGER NOT NULL,
+                end INTEGER NOT NULL,
+                heading_path TEXT NOT NULL,  -- JSON array
+                text TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                PRIMARY KEY (note_id, idx),
+                FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+            );
+
+            -- Document frequencies for IDF pooling
+            CREATE TABLE IF NOT EXISTS corpus_terms (
+                token TEXT PRIMARY KEY,
+                df INTEGER NOT NULL
+            );
+
             -- Index metadata
             CREATE TABLE IF NOT EXISTS index_meta (
                 key TEXT PRIMARY KEY,
@@ -81,22 +173,69 @@ impl VectorDB {
             CREATE INDEX IF NOT EXISTS idx_notes_type ON notes(note_type);
             CREATE INDEX IF NOT EXISTS idx_notes_area ON notes(area);
             CREATE INDEX IF NOT EXISTS idx_notes_mtime ON notes(mtime);
+
+            -- Keyword index, kept in step with notes
+            CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+                note_id UNINDEXED,
+                title,
+                gist,
+                tags,
+                body,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON notes BEGIN
+                DELETE FROM notes_fts WHERE note_id = old.id;
+            END;
             "#,
         )?;
 
+        // Indexes created before incremental indexing lack the hash column
+        let has_hash: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('notes') WHERE name = 'content_hash'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_hash {
+            self.conn
+                .execute("ALTER TABLE notes ADD COLUMN content_hash TEXT", [])?;
+        }
+
+        if let Some(name) = self.get_meta(META_QUANTIZATION)? {
+            match Quantization::from_name(&name) {
+                Some(quantization) => self.quantization.set(quantization),
+                None => bail!("Index uses unknown quantization '{}'", name),
+            }
+        }
+
         Ok(())
     }
 
-    /// Insert or update note with embedding
-    pub fn upsert_note(&self, note: &NoteRecord, embedding: &[f32]) -> Result<()> {
+    /// Insert or update a note with its keyword index entry, its gist
+    /// embedding (if it has a gist) and the embeddings of its body chunks,
+    /// replacing any previous chunks
+    ///
+    /// A stale row for another note at the same path (e.g. left behind by a
+    /// rename) is replaced.
+    pub fn upsert_note(
+        &self,
+        note: &NoteRecord,
+        body: &str,
+        content_hash: &str,
+        gist_embedding: Option<&[f32]>,
+        chunks: &[(Chunk, Vec<f32>)],
+    ) -> Result<()> {
         let tags_json = serde_json::to_string(&note.tags)?;
-        let embedding_blob = embedding_to_blob(embedding);
         let now = chrono::Utc::now().timestamp();
 
-        self.conn.execute(
+        // All or nothing, so a failed write never leaves a note without its
+        // keyword entry or chunks
+        let tx = self.conn.unchecked_transaction()?;
+        self.delete_stale(&note.id, &note.path)?;
+
+        tx.execute(
             r#"
-            INSERT INTO notes (id, path, title, gist, note_type, status, area, tags, mtime, indexed_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            INSERT INTO notes (id, path, title, gist, note_type, status, area, tags, mtime, indexed_at, content_hash)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
             ON CONFLICT(id) DO UPDATE SET
                 path = excluded.path,
                 title = excluded.title,
@@ -106,7 +245,8 @@ impl VectorDB {
                 area = excluded.area,
                 tags = excluded.tags,
                 mtime = excluded.mtime,
-                indexed_at = excluded.indexed_at
+                indexed_at = excluded.indexed_at,
+                content_hash = excluded.content_hash
             "#,
             params![
                 note.id,
@@ -119,18 +259,82 @@ impl VectorDB {
                 tags_json,
                 note.mtime,
                 now,
+                content_hash,
             ],
         )?;
 
-        self.conn.execute(
-            r#"
-            INSERT INTO embeddings (note_id, embedding)
-            VALUES (?1, ?2)
-            ON CONFLICT(note_id) DO UPDATE SET embedding = excluded.embedding
-            "#,
-            params![note.id, embedding_blob],
+        tx.execute("DELETE FROM notes_fts WHERE note_id = ?1", params![note.id])?;
+        tx.execute(
+            "INSERT INTO notes_fts (note_id, title, gist, tags, body) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                note.id,
+                note.title,
+                note.gist.as_deref().unwrap_or(""),
+                note.tags.join(" "),
+                body,
+            ],
         )?;
 
+        match gist_embedding {
+            Some(embedding) => {
+                tx.execute(
+                    r#"
+                    INSERT INTO embeddings (note_id, embedding)
+                    VALUES (?1, ?2)
+                    ON CONFLICT(note_id) DO UPDATE SET embedding = excluded.embedding
+                    "#,
+                    params![note.id, encode(self.quantization(), embedding)],
+                )?;
+            }
+            None => {
+                tx.execute(
+                    "DELETE FROM embeddings WHERE note_id = ?1",
+                    params![note.id],
+                )?;
+            }
+        }
+
+        tx.execute("DELETE FROM chunks WHERE note_id = ?1", params![note.id])?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO chunks (note_id, idx, start, end, heading_path, text, embedding) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for (idx, (chunk, embedding)) in chunks.iter().enumerate() {
+                insert.execute(params![
+                    note.id,
+                    idx,
+                    chunk.start,
+                    chunk.end,
+                    serde_json::to_string(&chunk.heading_path)?,
+                    chunk.text,
+                    encode(self.quantization(), embedding),
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+
+        let mut ann = self.ann_mut()?;
+        if let Some(index) = ann.index.as_mut() {
+            index.remove_note(&note.id);
+            let gist = gist_embedding.map(|embedding| (None, embedding));
+            let chunks = chunks
+                .iter()
+                .enumerate()
+                .map(|(idx, (_, embedding))| (Some(idx), embedding.as_slice()));
+            for (chunk, embedding) in gist.into_iter().chain(chunks) {
+                let key = VectorKey {
+                    note_id: note.id.clone(),
+                    chunk,
+                };
+                if index.insert(key, embedding).is_err() {
+                    // Dimension changed under the graph; rebuild it on save
+                    ann.index = None;
+                    break;
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -138,9 +342,89 @@ impl VectorDB {
     pub fn delete_note(&self, id: &str) -> Result<()> {
         self.conn
             .execute("DELETE FROM notes WHERE id = ?1", params![id])?;
+        if let Some(index) = self.ann_mut()?.index.as_mut() {
+            index.remove_note(id);
+        }
+        Ok(())
+    }
+
+    /// Delete a row for another note at `path`, e.g. left behind by a rename
+    fn delete_stale(&self, id: &str, path: &str) -> Result<()> {
+        let stale: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT id FROM notes WHERE path = ?1 AND id != ?2",
+                params![path, id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(stale) = stale {
+            self.delete_note(&stale)?;
+        }
         Ok(())
     }
 
+    /// Record that an unchanged note moved or was touched, keeping its embedding
+    pub fn update_location(&self, id: &str, path: &str, mtime: i64) -> Result<()> {
+        self.delete_stale(id, path)?;
+        self.conn.execute(
+            "UPDATE notes SET path = ?2, mtime = ?3 WHERE id = ?1",
+            params![id, path, mtime],
+        )?;
+        Ok(())
+    }
+
+    /// Remove every note, embedding and corpus statistic, keeping index metadata
+    pub fn clear(&self) -> Result<()> {
+        self.conn
+            .execute_batch(
+                "DELETE FROM chunks; DELETE FROM embeddings; DELETE FROM notes; DELETE FROM notes_fts; DELETE FROM corpus_terms;",
+            )?;
+        self.conn.execute(
+            "DELETE FROM index_meta WHERE key = ?1",
+            params![META_CORPUS_DOCUMENTS],
+        )?;
+        self.ann_mut()?.index = None;
+        Ok(())
+    }
+
+    /// Replace the stored corpus statistics
+    pub fn save_corpus_stats(&self, stats: &CorpusStats) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM corpus_terms", [])?;
+        {
+            let mut insert = tx.prepare("INSERT INTO corpus_terms (token, df) VALUES (?1, ?2)")?;
+            for (token, df) in &stats.document_frequency {
+                insert.execute(params![token, df])?;
+            }
+        }
+        tx.commit()?;
+        self.set_meta(META_CORPUS_DOCUMENTS, &stats.documents.to_string())
+    }
+
+    /// Number of documents the stored corpus statistics cover, if any are stored
+    pub fn corpus_documents(&self) -> Result<Option<usize>> {
+        Ok(match self.get_meta(META_CORPUS_DOCUMENTS)? {
+            Some(documents) => Some(documents.parse()?),
+            None => None,
+        })
+    }
+
+    /// The stored corpus statistics, if any
+    pub fn load_corpus_stats(&self) -> Result<Option<CorpusStats>> {
+        let Some(documents) = self.corpus_documents()? else {
+            return Ok(None);
+        };
+        let mut stmt = self.conn.prepare("SELECT token, df FROM corpus_terms")?;
+        let document_frequency = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(Some(CorpusStats {
+            documents,
+            document_frequency,
+        }))
+    }
+
     /// Get note by ID
     pub fn get_note(&self, id: &str) -> Result<Option<NoteRecord>> {
         let result = self
@@ -148,21 +432,7 @@ impl VectorDB {
             .query_row(
                 "SELECT id, path, title, gist, note_type, status, area, tags, mtime FROM notes WHERE id = ?1",
                 params![id],
-                |row| {
-                    let tags_json: String = row.get(7)?;
-                    let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-                    Ok(NoteRecord {
-                        id: row.get(0)?,
-                        path: row.get(1)?,
-                        title: row.get(2)?,
-                        gist: row.get(3)?,
-                        note_type: row.get(4)?,
-                        status: row.get(5)?,
-                        area: row.get(6)?,
-                        tags,
-                        mtime: row.get(8)?,
-                    })
-                },
+                read_note,
             )
             .optional()?;
 
@@ -170,56 +440,374 @@ impl VectorDB {
     }
 
     /// Search for similar notes using cosine similarity
-    pub fn search(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<(NoteRecord, f32)>> {
-        // Load all embeddings and compute similarity in Rust
-        // This is O(n) but fine for < 10,000 notes
-        // Can be optimized with HNSW index or sqlite-vec later
+    ///
+    /// A note scores as its best match among its gist and its body chunks;
+    /// the best-matching chunk is returned as the note's passage. Uses the
+    /// ANN index when there is one, falling back to the exact scan when
+    /// there is not or when too few of its candidates pass `filter`.
+    ///
+    /// Scores from a quantized index are estimated from the codes.
+    pub fn search(
+        &self,
+        query_embedding: &[f32],
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        match self.search_ann(query_embedding, filter, limit)? {
+            Some(results) => Ok(results),
+            None => self.search_exact(query_embedding, filter, limit),
+        }
+    }
 
-        let mut stmt = self.conn.prepare(
-            r#"
-            SELECT n.id, n.path, n.title, n.gist, n.note_type, n.status, n.area, n.tags, n.mtime, e.embedding
-            FROM notes n
-            JOIN embeddings e ON n.id = e.note_id
-            "#,
-        )?;
+    /// [`VectorDB::search`] by computing the similarity of every stored
+    /// embedding
+    pub fn search_exact(
+        &self,
+        query_embedding: &[f32],
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let scorer = QueryScorer::new(self.quantization(), query_embedding);
+        let mut scores: HashMap<String, f32> = HashMap::new();
+        let mut passages: HashMap<String, (f32, Passage)> = HashMap::new();
 
-        let rows = stmt.query_map([], |row| {
-            let tags_json: String = row.get(7)?;
-            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-            let embedding_blob: Vec<u8> = row.get(9)?;
+        // Metadata filters narrow the candidates before any similarity is computed
+        let (condition, filter_params) = filter.to_sql();
 
-            Ok((
-                NoteRecord {
-                    id: row.get(0)?,
-                    path: row.get(1)?,
-                    title: row.get(2)?,
-                    gist: row.get(3)?,
-                    note_type: row.get(4)?,
-                    status: row.get(5)?,
-                    area: row.get(6)?,
-                    tags,
-                    mtime: row.get(8)?,
-                },
-                embedding_blob,
-            ))
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT e.note_id, e.embedding FROM embeddings e \
+             JOIN notes n ON n.id = e.note_id WHERE {}",
+            condition
+        ))?;
+        let rows = stmt.query_map(params_from_iter(&filter_params), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
         })?;
+        for row in rows {
+            let (id, blob) = row?;
+            let similarity = scorer.similarity(&blob);
+            scores.insert(id, similarity);
+        }
 
-        let mut results: Vec<(NoteRecord, f32)> = Vec::new();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT c.note_id, c.start, c.end, c.heading_path, c.text, c.embedding FROM chunks c \
+             JOIN notes n ON n.id = c.note_id WHERE {}",
+            condition
+        ))?;
+        let mut rows = stmt.query(params_from_iter(&filter_params))?;
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(0)?;
+            let blob: Vec<u8> = row.get(5)?;
+            let similarity = scorer.similarity(&blob);
 
-        for row_result in rows {
-            let (note, embedding_blob) = row_result?;
-            let embedding = blob_to_embedding(&embedding_blob);
-            let similarity = cosine_similarity(query_embedding, &embedding);
-            results.push((note, similarity));
+            let score = scores.entry(id.clone()).or_insert(similarity);
+            *score = score.max(similarity);
+            if passages
+                .get(&id)
+                .is_some_and(|(best, _)| *best >= similarity)
+            {
+                continue;
+            }
+            let heading_path: String = row.get(3)?;
+            let passage = Passage {
+                text: row.get(4)?,
+                heading_path: serde_json::from_str(&heading_path).unwrap_or_default(),
+                start: row.get(1)?,
+                end: row.get(2)?,
+            };
+            passages.insert(id, (similarity, passage));
         }
 
+        let mut ranked: Vec<(String, f32)> = scores.into_iter().collect();
         // Sort by similarity descending
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        results.truncate(limit);
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ranked.truncate(limit);
+
+        let mut results = Vec::with_capacity(ranked.len());
+        for (id, score) in ranked {
+            if let Some(note) = self.get_note(&id)? {
+                let passage = passages.remove(&id).map(|(_, passage)| passage);
+                results.push((note, score, passage));
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// BM25-ranked keyword search over title, gist, tags and body
+    ///
+    /// Every word of `query` is matched as a prefix and any word may match;
+    /// notes matching more (and rarer) words rank higher, with title hits
+    /// weighted above gist, tags and body hits. `fields` restricts matching
+    /// to some of `title`, `gist`, `tags` and `body`. Each hit carries a
+    /// snippet with matches wrapped in `**`.
+    pub fn keyword_search(
+        &self,
+        query: &str,
+        fields: Option<&[&str]>,
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<(NoteRecord, f32, String)>> {
+        let Some(fts_query) = fts_query(query, fields) else {
+            return Ok(Vec::new());
+        };
 
+        let (condition, filter_params) = filter.to_sql();
+        let mut stmt = self.conn.prepare(&format!(
+            r#"
+            SELECT n.id, n.path, n.title, n.gist, n.note_type, n.status, n.area, n.tags, n.mtime,
+                   bm25(notes_fts, 0.0, 10.0, 5.0, 3.0, 1.0) AS rank,
+                   snippet(notes_fts, -1, '**', '**', '...', 16)
+            FROM notes_fts
+            JOIN notes n ON n.id = notes_fts.note_id
+            WHERE notes_fts MATCH ? AND {}
+            ORDER BY rank
+            LIMIT ?
+            "#,
+            condition
+        ))?;
+
+        let mut values = vec![Value::Text(fts_query)];
+        values.extend(filter_params);
+        values.push(Value::Integer(i64::try_from(limit).unwrap_or(-1)));
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            // bm25() is lower-is-better; flip it so higher scores rank first
+            let rank: f64 = row.get(9)?;
+            Ok((read_note(row)?, -rank as f32, row.get(10)?))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
         Ok(results)
     }
 
+    /// Approximate search through the HNSW graph
+    ///
+    /// Returns `None` when there is no usable graph or when fewer than
+    /// `limit` notes among its candidates pass `filter`.
+    fn search_ann(
+        &self,
+        query_embedding: &[f32],
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Option<Vec<SearchHit>>> {
+        let hits = {
+            let ann = self.ann()?;
+            let Some(index) = ann.index.as_ref() else {
+                return Ok(None);
+            };
+            if index.dimension() != query_embedding.len() {
+                return Ok(None);
+            }
+            let k = limit.saturating_mul(ANN_OVERFETCH).max(EF_SEARCH);
+            index.search(query_embedding, k, k)?
+        };
+
+        // Hits are best first, so a note's first hit is its score and its
+        // first chunk hit is its passage
+        let mut order: Vec<(String, f32)> = Vec::new();
+        let mut best_chunk: HashMap<String, usize> = HashMap::new();
+        let mut seen = HashSet::new();
+        for (key, similarity) in hits {
+            if seen.insert(key.note_id.clone()) {
+                order.push((key.note_id.clone(), similarity));
+            }
+            if let Some(chunk) = key.chunk {
+                best_chunk.entry(key.note_id).or_insert(chunk);
+            }
+        }
+
+        let (condition, filter_params) = filter.to_sql();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT n.id, n.path, n.title, n.gist, n.note_type, n.status, n.area, n.tags, n.mtime \
+             FROM notes n WHERE n.id = ? AND {}",
+            condition
+        ))?;
+        let mut results = Vec::with_capacity(limit);
+        for (id, score) in order {
+            if results.len() == limit {
+                break;
+            }
+            let mut values = vec![Value::Text(id.clone())];
+            values.extend(filter_params.iter().cloned());
+            let Some(note) = stmt
+                .query_row(params_from_iter(values), read_note)
+                .optional()?
+            else {
+                continue;
+            };
+            let passage = match best_chunk.get(&id) {
+                Some(&idx) => self.get_passage(&id, idx)?,
+                None => None,
+            };
+            results.push((note, score, passage));
+        }
+
+        Ok((results.len() == limit).then_some(results))
+    }
+
+    fn get_passage(&self, note_id: &str, idx: usize) -> Result<Option<Passage>> {
+        let passage = self
+            .conn
+            .query_row(
+                "SELECT start, end, heading_path, text FROM chunks WHERE note_id = ?1 AND idx = ?2",
+                params![note_id, idx],
+                |row| {
+                    let heading_path: String = row.get(2)?;
+                    Ok(Passage {
+                        text: row.get(3)?,
+                        heading_path: serde_json::from_str(&heading_path).unwrap_or_default(),
+                        start: row.get(0)?,
+                        end: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(passage)
+    }
+
+    /// Sidecar file holding the ANN index of the database at `db_path`
+    pub fn ann_path(db_path: &Path) -> PathBuf {
+        db_path.with_extension("hnsw")
+    }
+
+    /// Number of vectors in the ANN index, if there is a usable one
+    pub fn ann_len(&self) -> Result<Option<usize>> {
+        Ok(self.ann()?.index.as_ref().map(HnswIndex::len))
+    }
+
+    /// Bring the ANN index in line with the stored embeddings and save it
+    ///
+    /// The index is built once there are `ANN_MIN_VECTORS` vectors and
+    /// dropped below that; it is rebuilt when it has drifted from the
+    /// database or carries too many tombstones.
+    pub fn save_ann(&self) -> Result<()> {
+        let stats = self.get_stats()?;
+        if stats.embedding_count + stats.chunk_count < ANN_MIN_VECTORS {
+            let mut ann = self.ann_mut()?;
+            ann.index = None;
+            ann.dirty = false;
+            if let Some(path) = &self.ann_path {
+                if path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+            }
+            return Ok(());
+        }
+
+        let (stale, dirty) = {
+            let ann = self.ann()?;
+            let stale = match ann.index.as_ref() {
+                Some(index) => {
+                    index.len() != stats.embedding_count + stats.chunk_count
+                        || index.tombstone_ratio() > ANN_MAX_TOMBSTONES
+                }
+                None => true,
+            };
+            (stale, ann.dirty)
+        };
+        if stale {
+            self.rebuild_ann()
+        } else if dirty {
+            self.write_ann()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Build the ANN index from every stored embedding and save it,
+    /// regardless of the index size
+    ///
+    /// A quantized graph is built on the stored codes, so it is as compact
+    /// as they are.
+    pub fn rebuild_ann(&self) -> Result<()> {
+        let quantization = self.quantization();
+        let mut index: Option<HnswIndex> = None;
+        let mut stmt = self.conn.prepare(
+            "SELECT note_id, NULL, embedding FROM embeddings \
+             UNION ALL SELECT note_id, idx, embedding FROM chunks",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(2)?;
+            let index = index.get_or_insert_with(|| {
+                HnswIndex::new(decode(quantization, &blob).len(), quantization)
+            });
+            let key = VectorKey {
+                note_id: row.get(0)?,
+                chunk: row.get::<_, Option<i64>>(1)?.map(|idx| idx as usize),
+            };
+            index.insert_code(key, blob)?;
+        }
+
+        {
+            let mut ann = self.ann_mut()?;
+            ann.index = index;
+        }
+        self.write_ann()
+    }
+
+    /// Write the ANN index next to the database and stamp both with the
+    /// same value, so a stale file is never loaded
+    fn write_ann(&self) -> Result<()> {
+        let mut ann = self.ann_mut()?;
+        if let (Some(path), Some(index)) = (&self.ann_path, &ann.index) {
+            let stamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_nanos() as u64;
+            index.save(path, stamp)?;
+            self.set_meta(META_ANN_STAMP, &stamp.to_string())?;
+        }
+        ann.dirty = false;
+        Ok(())
+    }
+
+    /// The ANN cache, loading the sidecar file on first use
+    fn ann(&self) -> Result<RefMut<'_, AnnCache>> {
+        let mut ann = self.ann.borrow_mut();
+        if !ann.loaded {
+            ann.loaded = true;
+            ann.index = self.load_ann()?;
+        }
+        Ok(ann)
+    }
+
+    /// The ANN cache for a change to the database
+    ///
+    /// The first change unstamps the sidecar file, which is stale until the
+    /// next [`VectorDB::save_ann`].
+    fn ann_mut(&self) -> Result<RefMut<'_, AnnCache>> {
+        let mut ann = self.ann()?;
+        if !ann.dirty {
+            ann.dirty = true;
+            self.conn.execute(
+                "DELETE FROM index_meta WHERE key = ?1",
+                params![META_ANN_STAMP],
+            )?;
+        }
+        Ok(ann)
+    }
+
+    fn load_ann(&self) -> Result<Option<HnswIndex>> {
+        let Some(path) = self.ann_path.as_ref().filter(|p| p.exists()) else {
+            return Ok(None);
+        };
+        let Some(expected) = self.get_meta(META_ANN_STAMP)? else {
+            return Ok(None);
+        };
+        // An unreadable file is as good as a missing one: the exact scan
+        // still works and the next save rewrites it
+        Ok(match HnswIndex::load(path) {
+            Ok((index, stamp))
+                if stamp.to_string() == expected && index.quantization() == self.quantization() =>
+            {
+                Some(index)
+            }
+            _ => None,
+        })
+    }
+
     /// Get index statistics
     pub fn get_stats(&self) -> Result<IndexStats> {
         let note_count: i64 = self
@@ -230,35 +818,62 @@ impl VectorDB {
             .conn
             .query_row("SELECT COUNT(*) FROM embeddings", [], |row| row.get(0))?;
 
+        let chunk_count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))?;
+
         let last_indexed: Option<i64> = self
             .conn
-            .query_row(
-                "SELECT MAX(indexed_at) FROM notes",
-                [],
-                |row| row.get(0),
-            )
+            .query_row("SELECT MAX(indexed_at) FROM notes", [], |row| row.get(0))
             .optional()?
             .flatten();
 
         Ok(IndexStats {
             note_count: note_count as usize,
             embedding_count: embedding_count as usize,
+            chunk_count: chunk_count as usize,
             last_indexed,
         })
     }
 
-    /// Get all note IDs with their mtimes
-    pub fn get_all_mtimes(&self) -> Result<Vec<(String, i64)>> {
-        let mut stmt = self.conn.prepare("SELECT id, mtime FROM notes")?;
-        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    /// Path, mtime and content hash of every indexed note, by ID
+    pub fn get_indexed_notes(&self) -> Result<HashMap<String, IndexedNote>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, path, mtime, content_hash FROM notes")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                IndexedNote {
+                    path: row.get(1)?,
+                    mtime: row.get(2)?,
+                    content_hash: row.get(3)?,
+                },
+            ))
+        })?;
 
-        let mut result = Vec::new();
+        let mut result = HashMap::new();
         for row in rows {
-            result.push(row?);
+            let (id, note) = row?;
+            result.insert(id, note);
         }
         Ok(result)
     }
 
+    /// How embeddings are stored
+    pub fn quantization(&self) -> Quantization {
+        self.quantization.get()
+    }
+
+    /// Store embeddings written from now on as `quantization`
+    ///
+    /// Only meaningful on an empty index: existing rows keep their format.
+    pub fn set_quantization(&self, quantization: Quantization) -> Result<()> {
+        self.set_meta(META_QUANTIZATION, quantization.as_str())?;
+        self.quantization.set(quantization);
+        Ok(())
+    }
+
     /// Set index metadata
     pub fn set_meta(&self, key: &str, value: &str) -> Result<()> {
         self.conn.execute(
@@ -285,35 +900,57 @@ impl VectorDB {
 #[derive(Debug)]
 pub struct IndexStats {
     pub note_count: usize,
+    /// Gist embeddings
     pub embedding_count: usize,
+    pub chunk_count: usize,
     pub last_indexed: Option<i64>,
 }
 
-/// Convert f32 embedding to BLOB
-fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
-    let mut blob = Vec::with_capacity(embedding.len() * 4);
-    for &val in embedding {
-        blob.extend_from_slice(&val.to_le_bytes());
+/// Turn free text into an FTS5 query: each word quoted as a prefix term,
+/// ORed together, optionally limited to `fields`
+fn fts_query(query: &str, fields: Option<&[&str]>) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{}\"*", t))
+        .collect();
+    if terms.is_empty() {
+        return None;
     }
-    blob
+
+    let terms = terms.join(" OR ");
+    Some(match fields {
+        Some(fields) => format!("{{{}}} : ({})", fields.join(" "), terms),
+        None => terms,
+    })
 }
 
-/// Convert BLOB to f32 embedding
-fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
-    blob.chunks_exact(4)
-        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
-        .collect()
+fn read_note(row: &Row) -> rusqlite::Result<NoteRecord> {
+    let tags_json: String = row.get(7)?;
+    let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+    Ok(NoteRecord {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        title: row.get(2)?,
+        gist: row.get(3)?,
+        note_type: row.get(4)?,
+        status: row.get(5)?,
+        area: row.get(6)?,
+        tags,
+        mtime: row.get(8)?,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::search::embedding::EMBEDDING_DIM;
 
     #[test]
     fn test_blob_conversion() {
         let embedding = vec![1.0, 2.0, 3.0, -0.5];
-        let blob = embedding_to_blob(&embedding);
-        let recovered = blob_to_embedding(&blob);
+        let blob = encode(Quantization::None, &embedding);
+        let recovered = decode(Quantization::None, &blob);
         assert_eq!(embedding, recovered);
     }
 
@@ -334,7 +971,7 @@ mod tests {
         };
 
         let embedding = vec![0.1; EMBEDDING_DIM];
-        db.upsert_note(&note, &embedding)?;
+        db.upsert_note(&note, "Body text", "abc", Some(&embedding), &[])?;
 
         let retrieved = db.get_note("test-note")?;
         assert!(retrieved.is_some());
@@ -344,6 +981,316 @@ mod tests {
         assert_eq!(stats.note_count, 1);
         assert_eq!(stats.embedding_count, 1);
 
+        // A different note taking over the path replaces the stale row
+        let renamed = NoteRecord {
+            id: "other-note".to_string(),
+            ..note
+        };
+        db.upsert_note(&renamed, "Body text", "def", Some(&embedding), &[])?;
+        let indexed = db.get_indexed_notes()?;
+        assert_eq!(indexed.len(), 1);
+        assert_eq!(indexed["other-note"].content_hash.as_deref(), Some("def"));
+        assert_eq!(db.get_stats()?.embedding_count, 1);
+
+        db.delete_note("other-note")?;
+        assert_eq!(db.get_stats()?.embedding_count, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunk_search() -> Result<()> {
+        let db = VectorDB::open_in_memory()?;
+        let note = |id: &str| NoteRecord {
+            id: id.to_string(),
+            path: format!("Notes/{}.md", id),
+            title: id.to_string(),
+            gist: None,
+            note_type: None,
+            status: None,
+            area: None,
+            tags: Vec::new(),
+            mtime: 0,
+        };
+        let chunk = |text: &str, start: usize| Chunk {
+            heading_path: vec!["Setup".to_string()],
+            start,
+            end: start + text.len(),
+            text: text.to_string(),
+        };
+
+        db.upsert_note(
+            &note("a"),
+            "",
+            "h",
+            Some(&[0.0, 1.0]),
+            &[
+                (chunk("first", 0), vec![0.6, 0.8]),
+                (chunk("second", 10), vec![1.0, 0.0]),
+            ],
+        )?;
+        db.upsert_note(
+            &note("b"),
+            "",
+            "h",
+            None,
+            &[(chunk("other", 0), vec![0.8, 0.6])],
+        )?;
+
+        let results = db.search(&[1.0, 0.0], &SearchFilter::default(), 10)?;
+        let ids: Vec<&str> = results.iter().map(|(n, _, _)| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        let passage = results[0].2.as_ref().unwrap();
+        assert_eq!((passage.text.as_str(), passage.start), ("second", 10));
+        assert_eq!(passage.heading_path, vec!["Setup"]);
+
+        // Re-indexing replaces the chunks
+        db.upsert_note(
+            &note("a"),
+            "",
+            "h2",
+            None,
+            &[(chunk("only", 0), vec![0.0, 1.0])],
+        )?;
+        assert_eq!(db.get_stats()?.chunk_count, 2);
+        assert_eq!(db.get_stats()?.embedding_count, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyword_search() -> Result<()> {
+        let db = VectorDB::open_in_memory()?;
+        let note = |id: &str, gist: &str, tags: &[&str]| NoteRecord {
+            id: id.to_string(),
+            path: format!("Notes/{}.md", id),
+            title: id.to_string(),
+            gist: Some(gist.to_string()),
+            note_type: None,
+            status: None,
+            area: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            mtime: 0,
+        };
+
+        db.upsert_note(
+            &note("Kubernetes", "Cluster basics", &["k8s"]),
+            "Pods and services.",
+            "h",
+            None,
+            &[],
+        )?;
+        db.upsert_note(
+            &note("Networking", "Overlay networks", &["infra"]),
+            "How kubernetes pods talk to each other.",
+            "h",
+            None,
+            &[],
+        )?;
+
+        // Title hits outrank body hits; words match as prefixes
+        let none = SearchFilter::default();
+        let hits = db.keyword_search("kube pods", None, &none, 10)?;
+        let ids: Vec<&str> = hits.iter().map(|(n, _, _)| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["Kubernetes", "Networking"]);
+        assert!(hits[0].1 > hits[1].1);
+        assert!(hits[1].2.contains("**kubernetes**"));
+
+        let gist_only = db.keyword_search("pods", Some(&["title", "gist"]), &none, 10)?;
+        assert!(gist_only.is_empty());
+        assert!(db.keyword_search("\"(*", None, &none, 10)?.is_empty());
+
+        // Deleting a note drops it from the keyword index
+        db.delete_note("Networking")?;
+        assert_eq!(db.keyword_search("pods", None, &none, 10)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filtered_search() -> Result<()> {
+        let db = VectorDB::open_in_memory()?;
+        let note = |id: &str, folder: &str, status: &str, tags: &[&str], mtime: i64| NoteRecord {
+            id: id.to_string(),
+            path: format!("/vault/{}/{}.md", folder, id),
+            title: id.to_string(),
+            gist: Some("Rust tooling".to_string()),
+            note_type: Some("project".to_string()),
+            status: Some(status.to_string()),
+            area: Some("tech".to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            mtime,
+        };
+
+        for (record, embedding) in [
+            (
+                note("a", "Projects", "active", &["rust/cli"], 100),
+                [1.0, 0.0],
+            ),
+            (note("b", "ProjectsOld", "done", &["rust"], 200), [0.9, 0.1]),
+            (note("c", "Projects", "active", &["go"], 300), [0.8, 0.2]),
+        ] {
+            db.upsert_note(&record, "", "h", Some(&embedding), &[])?;
+        }
+
+        let ids = |filter: SearchFilter| -> Result<Vec<String>> {
+            let vector = db.search(&[1.0, 0.0], &filter, 10)?;
+            let keyword = db.keyword_search("rust", None, &filter, 10)?;
+            let mut ids: Vec<String> = vector.into_iter().map(|(n, _, _)| n.id).collect();
+            let mut keyword_ids: Vec<String> = keyword.into_iter().map(|(n, _, _)| n.id).collect();
+            ids.sort();
+            keyword_ids.sort();
+            assert_eq!(ids, keyword_ids);
+            Ok(ids)
+        };
+
+        assert_eq!(ids(SearchFilter::default())?, vec!["a", "b", "c"]);
+        let active = SearchFilter {
+            status: Some("Active".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(active)?, vec!["a", "c"]);
+        let nested_tag = SearchFilter {
+            all_tags: vec!["#rust".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(ids(nested_tag)?, vec!["a", "b"]);
+        let any_tag = SearchFilter {
+            any_tags: vec!["go".to_string(), "rust/cli".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(ids(any_tag)?, vec!["a", "c"]);
+        let path_and_time = SearchFilter {
+            path_prefix: Some("/vault/Projects".to_string()),
+            modified_after: Some(150),
+            ..Default::default()
+        };
+        assert_eq!(ids(path_and_time)?, vec!["c"]);
+        let before = SearchFilter {
+            modified_before: Some(200),
+            ..Default::default()
+        };
+        assert_eq!(ids(before)?, vec!["a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantized_index_is_smaller() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let sizes = |quantization: Quantization| -> Result<(u64, u64)> {
+            let db_path = dir.path().join(format!("{}.db", quantization.as_str()));
+            let db = VectorDB::open(&db_path)?;
+            db.set_quantization(quantization)?;
+            for i in 0..200 {
+                let record = NoteRecord {
+                    id: format!("n{}", i),
+                    path: format!("/vault/n{}.md", i),
+                    title: format!("n{}", i),
+                    gist: None,
+                    note_type: None,
+                    status: None,
+                    area: None,
+                    tags: Vec::new(),
+                    mtime: 0,
+                };
+                let vector: Vec<f32> = (0..EMBEDDING_DIM)
+                    .map(|d| ((i * 31 + d * 17) % 23) as f32 - 11.0)
+                    .collect();
+                db.upsert_note(&record, "", "h", Some(&vector), &[])?;
+            }
+            db.rebuild_ann()?;
+            assert_eq!(
+                db.ann()?.index.as_ref().unwrap().quantization(),
+                quantization
+            );
+            drop(db);
+            let size = |path: &Path| std::fs::metadata(path).map(|m| m.len());
+            Ok((size(&db_path)?, size(&VectorDB::ann_path(&db_path))?))
+        };
+
+        let (db, graph) = sizes(Quantization::None)?;
+        for quantization in [Quantization::Int8, Quantization::Binary] {
+            let (quantized_db, quantized_graph) = sizes(quantization)?;
+            assert!(
+                quantized_db < db,
+                "{:?}: {} >= {}",
+                quantization,
+                quantized_db,
+                db
+            );
+            assert!(
+                quantized_graph < graph,
+                "{:?}: {} >= {}",
+                quantization,
+                quantized_graph,
+                graph
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_ann_index() -> Result<()> {
+        let note = |i: usize| NoteRecord {
+            id: format!("n{}", i),
+            path: format!("/vault/n{}.md", i),
+            title: format!("n{}", i),
+            gist: None,
+            note_type: None,
+            status: None,
+            area: None,
+            tags: Vec::new(),
+            mtime: 0,
+        };
+        let vector = |i: usize| -> Vec<f32> {
+            (0..8)
+                .map(|d| ((i * 7 + d * 13) % 17) as f32 - 8.0)
+                .collect()
+        };
+        let filter = SearchFilter::default();
+
+        let db = VectorDB::open_in_memory()?;
+        for i in 0..300 {
+            db.upsert_note(&note(i), "", "h", Some(&vector(i)), &[])?;
+        }
+        db.rebuild_ann()?;
+        assert_eq!(db.ann_len()?, Some(300));
+
+        // Approximate and exact search agree on the best match
+        for i in [3, 50, 299] {
+            let approximate = db.search(&vector(i), &filter, 1)?;
+            let exact = db.search_exact(&vector(i), &filter, 1)?;
+            assert!((approximate[0].1 - exact[0].1).abs() < 1e-5);
+        }
+
+        // The index is kept up to date on changes
+        db.delete_note("n3")?;
+        assert_eq!(db.ann_len()?, Some(299));
+        assert!(db
+            .search(&vector(3), &filter, 5)?
+            .iter()
+            .all(|(n, _, _)| n.id != "n3"));
+
+        // The saved index is reused until the database changes without it
+        let dir = tempfile::tempdir()?;
+        let db_path = dir.path().join("search.db");
+        let db = VectorDB::open(&db_path)?;
+        for i in 0..10 {
+            db.upsert_note(&note(i), "", "h", Some(&vector(i)), &[])?;
+        }
+        db.rebuild_ann()?;
+        drop(db);
+
+        let db = VectorDB::open(&db_path)?;
+        assert_eq!(db.ann_len()?, Some(10));
+        db.delete_note("n3")?;
+        drop(db);
+
+        let db = VectorDB::open(&db_path)?;
+        assert_eq!(db.ann_len()?, None);
+        assert_eq!(db.search(&vector(4), &filter, 5)?.len(), 5);
+
+        // Small indexes go without one
+        db.save_ann()?;
+        assert!(!VectorDB::ann_path(&db_path).exists());
         Ok(())
     }
 }